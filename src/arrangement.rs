@@ -0,0 +1,249 @@
+//! Where segments meet, found with a Bentley–Ottmann sweep, the subdivision
+//! of the plane they make, and two subdivisions laid over each other.
+//!
+//! The sweep goes down the plane in [`LexYX`] order, stopping at the ends of
+//! the segments and at the points where segments next to each other along
+//! the sweep line meet, so segments far apart are never tested against each
+//! other. The segments on the sweep line are kept in a sorted `Vec`, so a
+//! stop costs time linear in how many there are, which at the sizes the
+//! viewers draw beats a balanced tree.
+//!
+//! Whether segments meet is decided with the exact [`orient2d`], so
+//! touching, overlapping and shared ends are all caught. Where segments
+//! cross properly, the point is rounded.
+
+use std::{cmp::Ordering, collections::{BTreeMap, HashMap}};
+use crate::{
+    dcel::{Dcel, FaceId, VertexId},
+    geom::{segment_intersection, Segment},
+    order::{LexYX, PointOrder},
+    point::Point,
+    predicates::{on_segment, opposite_signs, orient2d},
+};
+
+/// A point where two or more segments meet, by crossing, touching,
+/// overlapping or sharing an end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Intersection {
+    pub point: Point,
+    /// Indices of the segments through the point, in increasing order.
+    pub segments: Vec<usize>,
+}
+
+/// The faces of the two subdivisions a face of their [`overlay`] is in,
+/// `None` where it's outside all their bounded faces.
+pub type Overlap = (Option<FaceId>, Option<FaceId>);
+
+/// A stop of the sweep, in [`LexYX`] order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stop(Point);
+
+impl Eq for Stop {}
+
+impl PartialOrd for Stop {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Stop {
+    fn cmp(&self, other: &Self) -> Ordering {
+        LexYX.cmp(&self.0, &other.0)
+    }
+}
+
+/// Where `s` and `t` meet: the ends of either that are on the other, which
+/// covers touching and overlapping, or else the point where they cross.
+fn meeting_points(s: Segment, t: Segment) -> Vec<Point> {
+    let ends = [(s, t[0]), (s, t[1]), (t, s[0]), (t, s[1])];
+    let mut points: Vec<Point> = ends.into_iter().filter(|&([a, b], p)| on_segment(a, b, p)).map(|(_, p)| p).collect();
+    let crosses = opposite_signs(orient2d(s[0], s[1], t[0]), orient2d(s[0], s[1], t[1]))
+        && opposite_signs(orient2d(t[0], t[1], s[0]), orient2d(t[0], t[1], s[1]));
+    if points.is_empty() && crosses {
+        points.extend(segment_intersection(s, t));
+    }
+    points
+}
+
+/// Every point where two or more of `segments` meet, in [`LexYX`] order.
+/// A segment of no length only meets those through its point.
+pub fn intersections(segments: &[Segment]) -> Vec<Intersection> {
+    // each from its first end in sweep order to its last
+    let segs: Vec<Segment> = segments.iter()
+        .map(|&[a, b]| if LexYX.cmp(&a, &b).is_gt() { [b, a] } else { [a, b] })
+        .collect();
+    let mut stops: BTreeMap<Stop, Vec<usize>> = BTreeMap::new();
+    for (i, &[upper, lower]) in segs.iter().enumerate() {
+        stops.entry(Stop(upper)).or_default().push(i);
+        stops.entry(Stop(lower)).or_default().push(i);
+    }
+
+    let mut status: Vec<usize> = vec![];
+    let mut result = vec![];
+    while let Some((Stop(p), mut through)) = stops.pop_first() {
+        // the segments an end lands on in the middle are only found here
+        through.extend(status.iter().filter(|&&s| on_segment(segs[s][0], segs[s][1], p)));
+        through.sort();
+        through.dedup();
+        if through.len() > 1 {
+            result.push(Intersection { point: p, segments: through.clone() });
+        }
+
+        // those carrying on past p go back in, left to right just below it
+        status.retain(|s| !through.contains(s));
+        let mut below: Vec<usize> = through.into_iter().filter(|&s| segs[s][1] != p).collect();
+        let direction = |s: usize| segs[s][1] - segs[s][0];
+        below.sort_by(|&s, &t| direction(s).cross(direction(t)).total_cmp(&0.0));
+        let at = status.partition_point(|&s| orient2d(segs[s][0], segs[s][1], p) < 0.0);
+
+        let mut neighbours = vec![];
+        match (below.first(), below.last()) {
+            (Some(&first), Some(&last)) => {
+                if at > 0 { neighbours.push((status[at - 1], first)); }
+                if at < status.len() { neighbours.push((last, status[at])); }
+            }
+            _ => if at > 0 && at < status.len() { neighbours.push((status[at - 1], status[at])); },
+        }
+        for (s, t) in neighbours {
+            for q in meeting_points(segs[s], segs[t]) {
+                if Stop(q) > Stop(p) {
+                    stops.entry(Stop(q)).or_default().extend([s, t]);
+                }
+            }
+        }
+        status.splice(at..at, below);
+    }
+    result
+}
+
+/// The subdivision `segments` make: a vertex wherever one ends or meets
+/// another, and an edge for every piece between those, its half-edges
+/// labelled with the index of its segment, the first where several overlap.
+pub fn arrangement<F: Clone + Default>(segments: &[Segment]) -> Dcel<Option<usize>, F> {
+    let mut on: Vec<Vec<Point>> = segments.iter().map(|s| s.to_vec()).collect();
+    for intersection in intersections(segments) {
+        for &s in &intersection.segments {
+            on[s].push(intersection.point);
+        }
+    }
+
+    let mut dcel = Dcel::new();
+    let mut vertices: HashMap<Point, VertexId> = HashMap::new();
+    for (i, (&[a, b], points)) in segments.iter().zip(on.iter_mut()).enumerate() {
+        points.sort_by(|p, q| (*p - a).dot(b - a).total_cmp(&(*q - a).dot(b - a)));
+        points.dedup();
+        let ids: Vec<VertexId> = points.iter().map(|&p| *vertices.entry(p).or_insert_with(|| dcel.add_vertex(p))).collect();
+        for pair in ids.windows(2) {
+            if pair[0] == pair[1] || dcel.find_edge(pair[0], pair[1]).is_some() { continue; }
+            let h = dcel.add_edge(pair[0], pair[1]);
+            let t = dcel.twin(h);
+            *dcel.data_mut(h) = Some(i);
+            *dcel.data_mut(t) = Some(i);
+        }
+    }
+    dcel
+}
+
+/// `a` and `b` laid over each other: the [`arrangement`] of all their
+/// edges, `a`'s in the order of [`Dcel::edges`] and then `b`'s, with every
+/// face labelled with the faces of `a` and `b` it's in.
+pub fn overlay<E1, F1, E2, F2>(a: &Dcel<E1, F1>, b: &Dcel<E2, F2>) -> Dcel<Option<usize>, Overlap> {
+    let mut segments: Vec<Segment> = a.edges().filter_map(|h| a.segment(h)).collect();
+    segments.extend(b.edges().filter_map(|h| b.segment(h)));
+    let mut result = arrangement(&segments);
+    let faces: Vec<FaceId> = result.faces().collect();
+    for f in faces {
+        if let Some(p) = result.point_in(f) {
+            *result.face_data_mut(f) = (a.locate(p), b.locate(p));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{geom::Polygon, utils};
+    use macroquad::color::{BLANK, RED};
+
+    fn segments(coords: &[[f64; 4]]) -> Vec<Segment> {
+        coords.iter().map(|&[ax, ay, bx, by]| [Point::new(ax, ay), Point::new(bx, by)]).collect()
+    }
+
+    fn square(x: f32, y: f32, side: f32) -> Dcel {
+        Dcel::from_polygon(&Polygon::rectangle(euclid::vec2(x, y), euclid::vec2(x + side, y + side), RED, BLANK))
+    }
+
+    #[test]
+    fn finds_the_same_crossings_as_testing_every_pair() {
+        let mut rng = utils::seeded_rng(9);
+        let ends = utils::random_sites(&mut rng, 160, (0.0..1000.0, 0.0..1000.0));
+        let segs: Vec<Segment> = ends.chunks(2).map(|pair| [pair[0], pair[1]]).collect();
+
+        let mut swept: Vec<(usize, usize)> = intersections(&segs).iter()
+            .inspect(|i| assert_eq!(i.segments.len(), 2, "{i:?}"))
+            .map(|i| (i.segments[0], i.segments[1]))
+            .collect();
+        swept.sort();
+        let mut every_pair = vec![];
+        for s in 0..segs.len() {
+            for t in s + 1..segs.len() {
+                if !meeting_points(segs[s], segs[t]).is_empty() {
+                    every_pair.push((s, t));
+                }
+            }
+        }
+        assert!(every_pair.len() > 100);
+        assert_eq!(swept, every_pair);
+    }
+
+    #[test]
+    fn degenerate_meetings() {
+        let segs = segments(&[
+            // four through the origin
+            [-1.0, -1.0, 1.0, 1.0],
+            [-1.0, 1.0, 1.0, -1.0],
+            [0.0, -2.0, 0.0, 2.0],
+            [-2.0, 0.0, 2.0, 0.0],
+            // one starting where another ends, and one overlapping it
+            [2.0, 3.0, 2.0, 0.0],
+            [-3.0, 0.0, -1.0, 0.0],
+        ]);
+        let found: Vec<(Point, Vec<usize>)> = intersections(&segs).into_iter().map(|i| (i.point, i.segments)).collect();
+        let expected = [((-2.0, 0.0), vec![3, 5]), ((-1.0, 0.0), vec![3, 5]), ((0.0, 0.0), vec![0, 1, 2, 3]), ((2.0, 0.0), vec![3, 4])]
+            .map(|((x, y), s)| (Point::new(x, y), s));
+        assert_eq!(found, expected);
+
+        let dcel: Dcel<Option<usize>> = arrangement(&segs);
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.vertex_count(), 12);
+        // the overlap is one edge, labelled with the first segment along it
+        assert_eq!(dcel.edge_count(), 11);
+        let overlap = dcel.edges().find(|&h| dcel.segment(h).unwrap().contains(&Point::new(-2.0, 0.0))
+            && dcel.segment(h).unwrap().contains(&Point::new(-1.0, 0.0))).unwrap();
+        assert_eq!(*dcel.data(overlap), Some(3));
+        assert_eq!(dcel.face_count(), 1);
+    }
+
+    #[test]
+    fn overlay_labels_faces_with_both_sides() {
+        let a = square(0.0, 0.0, 4.0);
+        let fa = a.bounded_faces().next().unwrap();
+        let labelled = |b: &Dcel| {
+            let fb = b.bounded_faces().next().unwrap();
+            let result = overlay(&a, b);
+            result.check_invariants().unwrap();
+            let mut faces: Vec<(i64, Overlap)> = result.faces().map(|f| (result.signed_area(f).round() as i64, *result.face_data(f))).collect();
+            faces.sort();
+            (fb, faces)
+        };
+
+        let (fb, faces) = labelled(&square(2.0, 2.0, 4.0));
+        assert_eq!(faces, vec![(-28, (None, None)), (4, (Some(fa), Some(fb))), (12, (None, Some(fb))), (12, (Some(fa), None))]);
+
+        // a square floating inside the other has a face around it that's
+        // in the outer one
+        let (fb, faces) = labelled(&square(1.0, 1.0, 1.0));
+        assert_eq!(faces, vec![(-16, (None, None)), (-1, (Some(fa), None)), (1, (Some(fa), Some(fb))), (16, (Some(fa), None))]);
+    }
+}
@@ -0,0 +1,222 @@
+//! Times the viewers' algorithms over growing inputs for their `--bench`
+//! mode, and fits how the time scales with the input size.
+//!
+//! Every size is run a few times after a warmup run. The results go to a CSV
+//! with a row per operation and size, in seconds:
+//!
+//! ```text
+//! operation,n,m,runs,mean,median,stddev,exponent
+//! recalc_convex_hull (monotone chain),1000,,5,0.00012,0.00011,0.000004,1.07
+//! ```
+//!
+//! `exponent` is the `k` of `time ~ n^k` fitted over all the sizes of the
+//! operation, so it repeats along its rows.
+//!
+//! Debug builds are several times slower and not evenly so across the
+//! algorithms, so compare timings from `cargo run --release` builds.
+
+use std::{fmt::Write as _, hint::black_box, io, path::Path, time::Instant};
+use crate::cli::Args;
+
+/// The columns of the CSV reports.
+pub const CSV_COLUMNS: &[&str] = &["operation", "n", "m", "runs", "mean", "median", "stddev", "exponent"];
+/// How often every size is run when `--runs` isn't given.
+pub const DEFAULT_RUNS: usize = 5;
+
+/// Summary of a set of timings, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub mean: f64,
+    pub median: f64,
+    /// The sample standard deviation, zero for a single sample.
+    pub stddev: f64,
+}
+
+impl Stats {
+    pub fn of(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "no samples to summarise");
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] };
+        let variance = if samples.len() > 1 {
+            samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else { 0.0 };
+        Self { mean, median, stddev: variance.sqrt() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub operation: String,
+    /// The input size the exponent is fitted over.
+    pub n: usize,
+    /// A second size where there is one, like the bots walking a grid of `n` cells.
+    pub m: Option<usize>,
+    pub runs: usize,
+    pub stats: Stats,
+}
+
+/// Fits `time ~ n^k` to `(n, seconds)` pairs by least squares on their
+/// logarithms and returns `k`. `None` without two different sizes to fit
+/// over, or with a time too short to take the logarithm of.
+pub fn fit_exponent(points: &[(usize, f64)]) -> Option<f64> {
+    if points.iter().any(|&(n, t)| n == 0 || t <= 0.0) { return None; }
+    let logs: Vec<(f64, f64)> = points.iter().map(|&(n, t)| ((n as f64).ln(), t.ln())).collect();
+    let count = logs.len() as f64;
+    let mean_x = logs.iter().map(|p| p.0).sum::<f64>() / count;
+    let mean_y = logs.iter().map(|p| p.1).sum::<f64>() / count;
+    let sxx: f64 = logs.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = logs.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    if sxx == 0.0 { return None; }
+    Some(sxy / sxx)
+}
+
+/// Runs and collects the measurements of a sweep.
+#[derive(Debug, Clone)]
+pub struct Bench {
+    runs: usize,
+    sizes: Option<Vec<usize>>,
+    measurements: Vec<Measurement>,
+}
+
+impl Bench {
+    pub fn new(runs: usize) -> Self {
+        Self { runs: runs.max(1), sizes: None, measurements: vec![] }
+    }
+
+    /// Reads `--runs` and the comma separated `--sizes` to sweep.
+    pub fn from_args(args: &Args) -> Self {
+        let mut bench = Self::new(args.parsed_option("runs").unwrap_or(DEFAULT_RUNS));
+        bench.sizes = args.option("sizes").map(|sizes| sizes.split(',').map(|size| {
+            size.trim().parse().unwrap_or_else(|_| panic!("couldn't parse --sizes ({sizes:?})"))
+        }).collect());
+        bench
+    }
+
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+
+    /// The sizes given with `--sizes`, or `default` without them.
+    pub fn sizes(&self, default: &[usize]) -> Vec<usize> {
+        self.sizes.clone().unwrap_or_else(|| default.to_vec())
+    }
+
+    /// Times `run` [`Bench::runs`] times, after one run that isn't counted
+    /// so caches and allocations settle, and records it as `operation` on
+    /// `n` items.
+    pub fn measure<R>(&mut self, operation: &str, n: usize, mut run: impl FnMut() -> R) -> &mut Measurement {
+        black_box(run());
+        let samples: Vec<f64> = (0..self.runs).map(|_| {
+            let before = Instant::now();
+            black_box(run());
+            before.elapsed().as_secs_f64()
+        }).collect();
+        self.measurements.push(Measurement {
+            operation: operation.to_string(), n, m: None, runs: self.runs, stats: Stats::of(&samples),
+        });
+        self.measurements.last_mut().unwrap()
+    }
+
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    /// The operations measured, in the order they were first measured.
+    pub fn operations(&self) -> Vec<&str> {
+        let mut operations: Vec<&str> = vec![];
+        for m in &self.measurements {
+            if !operations.contains(&m.operation.as_str()) {
+                operations.push(&m.operation);
+            }
+        }
+        operations
+    }
+
+    /// How `operation`'s median time scales with `n`, see [`fit_exponent`].
+    pub fn exponent(&self, operation: &str) -> Option<f64> {
+        let points: Vec<(usize, f64)> = self.measurements.iter()
+            .filter(|m| m.operation == operation)
+            .map(|m| (m.n, m.stats.median))
+            .collect();
+        fit_exponent(&points)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = CSV_COLUMNS.join(",") + "\n";
+        for m in &self.measurements {
+            let opt = |v: Option<String>| v.unwrap_or_default();
+            let operation = if m.operation.contains([',', '"']) {
+                format!("\"{}\"", m.operation.replace('"', "\"\""))
+            } else { m.operation.clone() };
+            writeln!(csv, "{operation},{},{},{},{},{},{},{}", m.n, opt(m.m.map(|m| m.to_string())), m.runs,
+                     m.stats.mean, m.stats.median, m.stats.stddev,
+                     opt(self.exponent(&m.operation).map(|k| k.to_string()))).unwrap();
+        }
+        csv
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_csv())
+    }
+
+    /// A table of the median times, with the fitted exponent under each operation.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for operation in self.operations() {
+            writeln!(report, "{operation}:").unwrap();
+            for m in self.measurements.iter().filter(|m| m.operation == operation) {
+                let m_col = m.m.map_or(String::new(), |m| format!(" (m = {m})"));
+                writeln!(report, "  n = {:>8}{m_col}: {:.6}s median, {:.6}s mean ± {:.6}s",
+                         m.n, m.stats.median, m.stats.mean, m.stats.stddev).unwrap();
+            }
+            match self.exponent(operation) {
+                Some(k) => writeln!(report, "  time ~ n^{k:.2}").unwrap(),
+                None => writeln!(report, "  couldn't fit the scaling").unwrap(),
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_and_fitted_exponents() {
+        let stats = Stats::of(&[4.0, 1.0, 3.0, 2.0]);
+        assert_eq!((stats.mean, stats.median), (2.5, 2.5));
+        assert!((stats.stddev - (5.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(Stats::of(&[2.0]).stddev, 0.0);
+
+        let quadratic: Vec<_> = [10, 100, 1000].iter().map(|&n| (n, 3e-9 * (n * n) as f64)).collect();
+        assert!((fit_exponent(&quadratic).unwrap() - 2.0).abs() < 1e-9);
+        assert_eq!(fit_exponent(&[(10, 1.0), (10, 2.0)]), None);
+        assert_eq!(fit_exponent(&[(10, 1.0), (100, 0.0)]), None);
+    }
+
+    #[test]
+    fn measurements_make_a_row_each() {
+        let args = Args::parse(["bench", "--runs", "3", "--sizes", "4, 8"].map(String::from), &["runs", "sizes"]);
+        let mut bench = Bench::from_args(&args);
+        assert_eq!(bench.sizes(&[1]), vec![4, 8]);
+        let mut calls = 0;
+        for n in bench.sizes(&[1]) {
+            bench.measure("sum, squared", n, || { calls += 1; (0..n).sum::<usize>().pow(2) }).m = Some(2);
+        }
+        assert_eq!(calls, 2 * (1 + 3));
+        assert_eq!(bench.operations(), vec!["sum, squared"]);
+
+        let csv = bench.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_COLUMNS.join(","));
+        assert!(lines[1].starts_with("\"sum, squared\",4,2,3,"), "{}", lines[1]);
+        assert!(lines.iter().skip(1).all(|line| line.split(',').count() == CSV_COLUMNS.len() + 1));
+        assert!(bench.report().starts_with("sum, squared:\n  n =        4 (m = 2): "));
+    }
+}
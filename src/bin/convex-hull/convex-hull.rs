@@ -1,288 +1,283 @@
-use chrono::Timelike;
 use macroquad::prelude::*;
-use genmap::GenMap;
 
 use stales_geom_viewer::{
     utils,
+    bench::Bench,
+    camera::Viewport,
+    cli::Args,
+    logging::{self, Event, FileAction, Logger, MouseAction},
     common_traits::*,
     geom::{self, *, Vertex},
+    hud::{Hud, Metrics},
+    raster::{Raster, Recorder},
+    render::{self, Renderer, Svg},
+    hull,
     point::Point,
+    scene::{Object, Scene, LayerId},
+    scene_file::{self, SceneFile},
 };
 use euclid::default::Vector2D;
 
 use std::{
-    default::Default, fmt::Debug, io::Write, iter::{self, Iterator}, time::Instant
+    default::Default, iter::Iterator, time::Instant
 };
+use ::rand::rngs::StdRng;
 
 type Color = macroquad::color::Color;
 
-enum Object {
-    Point(geom::Vertex),
-    CircleObj(geom::Circle),
-    LineObj(geom::Line2D),
-    PolyObj(geom::Polygon),
-}
-
-impl Draw for Object {
-    fn draw(&self) {
-        match self {
-            Object::Point(p) => p.draw(),
-            Object::CircleObj(c) => c.draw(),
-            Object::LineObj(l) => l.draw(),
-            Object::PolyObj(p) => p.draw(),
-        }
-    }
-    fn vertices(&self) -> Vec<geom::Vertex> {
-        match self {
-            Object::Point(p) => p.vertices(),
-            Object::CircleObj(c) => c.vertices(),
-            Object::LineObj(l) => l.vertices(),
-            Object::PolyObj(p) => p.vertices(),
-        }
-    }
-}
-
-
+/// Hull input points are keyed by the object they come from and their index within it.
+type HullKey = (genmap::Handle, usize);
 
 struct State {
-    pub objects: GenMap<Object>,
+    pub scene: Scene,
     pub clear_color: Color,
-    pub startup: Instant,
     pub prev_mouse_pos: (f32, f32),
-    pub logfile: std::fs::File,
-    pub sorted_pts: Vec<Point>,
+    pub logger: Logger,
+    pub input_pts: Vec<Point>,
+    pub input_pts_stale: bool,
     pub convex_hull_poly: genmap::Handle,
+    pub hull_layer: LayerId, // holds the hull outline, which can't be picked
+    pub dynamic_hull: hull::DynamicHull<HullKey>,
+    pub algorithm: hull::Algorithm,
+    pub hull_steps: Option<(Vec<hull::Step>, usize)>, // recorded steps and the one on display
+    pub hull_layers: Option<Vec<Polygon>>, // onion peeling outlines, outermost first
+    pub viewport: Viewport,
+    pub hud: Hud,
+    pub rng: StdRng,
 }
 
-impl Default for State {
-    fn default() -> Self {
-        use chrono;
-        let mut objects = GenMap::<Object>::with_capacity(1000);
-        let chph = objects.insert(Object::PolyObj(Polygon::default()));
-        let cur_time = chrono::Local::now();
-        let log_name = format!("./log-{}-{}-{}.txt", cur_time.hour(), cur_time.minute(), cur_time.second());
+impl State {
+    fn new(seed: u64, logger: Logger) -> Self {
+        let mut scene = Scene::with_capacity(1000);
+        let hull_layer = scene.add_layer("hull");
+        scene.layer_mut(hull_layer).pickable = false;
+        let chph = scene.insert_on(hull_layer, Object::PolyObj(Polygon::default()));
         Self {
-            objects: objects,
+            scene,
             clear_color: BLACK,
-            startup: Instant::now(),
-            prev_mouse_pos: mouse_position(),
-            logfile: std::fs::File::create(log_name).expect("can't create \"./log.txt\" log file!"),
+            prev_mouse_pos: (0.0, 0.0),
+            logger,
             convex_hull_poly: chph,
-            sorted_pts: vec![],
+            hull_layer,
+            input_pts: vec![],
+            input_pts_stale: false,
+            dynamic_hull: hull::DynamicHull::new(),
+            algorithm: hull::Algorithm::MonotoneChain,
+            hull_steps: None,
+            hull_layers: None,
+            viewport: Viewport::default(),
+            // under the hull step label
+            hud: Hud::at(vec2(20.0, 70.0)),
+            rng: utils::seeded_rng(seed),
         }
     }
 }
 
 impl State {
-    fn add_line(&mut self, l: geom::Line2D) -> genmap::Handle {
-        self.objects.insert(Object::LineObj(l))
-
-    }
-    fn add_circle(&mut self, c: geom::Circle) -> genmap::Handle {
-        self.objects.insert(Object::CircleObj(c))
+    fn object_points(&self, handle: genmap::Handle) -> Vec<(HullKey, Point)> {
+        if handle == self.convex_hull_poly { return vec![]; } // skip the convex hull points
+        match self.scene.get(handle) {
+            Some(Object::Point(v)) => vec![((handle, 0), v.point())],
+            Some(Object::CircleObj(c)) => vec![((handle, 0), c.center.point())],
+            Some(Object::LineObj(l)) => vec![((handle, 0), l.a.point()), ((handle, 1), l.b.point())],
+            Some(Object::PolyObj(p)) => p.verts.iter().enumerate().map(|(i, v)| {
+                ((handle, i), v.point())
+            }).collect(),
+            _ => vec![],
+        }
     }
-    fn add_poly(&mut self, p: geom::Polygon) -> genmap::Handle {
-        self.objects.insert(Object::PolyObj(p))
+
+    pub fn recalc_input_pts(&mut self) -> &Vec<Point> {
+        self.input_pts = self.scene.handles()
+                                   .flat_map(|x| self.object_points(x))
+                                   .map(|(_, p)| p)
+                                   .collect();
+        self.input_pts_stale = false;
+        &self.input_pts
     }
-    fn all_elements(&self) -> impl Iterator<Item = (genmap::Handle, &dyn Element)> {
-        self.objects.iter().flat_map(|x| {
-            self.objects.get(x).and_then(|obj|
-                match obj {
-                    Object::Point(_p) => None,
-                    Object::CircleObj(c) => Some(c as &dyn Element),
-                    Object::LineObj(l) => Some(l as &dyn Element),
-                    Object::PolyObj(_p) => None,
-                }).map(|elem| (x, elem))
-            })
+
+    pub fn rebuild_dynamic_hull(&mut self) {
+        let points: Vec<_> = self.scene.handles().flat_map(|x| self.object_points(x)).collect();
+        self.dynamic_hull = hull::DynamicHull::from_points(points);
+        self.refresh_hull_poly();
     }
-    fn text_digest(&self) -> String {
-        let line_cnt = self.objects
-                           .iter().flat_map(|x| self.objects.get(x))
-                           .filter(|x| matches!(x, Object::LineObj(_))).count();
-        let circle_cnt = self.objects
-                             .iter().flat_map(|x| self.objects.get(x))
-                             .filter(|x| matches!(x, Object::CircleObj(_))).count();
-        let vertex_cnt = self.objects
-                             .iter().flat_map(|x| self.objects.get(x))
-                                    .map(Draw::vertices).fold(0usize, |acc,verts| acc + verts.len());
-        let frametime = get_frame_time();
-        let convex_hull_info = self.convex_hull_report();
-        format!(r"
-num. of lines: {line_cnt}
-num. of circles: {circle_cnt}
-num. of vertices: {vertex_cnt}
-frametime: {frametime}
-hull info: {convex_hull_info}
-")
+
+    /// Feeds the points of freshly added objects to the dynamic hull.
+    pub fn hull_insert(&mut self, handles: impl IntoIterator<Item = genmap::Handle>) {
+        for handle in handles {
+            for (key, p) in self.object_points(handle) {
+                self.dynamic_hull.insert(key, p);
+            }
+        }
+        self.refresh_hull_poly();
     }
 
-    pub fn recalc_sorted_pts(&mut self) -> &Vec<Point> {
-        self.sorted_pts = self.objects.iter().flat_map(|x| {
-            self.objects.get(x).and_then(|obj| {
-                match obj {
-                    Object::CircleObj(c) => Some(vec![Point::new(c.center.pos.x as f64, c.center.pos.y as f64)]),
-                    Object::PolyObj(p) => {
-                        if x == self.convex_hull_poly { None } // skip the convex hull points
-                        else { Some(p.verts.iter().map(|v| {
-                            Point::new(v.pos.x as f64, v.pos.y as f64)
-                        }).collect()) }
-                    },
-                    _ => None,
-                }
-            })
-        }).flatten().collect();
-        self.sorted_pts.sort();
-        &self.sorted_pts
+    /// Removes an object, taking its points off the dynamic hull.
+    pub fn hull_remove(&mut self, handle: genmap::Handle) {
+        for (key, _) in self.object_points(handle) {
+            self.dynamic_hull.remove(key);
+        }
+        self.scene.remove(handle);
+        self.refresh_hull_poly();
     }
 
-    pub fn convex_hull_report(&self) -> String {
-        let convex_hull = self.objects.get(self.convex_hull_poly).unwrap();
-        if let Object::PolyObj(convex_hull) = convex_hull {
-            let points_on_hull = convex_hull.edges.len()+1;
-            let points_inside_hull = self.sorted_pts.len() - points_on_hull;
-            let hull_centroid = convex_hull.edges.iter()
-                                                 .map(|e| convex_hull.verts[e.0].pos)
-                                                 .sum::<Vector2D<f32>>()
-                                                 .component_div(Vector2D::splat(points_on_hull as f32));
-            let points_centroid = {
-                let t = self.sorted_pts
-                            .iter()
-                            .fold(Point::new(0.0,0.0), |acc, v| Point::new(acc.x() + v.x(), acc.y() + v.y()));
-                Vector2D::new(t.x() as f32 / self.sorted_pts.len() as f32, t.y() as f32 / self.sorted_pts.len() as f32)
-            };
-
-            draw_circle(hull_centroid.x, hull_centroid.y, 10.0, MAGENTA);
-            draw_circle(points_centroid.x, points_centroid.y, 10.0, RED);
-            format!("convex hull has {} points on the hull perimeter and {} points inside.
-The geometric center of the hull perimeter is {:?}, while the geometric center of all points is {:?}",
-                    points_on_hull, points_inside_hull, hull_centroid, points_centroid)
-        } else {
-            panic!();
+    fn refresh_hull_poly(&mut self) {
+        let points: Vec<Point> = self.dynamic_hull.hull().into_iter()
+                                                  .map(|key| self.dynamic_hull.point(key).unwrap())
+                                                  .collect();
+        let order: Vec<usize> = (0..points.len()).collect();
+        let mut hull_poly = hull::hull_polygon(&points, &order, WHITE);
+        for edge in hull_poly.edges.iter_mut() {
+            edge.2 = utils::random_color(&mut self.rng);
+        }
+        *self.scene.get_mut(self.convex_hull_poly).unwrap() = Object::PolyObj(hull_poly);
+        self.input_pts_stale = true;
+        if self.hull_steps.is_some() || self.hull_layers.is_some() {
+            self.recalc_input_pts();
+        }
+        if self.hull_steps.is_some() {
+            self.restart_stepping();
+        }
+        if self.hull_layers.is_some() {
+            self.recalc_hull_layers();
         }
     }
 
     pub fn recalc_convex_hull(&mut self) {
-        let input = self.recalc_sorted_pts().clone();
-        let out_poly = self.objects.get_mut(self.convex_hull_poly).unwrap();
-        if let Object::PolyObj(ref mut out_poly) = out_poly {
-            let ref mut out_verts = out_poly.verts;
-            let ref mut out_edges = out_poly.edges;
-            // wipe previous result;
-            out_verts.clear();
-            out_edges.clear();
-
-            if input.len() < 3 {
-                return;
-            }
+        let input = self.recalc_input_pts().clone();
+        let mut hull_poly = hull::hull_polygon(&input, &self.algorithm.hull(&input), WHITE);
+        for edge in hull_poly.edges.iter_mut() {
+            edge.2 = utils::random_color(&mut self.rng);
+        }
+        *self.scene.get_mut(self.convex_hull_poly).unwrap() = Object::PolyObj(hull_poly);
+        if self.hull_steps.is_some() {
+            self.restart_stepping();
+        }
+        if self.hull_layers.is_some() {
+            self.recalc_hull_layers();
+        }
+    }
 
-            let mut upper = vec![0, 1];
-
-            for i in 2..input.len() {
-                upper.push(i);
-
-                loop {
-                    if upper.len() < 3 { break; }
-                    let p_lll = input[upper[upper.len()-3]];
-                    let p_ll = input[upper[upper.len()-2]];
-                    let p_l = input[upper[upper.len()-1]];
-                    let v_a = glamVec2_from_point(p_lll - p_ll);
-                    let v_b = glamVec2_from_point(p_l - p_ll);
-                    let last_3_make_right = (v_a.x*v_b.y - v_b.x*v_a.y) > 0.0;
-                    if last_3_make_right {
-                        break;
-                    }
-                    upper.remove(upper.len()-2);
-                }
-            }
+    /// Peels the input points into convex layers, each outlined in its own colour.
+    pub fn recalc_hull_layers(&mut self) {
+        let layers = hull::convex_layers(&self.input_pts).iter()
+            .map(|layer| hull::hull_polygon(&self.input_pts, layer, utils::random_color(&mut self.rng)))
+            .collect();
+        self.hull_layers = Some(layers);
+    }
 
-            let mut lower = vec![input.len()-1, input.len()-2];
-
-            for i in (0..input.len()-2).rev() {
-                lower.push(i);
-
-                loop {
-                    if lower.len() < 3 { break; }
-                    let p_lll = input[lower[lower.len()-3]];
-                    let p_ll = input[lower[lower.len()-2]];
-                    let p_l = input[lower[lower.len()-1]];
-                    let v_a = glamVec2_from_point(p_lll - p_ll);
-                    let v_b = glamVec2_from_point(p_l - p_ll);
-                    let last_3_make_right = (v_a.x*v_b.y - v_b.x*v_a.y) > 0.0;
-                    if last_3_make_right {
-                        break;
-                    }
-                    lower.remove(lower.len()-2);
-                }
-            }
+    pub fn restart_stepping(&mut self) {
+        let (_, steps) = self.algorithm.trace(&self.input_pts);
+        self.hull_steps = Some((steps, 0));
+    }
 
-            *out_verts = input.iter().map(|pos| {
-                Vertex::new(pos.x() as f32, pos.y() as f32, None)
-            }).collect();
+    /// Renders the scene with whichever hull view is on: the algorithm
+    /// stepper, the convex layers or the plain hull.
+    pub fn render(&mut self, target: &mut dyn Renderer) {
+        self.scene.layer_mut(self.hull_layer).visible = self.hull_steps.is_none() && self.hull_layers.is_none();
+        self.scene.render(target);
+        for layer in self.hull_layers.iter().flatten() {
+            layer.render(target);
+        }
+        self.render_hull_step(target);
+    }
 
+    /// Writes what's on display as the next frame of `recorder`.
+    pub fn record_frame(&mut self, recorder: &mut Recorder) -> std::io::Result<()> {
+        let mut frame = Raster::new(WIDTH as usize, HEIGHT as usize, self.clear_color);
+        self.render(&mut frame);
+        recorder.save(&frame).map(|_| ())
+    }
 
-            let lower_edges = lower
-                .iter()
-                .cloned()
-                .skip(1) // dedup the lowermost vert
-                .take(lower.len().saturating_sub(2 + 1)) // and the uppermost
-                .zip(lower.iter().cloned().skip(1 + 1).take(lower.len() - 2))
-                .chain(iter::once((lower[lower.len()-2], upper[0])));
-            let upper_edges = upper
-                .iter()
-                .cloned()
-                .take(upper.len()-1)
-                .zip(upper.iter().cloned().skip(1).take(upper.len()-1))
-                .chain(iter::once((upper[upper.len()-1], lower[1])));
-
-            *out_edges = upper_edges.chain(lower_edges).map(|e| (e.0, e.1, utils::random_color())).collect();
-        } else {
-            panic!();
+    pub fn save_svg(&mut self, path: &str) -> std::io::Result<()> {
+        let mut svg = Svg::with_background(self.clear_color);
+        self.render(&mut svg);
+        svg.save(path)
+    }
+
+    fn render_hull_step(&self, target: &mut dyn Renderer) {
+        let Some((steps, cur)) = &self.hull_steps else { return; };
+        let Some(step) = steps.get(*cur) else { return; };
+        let pos = |i: usize| Vec2::from(self.input_pts[i]);
+
+        for group in &step.groups {
+            for (a, b) in group.iter().zip(group.iter().cycle().skip(1)) {
+                let (a, b) = (pos(*a), pos(*b));
+                target.line(a, b, 1.0, DARKGRAY);
+            }
+        }
+        for w in step.chain.windows(2) {
+            let (a, b) = (pos(w[0]), pos(w[1]));
+            target.line(a, b, 3.0, YELLOW);
+        }
+        if let Some((a, b)) = step.candidate {
+            let (a, b) = (pos(a), pos(b));
+            target.line(a, b, 2.0, RED);
         }
     }
-}
 
-fn glamVec2_from_point(p: Point) -> glam::Vec2 {
-    glam::Vec2::new(p.x() as f32, p.y() as f32)
+    /// Names the step on display, in screen space.
+    pub fn draw_hull_step_label(&self) {
+        let Some((steps, cur)) = &self.hull_steps else { return; };
+        draw_text(&format!("{}: step {}/{}", self.algorithm, cur + 1, steps.len()),
+                  20.0, 50.0, 24.0, GRAY);
+    }
 }
 
-#[derive(Debug)]
-enum LogTag {
-    Mouse, FrameTime, Select, Timing,
+impl Metrics for State {
+    fn metrics(&self) -> Vec<(String, String)> {
+        let hull_size = match self.scene.get(self.convex_hull_poly) {
+            Some(Object::PolyObj(hull_poly)) => hull_poly.verts.len(),
+            _ => 0,
+        };
+        let mut rows = vec![
+            ("hull algorithm".to_string(), self.algorithm.to_string()),
+            ("hull size".to_string(), format!("{hull_size} of {} points", self.dynamic_hull.len())),
+            ("dynamic hull rebuilds".to_string(), self.dynamic_hull.rebuilds().to_string()),
+        ];
+        if let Some(layers) = &self.hull_layers {
+            rows.push(("convex layers".to_string(), layers.len().to_string()));
+        }
+        rows
+    }
 }
 
-#[macroquad::main("convex-hull")]
-async fn main() {
-    const WIDTH: f32 = 1800.0;
-    const HEIGHT: f32 = 1000.0;
-    request_new_screen_size(WIDTH, HEIGHT);
-    let state = std::rc::Rc::new(std::sync::RwLock::new(State::default()));
+const WIDTH: f32 = 1800.0;
+const HEIGHT: f32 = 1000.0;
+const CIRCLE_RADIUS: f32 = 4.0;
 
-    #[cfg(debug_assertions)]
-    {
-    stderrlog::new()
-        .module(module_path!())
-        .verbosity(log::LevelFilter::Trace)
-        .init().unwrap();
-    }
+/// Where random input points are scattered.
+fn point_bounds() -> (std::ops::Range<f32>, std::ops::Range<f32>) {
+    (1.0*WIDTH/4.0..3.0*WIDTH/4.0,
+     1.0*HEIGHT/4.0..3.0*HEIGHT/4.0)
+}
 
-    let log_line = |state: &mut State, time: &std::time::Duration, tag: LogTag, msg: &str| {
-        let secs = time.as_secs();
-        let nanosecs = time.subsec_nanos();
-        writeln!(&mut state.logfile, "({tag:?}) [{}s{}ns]: {msg}", secs, nanosecs).expect("couldn't write log line")
-    };
-    
-    const CIRCLE_RADIUS: f32 = 4.0;
+fn timed_recalc(state: &mut State) {
+    let before = Instant::now();
+    state.recalc_convex_hull();
+    let after = Instant::now();
 
-    let bounds = (1.0*WIDTH/4.0..3.0*WIDTH/4.0,
-                  1.0*HEIGHT/4.0..3.0*HEIGHT/4.0);
-    let random_float_points = utils::random_points(10, bounds.clone());
+    let point_count = state.input_pts.len();
+    let operation = format!("recalc_convex_hull ({})", state.algorithm);
+    state.logger.log(Event::timing(operation, point_count, after - before));
+}
 
-    {
-        let mut state = state.write().unwrap();
-        for p in &random_float_points {
-            state.add_circle(geom::Circle {
-                center: Vertex::new(p.x, p.y,
-                                    Some(utils::random_color())),
+/// Builds the input shapes, from the scene file or at random, and the hulls
+/// over them. Doesn't touch macroquad, so it works without a window.
+fn setup(args: &Args) -> State {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let mut state = State::new(seed, Logger::from_args(args, &logging::timestamped_path()));
+    state.logger.log(Event::Seed { seed });
+
+    let point_count = args.positional(0, 10);
+    if let Some(scene_path) = args.option("scene") {
+        let file = SceneFile::load(scene_path).unwrap_or_else(|e| panic!("couldn't load {scene_path}: {e}"));
+        file.add_to(&mut state.scene);
+    } else {
+        for p in utils::random_points(&mut state.rng, point_count, point_bounds()) {
+            let clr = utils::random_color(&mut state.rng);
+            state.scene.add_circle(geom::Circle {
+                center: Vertex::new(p.x, p.y, Some(clr)),
                 radius: CIRCLE_RADIUS,
             });
         }
@@ -298,91 +293,282 @@ async fn main() {
             for vert in circle_poly.verts.iter_mut() {
                 vert.pos = vert.pos + center;
             }
-            state.add_poly(circle_poly);
+            state.scene.add_poly(circle_poly);
         }
 
         let rectangles = &[(Vector2D::new(450.0, 100.0), Vector2D::new(500.0, 300.0))];
 
         for (a,b) in rectangles {
-            state.add_poly(geom::Polygon::rectangle(*a, *b, WHITE, YELLOW));
+            state.scene.add_poly(geom::Polygon::rectangle(*a, *b, WHITE, YELLOW));
         }
     }
 
-    let mut state = state.write().unwrap();
+    // calculate initial convex hull with timing
+    timed_recalc(&mut state);
 
-    { // calculate initial convex hull with timing
+    {
         let before = Instant::now();
-        state.recalc_convex_hull();
-        let after = Instant::now();
+        state.rebuild_dynamic_hull();
+        let d = Instant::now() - before;
+        let point_count = state.dynamic_hull.len();
+        state.logger.log(Event::timing("building the dynamic hull", point_count, d));
+    }
+    state
+}
 
-        let d = after - before;
-        let point_count = state.sorted_pts.len();
-        log_line(&mut state, &std::time::Duration::from_secs(0), LogTag::Timing,
-                 &format!("recalc_convex_hull took {}s{}ns for {} points", d.as_secs(), d.subsec_nanos(), point_count));
+fn main() {
+    let args = Args::from_env(&["scene", "seed", "svg", "record", "log", "log-level", "bench", "runs", "sizes"]);
+    if args.flag("bench") {
+        bench(&args);
+    } else if args.flag("headless") {
+        headless(&args);
+    } else {
+        macroquad::Window::new("convex-hull", windowed(args));
     }
-        
-    loop {
-        let tick_time = Instant::now().duration_since(state.startup);
+}
 
-        let log_line = |state: &mut State, tag: LogTag, msg: &str| {
-            log_line(state, &tick_time, tag, msg);
-        };
+/// Runs every hull algorithm and the convex layers once, logging the timings
+/// as usual and printing the results.
+fn headless(args: &Args) {
+    let mut state = setup(args);
+    let point_count = state.input_pts.len();
+    println!("{point_count} input points");
+    for algorithm in hull::Algorithm::ALL {
+        state.algorithm = algorithm;
+        let before = Instant::now();
+        timed_recalc(&mut state);
+        let d = Instant::now() - before;
+        if let Some(Object::PolyObj(hull_poly)) = state.scene.get(state.convex_hull_poly) {
+            println!("{algorithm}: {} points on the hull in {}s{}ns", hull_poly.verts.len(), d.as_secs(), d.subsec_nanos());
+        }
+    }
+    println!("dynamic hull: {} points on the hull", state.dynamic_hull.hull().len());
+
+    if let Some(dir) = args.option("record") {
+        let mut recorder = Recorder::new(dir).unwrap_or_else(|e| panic!("couldn't record to {dir}: {e}"));
+        for algorithm in hull::Algorithm::ALL {
+            let first = recorder.frames();
+            state.algorithm = algorithm;
+            state.restart_stepping();
+            let step_count = state.hull_steps.as_ref().map_or(0, |(steps, _)| steps.len());
+            for step in 0..step_count {
+                if let Some((_, cur)) = state.hull_steps.as_mut() { *cur = step; }
+                state.record_frame(&mut recorder).unwrap_or_else(|e| panic!("couldn't record to {dir}: {e}"));
+            }
+            println!("recorded {algorithm} as frames {first}..{} in {dir}", recorder.frames());
+        }
+        state.hull_steps = None;
+    }
+
+    let before = Instant::now();
+    state.recalc_hull_layers();
+    let d = Instant::now() - before;
+    let layer_sizes = state.hull_layers.iter().flatten().map(|l| l.verts.len().to_string()).collect::<Vec<_>>();
+    state.logger.log(Event::timing("convex layers", point_count, d).with_m(layer_sizes.len()));
+    println!("convex layers: {} (sizes {}) in {}s{}ns", layer_sizes.len(), layer_sizes.join(", "), d.as_secs(), d.subsec_nanos());
+
+    if args.flag("svg") {
+        let svg_path = args.option("svg").unwrap_or(render::DEFAULT_SVG_PATH);
+        state.save_svg(svg_path).unwrap_or_else(|e| panic!("couldn't export {svg_path}: {e}"));
+        println!("exported the convex layers to {svg_path}");
+    }
+}
+
+/// Sweeps the hull algorithms, the convex layers and building the dynamic
+/// hull over growing sets of random points, writing the timings to `--bench`.
+fn bench(args: &Args) {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let mut rng = utils::seeded_rng(seed);
+    let mut bench = Bench::from_args(args);
+    println!("seed: {seed}, {} runs per size", bench.runs());
+    for n in bench.sizes(&[1000, 2000, 4000, 8000, 16000]) {
+        let points = utils::random_sites(&mut rng, n, point_bounds());
+        for algorithm in hull::Algorithm::ALL {
+            bench.measure(&format!("recalc_convex_hull ({algorithm})"), n, || algorithm.hull(&points));
+        }
+        bench.measure("convex layers", n, || hull::convex_layers(&points));
+        bench.measure("building the dynamic hull", n, || hull::DynamicHull::from_points(points.iter().copied().enumerate()));
+    }
+    print!("{}", bench.report());
+
+    let path = args.option("bench").unwrap_or("./bench-convex-hull.csv");
+    bench.save(path).unwrap_or_else(|e| panic!("couldn't write {path}: {e}"));
+    println!("wrote the timings to {path}");
+}
+
+async fn windowed(args: Args) {
+    request_new_screen_size(WIDTH, HEIGHT);
+
+    #[cfg(debug_assertions)]
+    {
+    stderrlog::new()
+        .module(module_path!())
+        .verbosity(log::LevelFilter::Trace)
+        .init().unwrap();
+    }
+
+    let mut state = setup(&args);
+    state.prev_mouse_pos = mouse_position();
+    let scene_path = args.option("scene").unwrap_or(scene_file::DEFAULT_PATH).to_string();
+    let svg_path = args.option("svg").unwrap_or(render::DEFAULT_SVG_PATH).to_string();
+    let bounds = point_bounds();
 
-        log_line(&mut state, LogTag::FrameTime, &format!("{} / {}", get_frame_time(), get_fps()));
+    loop {
+        state.logger.log(Event::FrameTime { frame_time: get_frame_time(), fps: get_fps() });
+        state.hud.update();
 
         if is_quit_requested() { break }
         clear_background(state.clear_color);
 
-        for handle in state.objects.iter() {
-            let object = state.objects.get(handle).unwrap();
-            object.draw();
+        let State { viewport, scene, .. } = &mut state;
+        viewport.update(|| scene.bounds());
+        viewport.activate();
+        state.render(&mut render::Screen);
+
+        if is_key_released(KeyCode::A) {
+            state.algorithm = state.algorithm.next();
+            timed_recalc(&mut state);
+        }
+
+        if is_key_released(KeyCode::T) {
+            if state.hull_steps.is_some() {
+                state.hull_steps = None;
+            } else {
+                state.restart_stepping();
+            }
+        }
+
+        if is_key_released(KeyCode::L) {
+            if state.hull_layers.is_some() {
+                state.hull_layers = None;
+            } else {
+                if state.input_pts_stale { state.recalc_input_pts(); }
+                let before = Instant::now();
+                state.recalc_hull_layers();
+                let d = Instant::now() - before;
+                let layer_count = state.hull_layers.as_ref().map_or(0, Vec::len);
+                let point_count = state.input_pts.len();
+                state.logger.log(Event::timing("convex layers", point_count, d).with_m(layer_count));
+            }
+        }
+
+        if is_key_released(KeyCode::S) {
+            if let Some((steps, cur)) = state.hull_steps.as_mut() {
+                *cur = (*cur + 1).min(steps.len().saturating_sub(1));
+            }
+        }
+
+        if is_key_released(KeyCode::P) {
+            // scatter more points to stress the dynamic hull
+            let before = Instant::now();
+            let points = utils::random_points(&mut state.rng, 1000, bounds.clone());
+            let handles: Vec<_> = points.into_iter().map(|p| {
+                let clr = utils::random_color(&mut state.rng);
+                state.scene.add_circle(geom::Circle {
+                    center: Vertex::new(p.x, p.y, Some(clr)),
+                    radius: CIRCLE_RADIUS,
+                })
+            }).collect();
+            state.hull_insert(handles);
+            let d = Instant::now() - before;
+            let point_count = state.dynamic_hull.len();
+            state.logger.log(Event::timing("1000 dynamic hull inserts", point_count, d));
+        }
+
+        if is_key_released(KeyCode::F5) {
+            // the hull is derived from the rest, so only the input shapes are kept
+            let default_layer = Scene::<Object>::DEFAULT_LAYER;
+            let file = SceneFile::from_shapes(state.scene.iter()
+                .filter(|(h, _)| state.scene.layer_of(*h) == Some(default_layer))
+                .map(|(_, o)| o));
+            state.logger.log(Event::file(FileAction::Save, &scene_path, &file.save(&scene_path)));
+        }
+
+        if is_key_released(KeyCode::F6) {
+            let exported = state.save_svg(&svg_path);
+            state.logger.log(Event::file(FileAction::Export, &svg_path, &exported));
+        }
+
+        if is_key_released(KeyCode::F9) {
+            let loaded = SceneFile::load(&scene_path);
+            state.logger.log(Event::file(FileAction::Load, &scene_path, &loaded));
+            if let Ok(file) = loaded {
+                state.scene.clear_layer(Scene::<Object>::DEFAULT_LAYER);
+                file.add_to(&mut state.scene);
+                timed_recalc(&mut state);
+                state.rebuild_dynamic_hull();
+            }
         }
 
+        set_default_camera();
+        state.draw_hull_step_label();
+
         // enumerate input points if requested
         if is_key_down(KeyCode::N) {
-            for (i,point) in state.sorted_pts.iter().enumerate() {
-                draw_text(&i.to_string(), point.x() as f32, point.y() as f32, 20.0, WHITE);
+            if state.input_pts_stale { state.recalc_input_pts(); }
+            for (i,point) in state.input_pts.iter().enumerate() {
+                let pos = state.viewport.world_to_screen(Vec2::from(*point));
+                draw_text(&i.to_string(), pos.x, pos.y, 20.0, WHITE);
             }
         }
 
         { // Mouse handling
-            let mouse_pos = mouse_position();
+            let mouse_pos = state.viewport.mouse_world();
             if mouse_pos != state.prev_mouse_pos {
-                log_line(&mut state, LogTag::Mouse, &format!("pos {},{}", mouse_pos.0, mouse_pos.1));
+                state.logger.log(Event::Mouse { action: MouseAction::Pos, x: mouse_pos.0, y: mouse_pos.1 });
                 state.prev_mouse_pos = mouse_pos;
             }
 
             if is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_pressed(MouseButton::Right) {
-                log_line(&mut state, LogTag::Mouse, &format!("clicked {},{}", mouse_pos.0, mouse_pos.1));
+                state.logger.log(Event::Mouse { action: MouseAction::Clicked, x: mouse_pos.0, y: mouse_pos.1 });
 
-                let mut hit_elem = None;
                 let delete = is_mouse_button_pressed(MouseButton::Right);
-                for (handle,element) in state.all_elements() {
-                    if element.contains_point(&Vector2D::new(mouse_pos.0, mouse_pos.1)) {
-                        hit_elem = Some(handle);
-                        break;
-                    }
-                }
-                if let Some(elem) = hit_elem {
-                    log_line(&mut state, LogTag::Select, &format!("selected {:?}", elem));
+                let hit_elem = state.scene.pick(&Vector2D::new(mouse_pos.0, mouse_pos.1));
+                let before = Instant::now();
+                let update = if let Some(elem) = hit_elem {
+                    state.logger.log(Event::Select { object: format!("{:?}", elem) });
                     if delete {
-                        state.objects.remove(elem);
-                    }
+                        state.hull_remove(elem);
+                        Some("remove")
+                    } else { None }
                 } else {
-                    state.add_circle(geom::Circle {
-                        center: Vertex::new(mouse_pos.0, mouse_pos.1, Some(utils::random_color())),
+                    let clr = utils::random_color(&mut state.rng);
+                    let handle = state.scene.add_circle(geom::Circle {
+                        center: Vertex::new(mouse_pos.0, mouse_pos.1, Some(clr)),
                         radius: CIRCLE_RADIUS,
                     });
+                    state.hull_insert([handle]);
+                    Some("insert")
+                };
+                if let Some(update) = update {
+                    let d = Instant::now() - before;
+                    let point_count = state.dynamic_hull.len();
+                    // the rebuilds so far tell how much an update really costs
+                    let rebuilds = state.dynamic_hull.rebuilds();
+                    state.logger.log(Event::timing(format!("dynamic hull {update}"), point_count, d).with_m(rebuilds));
                 }
-                state.recalc_convex_hull();
             }
         }
 
-        draw_text("IT WORKS!", 20.0, 20.0, 30.0, DARKGRAY);
-
-        if is_key_released(KeyCode::R) {
-            println!("{}", state.text_digest())
-        }
+        state.hud.draw(&[&state.scene, &state]);
         next_frame().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_and_lines_are_on_the_hull() {
+        let log = std::env::temp_dir().join("convex-hull-points-and-lines.jsonl");
+        let mut state = State::new(0, Logger::create(&log, log::LevelFilter::Off).unwrap());
+        let file = SceneFile::from_text("point 10 10\npoint 50 20\nline 0 40 60 60 1\n").unwrap();
+        file.add_to(&mut state.scene);
+
+        assert_eq!(state.recalc_input_pts().len(), 4);
+        assert_eq!(hull::convex_hull(&state.input_pts).len(), 4);
+        state.rebuild_dynamic_hull();
+        assert_eq!(state.dynamic_hull.hull().len(), 4);
+    }
+}
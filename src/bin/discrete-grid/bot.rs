@@ -1,6 +1,6 @@
-use ::rand::random_range;
+use ::rand::Rng;
 use stales_geom_viewer::{
-    common_traits::*, geom::{self, Vertex, *}, point::Point, utils::{self, random_color}
+    common_traits::*, geom::{self, Vertex, *}, point::Point, render::Renderer, utils::{self, random_color}
 };
 use euclid::default::Vector2D;
 use macroquad::prelude::*;
@@ -49,10 +49,10 @@ impl Bot {
         }
     }
 
-    pub fn random_inside(grid: &Box<dyn ObservableGrid>, pathfinder: Box<dyn Pathfinder>) -> Self {
-        let origin_idx = random_range(0..grid.size().0*grid.size().1);
-        let dest_idx = random_range(0..grid.size().0*grid.size().1);
-        let clr = random_color();
+    pub fn random_inside(grid: &Box<dyn ObservableGrid>, pathfinder: Box<dyn Pathfinder>, rng: &mut impl Rng) -> Self {
+        let origin_idx = rng.random_range(0..grid.size().0*grid.size().1);
+        let dest_idx = rng.random_range(0..grid.size().0*grid.size().1);
+        let clr = random_color(rng);
         Self::new(grid, pathfinder, origin_idx, dest_idx, clr)
     }
 
@@ -67,16 +67,16 @@ impl Bot {
 }
 
 impl Draw for Bot {
-    fn draw(&self) {
+    fn render(&self, target: &mut dyn Renderer) {
         let (x,y) = match &self.path {
             Ok(path) => {
                 let a_i = self.path_step.min(path.len() - 1);
                 let xy = path[a_i].1;
                 xy
             },
-            Err(_) => (self.pos.x() as f32, self.pos.y() as f32),
+            Err(_) => Vec2::from(self.pos).into(),
         };
-        draw_circle(x, y, self.radius, self.clr);
+        target.circle(vec2(x, y), self.radius, self.clr);
 
         match self.path {
             Ok(ref path) => {
@@ -84,7 +84,7 @@ impl Draw for Bot {
                 let verts_len = verts.len();
                 let segments = verts.clone().zip(verts.skip(1)).take(verts_len - 1);
                 for (a,b) in segments {
-                    draw_line(a.0, a.1, b.0, b.1, 2.0, self.clr);
+                    target.line(a.into(), b.into(), 2.0, self.clr);
                     //draw_circle_lines(b.0, b.1, self.radius/4.0, 2.0, self.clr);
                 }
             },
@@ -93,19 +93,19 @@ impl Draw for Bot {
     }
 
     fn vertices(&self) -> Vec<Vertex> {
-        vec![Vertex::new(self.pos.x() as f32, self.pos.y() as f32, Some(self.clr))]
+        vec![Vertex { clr: Some(self.clr), ..self.pos.into() }]
     }
 }
 
 impl Select for Bot {
     fn compute_aabb(&self) -> euclid::default::Box2D<f32> {
-        let topleft  = euclid::default::Point2D::new(self.pos.x() as f32 - self.radius, self.pos.y() as f32 - self.radius);
-        let botright = euclid::default::Point2D::new(self.pos.x() as f32 + self.radius, self.pos.y() as f32 + self.radius);
-        euclid::default::Box2D::new(topleft, botright)
+        let center: euclid::default::Point2D<f32> = self.pos.into();
+        let radius = euclid::default::Vector2D::splat(self.radius);
+        euclid::default::Box2D::new(center - radius, center + radius)
     }
 
     fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
-        (Point::new(global_sample_point.x as f64, global_sample_point.y as f64) - self.pos).magnitude() as f32 - self.radius
+        (Point::from(*global_sample_point) - self.pos).magnitude() as f32 - self.radius
     }
 }
 
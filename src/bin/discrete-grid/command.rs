@@ -88,7 +88,7 @@ impl Command<State> for StepForward {
         use crate::Object;
         state.tick += 1;
         for bot_handle in &state.bots {
-            if let Object::BotObj(bot) = state.objects.get(*bot_handle).unwrap() {
+            if let Object::BotObj(bot) = state.scene.get(*bot_handle).unwrap() {
                 bot.borrow_mut().path_step = state.tick;
             }
         }
@@ -110,7 +110,7 @@ impl Command<State> for StepBackwards {
         use crate::Object;
         state.tick = state.tick.saturating_sub(1);
         for bot_handle in &state.bots {
-            if let Object::BotObj(bot) = state.objects.get(*bot_handle).unwrap() {
+            if let Object::BotObj(bot) = state.scene.get(*bot_handle).unwrap() {
                 bot.borrow_mut().path_step = state.tick;
             }
         }
@@ -135,13 +135,12 @@ impl Command<State> for AddBot {
         use crate::{bot, Object};
         use stales_geom_viewer::utils::random_color;
         use std::cell::RefCell;
-        use bot::PathfinderDecorator;
         let (origin_idx, dest_idx) =
             (state.grid.coords_idx(self.pos).ok_or(CommandError("invalid coordinates for origin".to_string()))?,
              state.grid.coords_idx(self.dest).ok_or(CommandError("invalid coordinates for origin".to_string()))?);
-        let pathfinder = bot::DebugPathFinder::wrap(Box::new(bot::BasePathfinder {}));
-        let bot = bot::Bot::new(&state.grid, pathfinder, origin_idx, dest_idx, random_color());
-        let bot_handle = state.objects.insert(Object::BotObj(RefCell::new(bot)));
+        let pathfinder = state.pathfinder();
+        let bot = bot::Bot::new(&state.grid, pathfinder, origin_idx, dest_idx, random_color(&mut state.rng));
+        let bot_handle = state.scene.insert(Object::BotObj(RefCell::new(bot)));
         state.bots.push(bot_handle);
         Ok(Some(Box::new(RemoveBot::new(bot_handle))))
     }
@@ -161,7 +160,7 @@ impl RemoveBot {
 impl Command<State> for RemoveBot {
     fn run(&self, state: &mut State) -> CommandResult<State> {
         use crate::Object;
-        let bot = state.objects.remove(self.bot_handle).ok_or(CommandError("no such bot".to_string()))?;
+        let bot = state.scene.remove(self.bot_handle).ok_or(CommandError("no such bot".to_string()))?;
         state.bots.retain(|handle| *handle != self.bot_handle);
         if let Object::BotObj(bot) = bot {
             let bot = bot.borrow();
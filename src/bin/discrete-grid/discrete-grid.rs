@@ -1,17 +1,25 @@
-use chrono::Timelike;
 use macroquad::prelude::*;
-use genmap::GenMap;
 
 use obstacle::Obstacle;
 use stales_geom_viewer::{
+    bench::Bench,
     common_traits::*,
     geom::{self, *},
-    point::Point, utils::random_color,
+    camera::Viewport,
+    hud::{Hud, Metrics},
+    cli::Args,
+    logging::{self, Event, FileAction, Logger, MouseAction},
+    point::Point, utils::{self, random_color},
+    raster::{Raster, Recorder},
+    render::{self, Renderer, Svg},
+    scene::{self, Scene, SceneObject},
+    scene_file::{self, BotSpec, GridKind, GridSpec, SceneFile},
 };
 use euclid::{default::Vector2D, num::Floor};
+use ::rand::{rngs::StdRng, SeedableRng};
 
 use std::{
-    cell::RefCell, cmp::{Ord, Ordering}, collections::{BTreeMap, HashMap}, default::Default, env, fmt::Debug, fs::File, io::Write, iter::{self, Iterator}, ops::{Deref, DerefMut}, sync::{Arc, RwLock}, time::{self, Instant}
+    cell::RefCell, cmp::{Ord, Ordering}, collections::{BTreeMap, HashMap}, default::Default, fmt::Debug, fs::File, iter::{self, Iterator}, ops::{Deref, DerefMut}, sync::{Arc, RwLock}, time::{self, Instant}
 };
 
 mod bot;
@@ -32,39 +40,63 @@ pub enum DrawingState {
 
 type Color = macroquad::color::Color;
 
+/// The shared shapes plus the grid and its bots.
 #[derive(Debug)]
 pub enum Object {
-    Point(geom::Vertex),
-    CircleObj(geom::Circle),
-    LineObj(geom::Line2D),
-    PolyObj(geom::Polygon),
+    Shape(scene::Object),
     GridObj(Box<dyn Grid>),
     BotObj(RefCell<Bot>),
 }
 
+impl From<scene::Object> for Object {
+    fn from(shape: scene::Object) -> Self {
+        Object::Shape(shape)
+    }
+}
+
 impl Draw for Object {
-    fn draw(&self) {
+    fn render(&self, target: &mut dyn Renderer) {
         match self {
-            Object::Point(p) => p.draw(),
-            Object::CircleObj(c) => c.draw(),
-            Object::LineObj(l) => l.draw(),
-            Object::PolyObj(p) => p.draw(),
-            Object::GridObj(g) => g.draw(),
-            Object::BotObj(b) => b.borrow().draw(),
+            Object::Shape(s) => s.render(target),
+            Object::GridObj(g) => g.render(target),
+            Object::BotObj(b) => b.borrow().render(target),
         }
     }
     fn vertices(&self) -> Vec<geom::Vertex> {
         match self {
-            Object::Point(p) => p.vertices(),
-            Object::CircleObj(c) => c.vertices(),
-            Object::LineObj(l) => l.vertices(),
-            Object::PolyObj(p) => p.vertices(),
+            Object::Shape(s) => s.vertices(),
             Object::GridObj(g) => g.vertices(),
             Object::BotObj(b) => b.borrow().vertices(),
         }
     }
 }
 
+impl Select for Object {
+    fn compute_aabb(&self) -> euclid::default::Box2D<f32> {
+        match self {
+            Object::Shape(s) => s.compute_aabb(),
+            Object::GridObj(g) => g.compute_aabb(),
+            Object::BotObj(b) => b.borrow().compute_aabb(),
+        }
+    }
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        match self {
+            Object::Shape(s) => s.sample_signed_distance_field(global_sample_point),
+            Object::GridObj(g) => g.sample_signed_distance_field(global_sample_point),
+            Object::BotObj(b) => b.borrow().sample_signed_distance_field(global_sample_point),
+        }
+    }
+}
+
+impl SceneObject for Object {
+    fn shape(&self) -> Option<&scene::Object> {
+        if let Object::Shape(s) = self { Some(s) } else { None }
+    }
+    fn shape_mut(&mut self) -> Option<&mut scene::Object> {
+        if let Object::Shape(s) = self { Some(s) } else { None }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
     Obstacles(DrawingState),
@@ -143,11 +175,10 @@ pub enum GameState {
 
 type Handler = Box<dyn Fn(&State) -> Result<GameState, GameState>>;
 pub struct State {
-    pub objects: GenMap<Object>,
+    pub scene: Scene<Object>,
     pub clear_color: Color,
-    pub startup: Instant,
     pub prev_mouse_pos: (f32, f32),
-    pub logfile: std::fs::File,
+    pub logger: Logger,
 
     pub tick: usize,
     pub input_mode: InputMode,
@@ -160,16 +191,41 @@ pub struct State {
     pub gamestate: GameState,
     pub goal: Option<(usize, usize)>,
     pub chain: Vec<Handler>,
+    pub viewport: Viewport,
+    pub hud: Hud,
+    pub rng: StdRng,
+    /// No window to draw on, so the bots don't get the debug pathfinder.
+    pub headless: bool,
+}
+
+impl Metrics for State {
+    fn metrics(&self) -> Vec<(String, String)> {
+        let bots: Vec<_> = self.bots.iter().filter_map(|handle| match self.scene.get(*handle) {
+            Some(Object::BotObj(bot)) => Some(bot.borrow()),
+            _ => None,
+        }).collect();
+        let path_lengths: Vec<usize> = bots.iter().filter_map(|bot| bot.path.as_ref().ok()).map(|path| path.len().saturating_sub(1)).collect();
+        let arrived = bots.iter().filter(|bot| bot.path.as_ref().is_ok_and(|path| bot.path_step + 1 >= path.len())).count();
+        let (cols, rows) = self.grid.size();
+        let mut metrics = vec![
+            ("grid".to_string(), format!("{cols}x{rows}")),
+            ("bots".to_string(), format!("{} ({arrived} arrived, {} without a path)", bots.len(), bots.len() - path_lengths.len())),
+        ];
+        if let Some(longest) = path_lengths.iter().max() {
+            let mean = path_lengths.iter().sum::<usize>() as f32 / path_lengths.len() as f32;
+            metrics.push(("path lengths".to_string(), format!("{mean:.1} on average, {longest} at most")));
+        }
+        metrics
+    }
 }
 
 impl Debug for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("State")
-         .field("objects", &self.objects)
+         .field("scene", &self.scene)
          .field("clear_color", &self.clear_color)
-         .field("startup", &self.startup)
          .field("prev_mouse_pos", &self.prev_mouse_pos)
-         .field("logfile", &self.logfile)
+         .field("logger", &self.logger)
          .field("tick", &self.tick)
          .field("input_mode", &self.input_mode)
          .field("sel_cell", &self.sel_cell)
@@ -181,16 +237,16 @@ impl Debug for State {
          .field("gamestate", &self.gamestate)
          .field("goal", &self.goal)
          .field("chain", &self.chain.len())
+         .field("viewport", &self.viewport)
+         .field("hud", &self.hud)
+         .field("headless", &self.headless)
          .finish()
     }
 }
 
 impl State {
-    fn new(mut grid: Box<dyn ObservableGrid>) -> Self {
-        use chrono;
-        let objects = GenMap::<Object>::with_capacity(1000);
-        let cur_time = chrono::Local::now();
-        let log_name = format!("./log-{}-{}-{}-{}.txt", cur_time.hour(), cur_time.minute(), cur_time.second(), cur_time.nanosecond());
+    fn new(mut grid: Box<dyn ObservableGrid>, rng: StdRng, logger: Logger) -> Self {
+        let scene = Scene::with_capacity(1000);
         let chain = {
             let mut chain: Vec<Handler> = vec![];
             let has_hero = Box::new(|s: &State| {
@@ -216,11 +272,10 @@ impl State {
         };
         
         Self {
-            objects: objects,
+            scene,
             clear_color: BLACK,
-            startup: Instant::now(),
-            prev_mouse_pos: mouse_position(),
-            logfile: std::fs::File::create(log_name).expect("can't create \"./log.txt\" log file!"),
+            prev_mouse_pos: (0.0, 0.0),
+            logger,
             tick: 0,
             input_mode: InputMode::Obstacles(DrawingState::Ground),
             sel_cell: None,
@@ -232,68 +287,26 @@ impl State {
             gamestate: GameState::Play,
             goal: None,
             chain,
+            viewport: Viewport::default(),
+            // under the input mode line
+            hud: Hud::at(vec2(20.0, 40.0)),
+            rng,
+            headless: false,
         }
     }
 
-    fn add_line(&mut self, l: geom::Line2D) -> genmap::Handle {
-        self.objects.insert(Object::LineObj(l))
-
-    }
-    fn add_circle(&mut self, c: geom::Circle) -> genmap::Handle {
-        self.objects.insert(Object::CircleObj(c))
-    }
-    fn add_poly(&mut self, p: geom::Polygon) -> genmap::Handle {
-        self.objects.insert(Object::PolyObj(p))
-    }
-    fn all_elements(&self) -> impl Iterator<Item = (genmap::Handle, &dyn Element)> {
-        self.objects.iter().flat_map(|x| {
-            self.objects.get(x).and_then(|obj|
-                match obj {
-                    Object::Point(_p) => None,
-                    Object::CircleObj(c) => Some(c as &dyn Element),
-                    Object::LineObj(l) => Some(l as &dyn Element),
-                    Object::PolyObj(_p) => None,
-                    //Object::GridObj(ref g) => Some(g as &dyn Element),
-                    Object::GridObj(_g) => None,
-                    Object::BotObj(_b) => None, //Some(b.into_inner().clone() as &dyn Element),
-                }).map(|elem| (x, elem))
-            })
-    }
-    fn text_digest(&self) -> String {
-        let line_cnt = self.objects
-                           .iter().flat_map(|x| self.objects.get(x))
-                           .filter(|x| matches!(x, Object::LineObj(_))).count();
-        let circle_cnt = self.objects
-                             .iter().flat_map(|x| self.objects.get(x))
-                             .filter(|x| matches!(x, Object::CircleObj(_))).count();
-        let vertex_cnt = self.objects
-                             .iter().flat_map(|x| self.objects.get(x))
-                                    .map(Draw::vertices).fold(0usize, |acc,verts| acc + verts.len());
-        let frametime = get_frame_time();
-        let bots = format!("{:#?}", self.bots.iter().map(|b| self.objects.get(*b)).collect::<Vec<_>>());
-        let command_history = format!("{:#?}", self.command_history);
-        format!(r"
-num. of lines: {line_cnt}
-num. of circles: {circle_cnt}
-num. of vertices: {vertex_cnt}
-frametime: {frametime}
-bots: {bots}
-command history: {command_history}
-")
-    }
-
-    pub fn log_line(&mut self, tag: LogTag, msg: &str) {
-        let tick_time = Instant::now().duration_since(self.startup);
-        let secs = tick_time.as_secs();
-        let nanosecs = tick_time.subsec_nanos();
-
-        writeln!(&mut self.logfile, "({tag:?}) [{}s{}ns]: {msg}", secs, nanosecs).expect("couldn't write log line")
+    /// The pathfinder new bots get: the debug one draws its paths, which
+    /// needs a window.
+    pub fn pathfinder(&self) -> Box<dyn bot::Pathfinder> {
+        use bot::PathfinderDecorator;
+        let base = Box::new(bot::BasePathfinder {});
+        if self.headless { base } else { bot::DebugPathFinder::wrap(base) }
     }
 
     fn run_command_inner(&mut self, cmd: &Box<dyn Command<State>>, forget: bool) -> Option<CommandHistoryEntry> {
         match cmd.run(self) {
             Ok(undo) => {
-                self.log_line(LogTag::Command, &format!("executing command {cmd:?}"));
+                self.logger.log(Event::Command { command: format!("{cmd:?}") });
                 let entry = CommandHistoryEntry::new(cmd.clone(), undo);
                 if ! forget {
                     self.command_history.push_entry(entry);
@@ -303,7 +316,7 @@ command history: {command_history}
                 }
             },
             Err(e) => {
-                self.log_line(LogTag::Error, &format!("couldn't execute command {cmd:?}, reason: {e}"));
+                self.logger.log(Event::Error { msg: format!("couldn't execute command {cmd:?}, reason: {e}") });
                 None
             }
         }
@@ -332,7 +345,7 @@ command history: {command_history}
     }
 
     pub fn hero(&self) -> Option<&RefCell<Bot>> {
-        let hobj = self.objects.get(self.hero?)?;
+        let hobj = self.scene.get(self.hero?)?;
         if let Object::BotObj(ref b) = hobj {
             Some(b)
         } else {
@@ -344,6 +357,91 @@ command history: {command_history}
         self.goal = goal;
     }
 
+    /// The scene with the grid over it, as on screen.
+    fn render(&self, target: &mut dyn Renderer) {
+        self.scene.render(target);
+        self.grid.render(target);
+    }
+
+    /// Writes what's on screen as the next frame of `recorder`.
+    fn record_frame(&self, recorder: &mut Recorder) -> std::io::Result<()> {
+        let mut frame = Raster::new(WIDTH as usize, HEIGHT as usize, self.clear_color);
+        self.render(&mut frame);
+        recorder.save(&frame).map(|_| ())
+    }
+
+    fn save_svg(&self, path: &str) -> std::io::Result<()> {
+        let mut svg = Svg::with_background(self.clear_color);
+        self.render(&mut svg);
+        svg.save(path)
+    }
+
+    /// The shapes, the grid with its obstacles, the bots and the goal.
+    fn scene_file(&self) -> SceneFile {
+        let grid = &self.grid;
+        let (topleft, botright) = grid.corners();
+        let cell_count = grid.size().0 * grid.size().1;
+        let bots = self.bots.iter().flat_map(|h| match self.scene.get(*h) {
+            Some(Object::BotObj(bot)) => {
+                let bot = bot.borrow();
+                Some(BotSpec { origin: grid.idx_coords(bot.origin_idx)?, dest: grid.idx_coords(bot.dest_idx)? })
+            }
+            _ => None,
+        }).collect();
+        SceneFile {
+            grid: Some(GridSpec {
+                kind: grid.kind(),
+                size: grid.size(),
+                topleft: (topleft.x(), topleft.y()),
+                botright: (botright.x(), botright.y()),
+                obstacles: (0..cell_count).filter(|i| grid.idx(*i).is_some()).flat_map(|i| grid.idx_coords(i)).collect(),
+                bots,
+                goal: self.goal,
+            }),
+            ..SceneFile::from_scene(&self.scene)
+        }
+    }
+
+    /// Replaces the shapes with the ones in `file` and, if it has a grid, the
+    /// grid, bots and goal too. The new grid has no observers yet. When an
+    /// obstacle or bot can't be placed, nothing is replaced.
+    fn load_scene_file(&mut self, file: &SceneFile) -> Result<(), command::CommandError> {
+        if let Some(spec) = &file.grid {
+            // the new grid and bots go in beside the old ones until they're all placed
+            let grid = new_grid(spec.kind, Point::new(spec.topleft.0, spec.topleft.1), Point::new(spec.botright.0, spec.botright.1), spec.size, vec![]);
+            let old_grid = std::mem::replace(&mut self.grid, grid);
+            let old_bots = std::mem::take(&mut self.bots);
+            let placed = spec.obstacles.iter().try_for_each(|cell| command::AddObstacle::new(*cell).run(self).map(|_| ()))
+                .and_then(|_| spec.bots.iter().try_for_each(|bot| command::AddBot::new(bot.origin, bot.dest).run(self).map(|_| ())));
+            let gone = match placed {
+                Ok(()) => old_bots,
+                Err(e) => {
+                    for bot in std::mem::replace(&mut self.bots, old_bots) {
+                        self.scene.remove(bot);
+                    }
+                    self.grid = old_grid;
+                    return Err(e);
+                }
+            };
+            for bot in gone {
+                self.scene.remove(bot);
+            }
+            self.hero = None;
+            self.sel_cell = None;
+            self.input_mode = InputMode::Obstacles(DrawingState::Ground);
+            // the recorded commands refer to the old grid
+            self.command_history = CommandHistory::default();
+            self.set_goal(spec.goal);
+        }
+
+        let shapes: Vec<_> = self.scene.shapes().map(|(h, _)| h).collect();
+        for handle in shapes {
+            self.scene.remove(handle);
+        }
+        file.add_to(&mut self.scene);
+        Ok(())
+    }
+
     fn recalc_gamestate(&mut self) {
         let state = &self.chain.iter().fold(
             Ok::<GameState, GameState>(self.gamestate),
@@ -352,40 +450,177 @@ command history: {command_history}
     }
 }
 
-fn glamVec2_from_point(p: Point) -> glam::Vec2 {
-    glam::Vec2::new(p.x() as f32, p.y() as f32)
+fn new_grid(kind: GridKind, topleft: Point, botright: Point, size: (usize, usize),
+            obstacle_factories: Vec<(usize, Box<dyn obstacle::Factory>)>) -> Box<dyn ObservableGrid> {
+    let grid: Box<dyn Grid> = match kind {
+        GridKind::Square => Box::new(SquareGrid::new(topleft, botright, WHITE, size, obstacle_factories)),
+        GridKind::Hex => Box::new(HexGrid::new(topleft, botright, WHITE, size, obstacle_factories)),
+    };
+    Box::new(grid::ObservableGridDecorator::new(grid))
 }
 
-#[derive(Debug)]
-pub enum LogTag {
-    Mouse, FrameTime, Select, Timing,
-    Command, Error,
+const WIDTH: f32 = 1800.0;
+const HEIGHT: f32 = 1000.0;
+/// How many ticks `--headless` runs when `--ticks` isn't given.
+const HEADLESS_TICKS: usize = 100;
+
+/// Builds the grid, its obstacles and bots, from the scene file or at random.
+/// Doesn't touch macroquad, so it works without a window.
+fn setup(args: &Args) -> State {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let mut rng = utils::seeded_rng(seed);
+    let grid_dims = (args.positional(0, 30), args.positional(1, 30));
+    let cell_count = grid_dims.0*grid_dims.1;
+    let scene_file = args.option("scene").map(|scene_path| {
+        SceneFile::load(scene_path).unwrap_or_else(|e| panic!("couldn't load {scene_path}: {e}"))
+    });
+    // a scene file brings its own bots and obstacles
+    let populate = scene_file.is_none();
+    let bot_count = if populate { args.positional(2, 5.min(cell_count)) } else { 0 };
+    let obstacle_count = if populate { args.positional(3, 10.min(cell_count)) } else { 0 };
+
+    let grid = new_grid(
+        GridKind::Hex,
+        Point::new(WIDTH as f64/8.0, HEIGHT as f64/8.0),
+        Point::new(WIDTH as f64 * 7.0/8.0, HEIGHT as f64 * 7.0/8.0),
+        grid_dims,
+        vec![
+            (obstacle_count, Box::new(obstacle::factories::RandomBoulder::new(StdRng::from_rng(&mut rng)))),
+        ]
+    );
+    let mut state = State::new(grid, rng, Logger::from_args(args, &logging::timestamped_path()));
+    state.headless = args.flag("headless");
+
+    state.logger.log(Event::Seed { seed });
+    let mut bots = vec![];
+    for _ in 0..bot_count {
+        let pathfinder = state.pathfinder();
+        bots.push(Bot::random_inside(&state.grid, pathfinder, &mut state.rng));
+    }
+    for bot in bots {
+        let bot_handle = state.scene.insert(Object::BotObj(RefCell::new(bot)));
+        state.bots.push(bot_handle);
+    }
+    if let Some(file) = &scene_file {
+        state.load_scene_file(file).unwrap_or_else(|e| panic!("couldn't set up the scene file: {e}"));
+    }
+    state
 }
 
-#[macroquad::main("discrete-grid")]
-async fn main() {
-    const WIDTH: f32 = 1800.0;
-    const HEIGHT: f32 = 1000.0;
-    request_new_screen_size(WIDTH, HEIGHT);
+fn recalc_paths(state: &mut State) {
+    let before = Instant::now();
 
-    let args: Vec<String> = env::args().collect();
-    let grid_dims = (args.get(1).map_or(30, |x| x.parse::<usize>().unwrap()), args.get(2).map_or(30, |x| x.parse::<usize>().unwrap()));
-    let cell_count = grid_dims.0*grid_dims.1;
-    let bot_count = args.get(3).map_or(5.min(cell_count), |x| x.parse::<usize>().unwrap());
-    let obstacle_count = args.get(4).map_or(10.min(cell_count), |x| x.parse::<usize>().unwrap());
+    let bots = state.bots.clone();
+    let bot_count = bots.len();
+    let cell_count = state.grid.size().0 * state.grid.size().1;
+    for bot in bots {
+        if let Object::BotObj(bot) = state.scene.get(bot).unwrap() {
+            bot.borrow_mut().recalc_path(&state.grid);
+        }
+    }
+
+    let after = Instant::now();
+    state.logger.log(Event::timing("recalculating the bot paths", bot_count, after - before).with_m(cell_count));
+}
 
-    let state = {
-        let grid = HexGrid::new(
+fn main() {
+    let args = Args::from_env(&["scene", "seed", "ticks", "svg", "record", "log", "log-level", "bench", "runs", "sizes"]);
+    if args.flag("bench") {
+        bench(&args);
+    } else if args.flag("headless") {
+        headless(&args);
+    } else {
+        macroquad::Window::new("discrete-grid", windowed(args));
+    }
+}
+
+/// Finds the bot paths and walks them for `--ticks` ticks, logging the
+/// timings as usual and printing where the bots ended up.
+fn headless(args: &Args) {
+    let mut state = setup(args);
+    recalc_paths(&mut state);
+
+    let ticks = args.parsed_option("ticks").unwrap_or(HEADLESS_TICKS);
+    let record_dir = args.option("record");
+    let mut recorder = record_dir.map(|dir| Recorder::new(dir).unwrap_or_else(|e| panic!("couldn't record to {dir}: {e}")));
+    let mut record = |state: &State| if let (Some(recorder), Some(dir)) = (recorder.as_mut(), record_dir) {
+        state.record_frame(recorder).unwrap_or_else(|e| panic!("couldn't record to {dir}: {e}"));
+    };
+    record(&state);
+    // only the ticks are timed, not the frames
+    let mut d = std::time::Duration::ZERO;
+    for _ in 0..ticks {
+        let before = Instant::now();
+        let cmd: Box<dyn Command<State>> = Box::new(command::StepForward::new());
+        state.run_command(&cmd);
+        state.recalc_gamestate();
+        d += Instant::now() - before;
+        record(&state);
+    }
+    state.logger.log(Event::timing("ticks", ticks, d));
+
+    let (cols, rows) = state.grid.size();
+    println!("{} bots on a {cols}x{rows} grid, after {} ticks:", state.bots.len(), state.tick);
+    for (i, handle) in state.bots.iter().enumerate() {
+        let Some(Object::BotObj(bot)) = state.scene.get(*handle) else { continue; };
+        let bot = bot.borrow();
+        let (origin, dest) = (state.grid.idx_coords(bot.origin_idx), state.grid.idx_coords(bot.dest_idx));
+        match &bot.path {
+            Ok(path) => {
+                let arrived = if bot.path_step + 1 >= path.len() { "arrived" } else { "on the way" };
+                println!("bot {i}: {origin:?} -> {dest:?}, {} steps, {arrived}", path.len().saturating_sub(1));
+            }
+            Err(e) => println!("bot {i}: {origin:?} -> {dest:?}, no path ({e})"),
+        }
+    }
+
+    if args.flag("svg") {
+        let svg_path = args.option("svg").unwrap_or(render::DEFAULT_SVG_PATH);
+        state.save_svg(svg_path).unwrap_or_else(|e| panic!("couldn't export {svg_path}: {e}"));
+        println!("exported the grid to {svg_path}");
+    }
+    if let (Some(recorder), Some(dir)) = (recorder, record_dir) {
+        println!("recorded {} frames in {dir}", recorder.frames());
+    }
+}
+
+/// Sweeps the bots' pathfinding over growing square grids, `--sizes` giving
+/// their side, and writes the timings to `--bench`. The bot and obstacle
+/// counts are the positional arguments after the grid size, as usual.
+fn bench(args: &Args) {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let mut rng = utils::seeded_rng(seed);
+    let mut bench = Bench::from_args(args);
+    let (bot_count, obstacle_count) = (args.positional(2, 20), args.positional(3, 10));
+    println!("seed: {seed}, {} runs per size", bench.runs());
+    for side in bench.sizes(&[10, 20, 40, 80]) {
+        let grid = new_grid(
+            GridKind::Hex,
             Point::new(WIDTH as f64/8.0, HEIGHT as f64/8.0),
             Point::new(WIDTH as f64 * 7.0/8.0, HEIGHT as f64 * 7.0/8.0),
-            WHITE,
-            grid_dims,
+            (side, side),
             vec![
-                (obstacle_count, Box::new(obstacle::factories::RandomBoulder::new())),
+                (obstacle_count, Box::new(obstacle::factories::RandomBoulder::new(StdRng::from_rng(&mut rng)))),
             ]
         );
-        std::rc::Rc::new(std::sync::RwLock::new(State::new(Box::new(grid::ObservableGridDecorator::new(Box::new(grid))))))
-    };
+        let mut bots: Vec<Bot> = (0..bot_count)
+            .map(|_| Bot::random_inside(&grid, Box::new(bot::BasePathfinder {}), &mut rng))
+            .collect();
+        bench.measure("recalculating the bot paths", side * side, || {
+            for bot in bots.iter_mut() {
+                bot.recalc_path(&grid);
+            }
+        }).m = Some(bot_count);
+    }
+    print!("{}", bench.report());
+
+    let path = args.option("bench").unwrap_or("./bench-discrete-grid.csv");
+    bench.save(path).unwrap_or_else(|e| panic!("couldn't write {path}: {e}"));
+    println!("wrote the timings to {path}");
+}
+
+async fn windowed(args: Args) {
+    request_new_screen_size(WIDTH, HEIGHT);
 
     #[cfg(debug_assertions)]
     {
@@ -395,61 +630,35 @@ async fn main() {
         .init().unwrap();
     }
 
-    {
-        let mut state = state.write().unwrap();
-        let mut bots = vec![];
-        for _ in 0..bot_count {
-            use bot::PathfinderDecorator;
-            bots.push(Bot::random_inside(&state.grid, bot::DebugPathFinder::wrap(Box::new(bot::BasePathfinder {}))));
-        }
-        for bot in bots {
-            let bot_handle = state.objects.insert(Object::BotObj(RefCell::new(bot)));
-            state.bots.push(bot_handle);
-        }
-    }
-
-    let mut state = state.write().unwrap();
+    let scene_path = args.option("scene").unwrap_or(scene_file::DEFAULT_PATH).to_string();
+    let svg_path = args.option("svg").unwrap_or(render::DEFAULT_SVG_PATH).to_string();
+    let mut state = setup(&args);
+    state.prev_mouse_pos = mouse_position();
 
     let recalc_signaller_sender = state.grid_recalc_signal.0.clone();
     let recalc_signaller = move || {
         let sender = recalc_signaller_sender.clone();
         sender.send(()).expect("signal should always go through");
     };
-    let recalc_paths = |state: &mut State| {
-        let before = Instant::now();
-
-        let bots = state.bots.clone();
-        let bot_count = bots.len();
-        let cell_count = state.grid.size().0 * state.grid.size().1;
-        for bot in bots {
-            if let Object::BotObj(bot) = state.objects.get(bot).unwrap() {
-                bot.borrow_mut().recalc_path(&state.grid);
-            }
-        }
-
-        let after = Instant::now();
-        let d = after - before;
-        state.log_line(LogTag::Timing,
-                       &format!("recalculating the bot paths took {}s{}ns for {bot_count} bots on a grid with {cell_count}",
-                                d.as_secs(), d.subsec_nanos()));
-    };
 
     recalc_paths(&mut state);
 
-    state.grid.add_observer(Box::new(recalc_signaller));
+    state.grid.add_observer(Box::new(recalc_signaller.clone()));
 
     loop {
-        state.log_line(LogTag::FrameTime, &format!("{} / {}", get_frame_time(), get_fps()));
+        state.logger.log(Event::FrameTime { frame_time: get_frame_time(), fps: get_fps() });
+        state.hud.update();
 
         if is_quit_requested() { break }
         clear_background(state.clear_color);
 
-        for handle in state.objects.iter() {
-            let object = state.objects.get(handle).unwrap();
-            object.draw();
-        }
-
-        state.grid.draw();
+        let State { viewport, scene, grid, .. } = &mut state;
+        viewport.update(|| {
+            let grid_aabb = grid.compute_aabb();
+            Some(scene.bounds().map_or(grid_aabb, |b| b.union(&grid_aabb)))
+        });
+        viewport.activate();
+        state.render(&mut render::Screen);
 
         { // input handling
 
@@ -464,23 +673,17 @@ async fn main() {
             }
             
             let command: Arc<RwLock<Option<Box<dyn Command<State>>>>> = Arc::new(RwLock::new(None));
-            let mouse_pos = mouse_position();
+            let mouse_pos = state.viewport.mouse_world();
             if mouse_pos != state.prev_mouse_pos {
-                state.log_line(LogTag::Mouse, &format!("pos {},{}", mouse_pos.0, mouse_pos.1));
+                state.logger.log(Event::Mouse { action: MouseAction::Pos, x: mouse_pos.0, y: mouse_pos.1 });
                 state.prev_mouse_pos = mouse_pos;
             }
 
             let grid_interact = |state: &mut State, mut grid_func: Box<dyn FnMut(&mut dyn Grid, usize) -> Option<usize>>| -> Option<usize> {
-                let mut hit_elem = None;
-                for (handle,element) in state.all_elements() {
-                    if element.contains_point(&Vector2D::new(mouse_pos.0, mouse_pos.1)) {
-                        hit_elem = Some(handle);
-                        break;
-                    }
-                }
-                
+                let hit_elem = state.scene.pick(&Vector2D::new(mouse_pos.0, mouse_pos.1));
+
                 if let Some(elem) = hit_elem {
-                    state.log_line(LogTag::Select, &format!("selected {:?}", elem));
+                    state.logger.log(Event::Select { object: format!("{:?}", elem) });
                     None
                 } else {
                     // grid is no longer a registered element
@@ -531,7 +734,7 @@ async fn main() {
                     }
 
                     if *drawing_state != DrawingState::Ground {
-                        state.log_line(LogTag::Mouse, &format!("clicked {},{}", mouse_pos.0, mouse_pos.1));
+                        state.logger.log(Event::Mouse { action: MouseAction::Clicked, x: mouse_pos.0, y: mouse_pos.1 });
 
                         if state.grid.compute_aabb().contains((mouse_pos.0, mouse_pos.1).into()) {
                             state.sel_cell = grid_interact(
@@ -610,11 +813,58 @@ async fn main() {
             }
         }
 
+        set_default_camera();
         draw_text(&format!("Input mode: {:?} | tick: {} | GameState: {:?}", state.input_mode, state.tick, state.gamestate), 20.0, 20.0, 30.0, DARKGRAY);
+        state.hud.draw(&[&state.scene, &state]);
+
+        if is_key_released(KeyCode::F5) {
+            let saved = state.scene_file().save(&scene_path);
+            state.logger.log(Event::file(FileAction::Save, &scene_path, &saved));
+        }
 
-        if is_key_released(KeyCode::R) {
-            println!("{}", state.text_digest())
+        if is_key_released(KeyCode::F6) {
+            let exported = state.save_svg(&svg_path);
+            state.logger.log(Event::file(FileAction::Export, &svg_path, &exported));
+        }
+
+        if is_key_released(KeyCode::F9) {
+            let loaded = SceneFile::load(&scene_path).map_err(|e| e.to_string()).and_then(|file| {
+                state.load_scene_file(&file).map_err(|e| e.to_string())?;
+                Ok(file.grid.is_some())
+            });
+            if loaded == Ok(true) {
+                state.grid.add_observer(Box::new(recalc_signaller.clone()));
+                recalc_paths(&mut state);
+                state.recalc_gamestate();
+            }
+            state.logger.log(Event::file(FileAction::Load, &scene_path, &loaded));
         }
         next_frame().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_failed_load_keeps_the_old_grid() {
+        let log = std::env::temp_dir().join("discrete-grid-failed-load.jsonl");
+        let grid = new_grid(GridKind::Square, Point::new(0.0, 0.0), Point::new(100.0, 100.0), (4, 4), vec![]);
+        let mut state = State::new(grid, utils::seeded_rng(0), Logger::create(&log, log::LevelFilter::Off).unwrap());
+        state.headless = true;
+        let load = |state: &mut State, text: &str| state.load_scene_file(&SceneFile::from_text(text).unwrap());
+
+        load(&mut state, "grid square 4 4 0 0 100 100\nobstacle 1 1\nbot 0 0 3 3\n").unwrap();
+        let bot = state.bots[0];
+        // the same cell twice gets past the file's own checks, the second fails to go in
+        assert!(load(&mut state, "point 5 5\ngrid hex 5 5 0 0 100 100\nobstacle 2 2\nobstacle 2 2\nbot 0 0 4 4\n").is_err());
+
+        assert_eq!(state.grid.size(), (4, 4));
+        assert!(state.grid.idx(state.grid.coords_idx((1, 1)).unwrap()).is_some());
+        assert_eq!(state.bots, vec![bot]);
+        assert!(state.scene.get(bot).is_some());
+        assert_eq!(state.scene.iter().filter(|(_, object)| matches!(object, Object::BotObj(_))).count(), 1);
+        assert_eq!(state.scene.shapes().count(), 0);
+    }
+}
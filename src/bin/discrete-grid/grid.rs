@@ -2,6 +2,8 @@ use stales_geom_viewer::{
     common_traits::*,
     geom::Vertex,
     point::Point,
+    render::Renderer,
+    scene_file::GridKind,
 };
 use euclid::default::Vector2D;
 use macroquad::prelude::*;
@@ -62,6 +64,14 @@ impl Grid for ObservableGridDecorator {
         self.grid.size()
     }
 
+    fn kind(&self) -> GridKind {
+        self.grid.kind()
+    }
+
+    fn corners(&self) -> (Point, Point) {
+        self.grid.corners()
+    }
+
     fn push_obstacle(&mut self, obstacle: Box<dyn obstacle::Obstacle>) -> usize {
         let tmp = self.grid.push_obstacle(obstacle);
         self.invalidate_for_observers();
@@ -86,8 +96,8 @@ impl Select for ObservableGridDecorator {
 }
 
 impl Draw for ObservableGridDecorator {
-    fn draw(&self) {
-        self.grid.draw();
+    fn render(&self, target: &mut dyn Renderer) {
+        self.grid.render(target);
     }
 
     fn vertices(&self) -> Vec<Vertex> {
@@ -116,6 +126,9 @@ pub trait Grid: Debug + Draw + Select + Element {
 
     fn neighbourhood(&self, idx: usize) -> Vec<(usize, bool)>;
     fn size(&self) -> (usize, usize);
+    fn kind(&self) -> GridKind;
+    /// The top left and bottom right corners the grid was laid out in.
+    fn corners(&self) -> (Point, Point);
 
     fn push_obstacle(&mut self, obstacle: Box<dyn obstacle::Obstacle>) -> usize;
     fn remove_obstacle(&mut self, idx: usize) -> Box<dyn obstacle::Obstacle>;
@@ -200,7 +213,7 @@ impl HexGrid {
         }
         let mut grid = Self {
             topleft, botright, clr, size, strides,
-            verts: verts.into_iter().map(|p| Vertex::new(p.x() as f32, p.y() as f32, None)).collect(),
+            verts: verts.into_iter().map(Vertex::from).collect(),
             array: [None].into_iter().cycle().take(size.0*size.1).collect(),
             obstacles: vec![],
             cell_wh,
@@ -336,6 +349,8 @@ impl Grid for HexGrid {
     }
 
     fn size(&self) -> (usize, usize) { self.size }
+    fn kind(&self) -> GridKind { GridKind::Hex }
+    fn corners(&self) -> (Point, Point) { (self.topleft, self.botright) }
 
     fn push_obstacle(&mut self, obstacle: Box<dyn obstacle::Obstacle>) -> usize {
         let l = self.obstacles.len();
@@ -358,12 +373,12 @@ impl Grid for HexGrid {
 }
 
 impl Draw for HexGrid {
-    fn draw(&self) {
+    fn render(&self, target: &mut dyn Renderer) {
         for vs in self.verts.chunks(6) {
             let pos: Vec<_> = vs.iter().map(|v| v.pos).collect();
             const LUT: [(usize, usize); 6] = [(0,1),(0,2),(1,3),(2,4),(3,5),(4,5)];
             for (a,b) in LUT {
-                draw_line(pos[a].x, pos[a].y, pos[b].x, pos[b].y, 2.0, self.clr);
+                target.line(pos[a].to_array().into(), pos[b].to_array().into(), 2.0, self.clr);
             }
         }
         for idx in 0..self.size.0*self.size.1 {
@@ -371,7 +386,7 @@ impl Draw for HexGrid {
             let pos = pos.unwrap();
             let r = self.array[idx].map(|_| 10.0);
             if let Some(r) = r {
-                draw_circle((pos.0 + self.cell_wh.0/2.0) as f32, (pos.1 + self.cell_wh.1/2.0) as f32, r, self.clr);
+                target.circle(vec2((pos.0 + self.cell_wh.0/2.0) as f32, (pos.1 + self.cell_wh.1/2.0) as f32), r, self.clr);
             };
         }
     }
@@ -384,8 +399,8 @@ impl Draw for HexGrid {
 impl Select for HexGrid {
     fn compute_aabb(&self) -> euclid::default::Box2D<f32> {
         euclid::default::Box2D::new(
-            euclid::default::Point2D::new(self.topleft.x()  as f32, self.topleft.y()  as f32),
-            euclid::default::Point2D::new(self.botright.x() as f32, self.botright.y() as f32),
+            self.topleft.into(),
+            self.botright.into(),
             )
     }
 
@@ -397,7 +412,7 @@ impl Select for HexGrid {
         //     return length(max(d,0.0)) + min(max(d.x,d.y),0.0);
         // }
         let midpoint  = (self.topleft + self.botright) / 2.0;
-        let point_rel = Point::new(global_sample_point.x as f64, global_sample_point.y as f64) - midpoint;
+        let point_rel = Point::from(*global_sample_point) - midpoint;
         let halfdelta = (self.botright - self.topleft) / 2.0;
         let d = Point::new(point_rel.x().abs(), point_rel.y().abs()) - halfdelta;
         let length = Point::new(d.x().max(0.0), d.y().max(0.0)).magnitude();
@@ -440,7 +455,7 @@ impl SquareGrid {
 
         let mut grid = Self {
             topleft, botright, clr, size, strides,
-            verts: verts.into_iter().map(|p| Vertex::new(p.x() as f32, p.y() as f32, None)).collect(),
+            verts: verts.into_iter().map(Vertex::from).collect(),
             array: [None].into_iter().cycle().take(size.0*size.1).collect(),
             obstacles: vec![],
         };
@@ -544,6 +559,8 @@ impl Grid for SquareGrid {
     }
 
     fn size(&self) -> (usize, usize) { self.size }
+    fn kind(&self) -> GridKind { GridKind::Square }
+    fn corners(&self) -> (Point, Point) { (self.topleft, self.botright) }
 
     fn push_obstacle(&mut self, obstacle: Box<dyn obstacle::Obstacle>) -> usize {
         let l = self.obstacles.len();
@@ -566,11 +583,11 @@ impl Grid for SquareGrid {
 }
 
 impl Draw for SquareGrid {
-    fn draw(&self) {
+    fn render(&self, target: &mut dyn Renderer) {
         for vs in self.verts.chunks(2) {
-            draw_line(vs[0].pos.x, vs[0].pos.y,
-                      vs[1].pos.x, vs[1].pos.y,
-                      1.0, self.clr);
+            target.line(vs[0].pos.to_array().into(),
+                        vs[1].pos.to_array().into(),
+                        1.0, self.clr);
         }
 
         for (idx, _) in self.array.iter().enumerate().filter(|(_,x)| x.is_some()) {
@@ -595,8 +612,8 @@ impl Draw for SquareGrid {
 
             if let Some((topl,botr)) = idx_box(idx) {
                 let s = botr - topl;
-                let (w,h) = (s.x() as f32, s.y() as f32);
-                draw_rectangle(topl.x() as f32, topl.y() as f32, w, h, self.clr);
+                let (topl, s) = (Vec2::from(topl), Vec2::from(s));
+                target.rectangle(topl, s, self.clr);
             }
         }
     }
@@ -609,8 +626,8 @@ impl Draw for SquareGrid {
 impl Select for SquareGrid {
     fn compute_aabb(&self) -> euclid::default::Box2D<f32> {
         euclid::default::Box2D::new(
-            euclid::default::Point2D::new(self.topleft.x()  as f32, self.topleft.y()  as f32),
-            euclid::default::Point2D::new(self.botright.x() as f32, self.botright.y() as f32),
+            self.topleft.into(),
+            self.botright.into(),
             )
     }
 
@@ -622,7 +639,7 @@ impl Select for SquareGrid {
         //     return length(max(d,0.0)) + min(max(d.x,d.y),0.0);
         // }
         let midpoint  = (self.topleft + self.botright) / 2.0;
-        let point_rel = Point::new(global_sample_point.x as f64, global_sample_point.y as f64) - midpoint;
+        let point_rel = Point::from(*global_sample_point) - midpoint;
         let halfdelta = (self.botright - self.topleft) / 2.0;
         let d = Point::new(point_rel.x().abs(), point_rel.y().abs()) - halfdelta;
         let length = Point::new(d.x().max(0.0), d.y().max(0.0)).magnitude();
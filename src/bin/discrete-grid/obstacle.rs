@@ -149,7 +149,7 @@ impl HeterogenousObstacle for Wall {
 
 pub mod factories {
     use super::{Factory, FactoryError, FactoryResult, Grid, NullObstacle, Obstacle};
-    use rand::random_range;
+    use rand::{rngs::StdRng, Rng};
 
     #[derive(Debug)]
     pub struct Replicate {
@@ -170,14 +170,14 @@ pub mod factories {
         }
     }
 
-    #[derive(Debug, Clone)]
-    pub struct RandomBoulder();
+    #[derive(Debug)]
+    pub struct RandomBoulder(StdRng);
     
     impl RandomBoulder {
-        pub fn new() -> Self { Self() }
+        pub fn new(rng: StdRng) -> Self { Self(rng) }
         fn new_object_inner(&mut self, grid: &dyn Grid, depth: usize) -> FactoryResult {
             use super::Boulder;
-            let idx = random_range(0..grid.size().0*grid.size().1);
+            let idx = self.0.random_range(0..grid.size().0*grid.size().1);
             if let Some(_) = grid.idx(idx) {
                 if depth > 10 { Err(FactoryError::Occluded(vec![idx])) }
                 else { Self::new_object_inner(self, grid, depth+1) }
@@ -1,198 +1,232 @@
 use macroquad::prelude::*;
 use std::{
-    default::Default, time::Instant,
+    default::Default,
+    collections::VecDeque,
     iter::Iterator,
     ops::Range,
-    io::Write,
 };
 
-use stales_geom_viewer::common_traits::*;
-use euclid::{default::Vector2D, *};
+use stales_geom_viewer::{camera::Viewport, cli::Args, common_traits::Draw, hud::Hud, logging::{Event, FileAction, Logger, MouseAction}, render::{self, Svg}, scene::{Object, Scene}, scene_file::{self, SceneFile}, triangulation::{Step, Triangulation}, utils};
+use euclid::default::Vector2D;
 
 type Color = macroquad::color::Color;
 
 pub use stales_geom_viewer::geom::{self, *, Vertex};
 
-enum Object {
-    Point(geom::Vertex),
-    CircleObj(geom::Circle),
-    LineObj(geom::Line2D),
-    PolyObj(geom::Polygon),
-}
-
-impl Draw for Object {
-    fn draw(&self) {
-        match self {
-            Object::Point(p) => p.draw(),
-            Object::CircleObj(c) => c.draw(),
-            Object::LineObj(l) => l.draw(),
-            Object::PolyObj(p) => p.draw(),
-        }
-    }
-    fn vertices(&self) -> Vec<geom::Vertex> {
-        match self {
-            Object::Point(p) => p.vertices(),
-            Object::CircleObj(c) => c.vertices(),
-            Object::LineObj(l) => l.vertices(),
-            Object::PolyObj(p) => p.vertices(),
-        }
-    }
-}
-
 #[derive(Default)]
 struct State {
-    pub objects: Vec<Object>,
+    pub scene: Scene,
     pub clear_color: Color,
+    pub viewport: Viewport,
+    pub hud: Hud,
+    pub triangulation: Triangulation,
+    /// What's left to put into the triangulation, one step per `S`.
+    pub triangulation_steps: VecDeque<Step>,
+    pub show_triangulation: bool,
 }
 
 impl State {
-    fn add_line(&mut self, l: geom::Line2D) {
-        self.objects.push(Object::LineObj(l));
-    }
-    fn add_circle(&mut self, c: geom::Circle) {
-        self.objects.push(Object::CircleObj(c));
-    }
-    fn add_poly(&mut self, p: geom::Polygon) {
-        self.objects.push(Object::PolyObj(p))
+    fn save_svg(&self, path: &str) -> std::io::Result<()> {
+        let mut svg = Svg::with_background(self.clear_color);
+        self.scene.render(&mut svg);
+        if self.show_triangulation {
+            self.triangulation_poly().render(&mut svg);
+        }
+        svg.save(path)
     }
-    fn all_elements(&self) -> impl Iterator<Item = &dyn Element> {
-        self.objects.iter().flat_map(|x| {
-            match x {
-                Object::Point(_p) => None,
-                Object::CircleObj(c) => Some(c as &dyn Element),
-                Object::LineObj(l) => Some(l as &dyn Element),
-                Object::PolyObj(_p) => None,
-            }
-        })
+
+    /// Starts triangulating the scene as it is now, with nothing inserted yet.
+    fn restart_triangulation(&mut self) {
+        self.triangulation = Triangulation::new();
+        self.triangulation_steps = triangulation_steps(&self.scene).into();
     }
-    fn text_digest(&self) -> String {
-        let line_cnt = self.objects.iter().filter(|x| matches!(x, Object::LineObj(_))).count();
-        let circle_cnt = self.objects.iter().filter(|x| matches!(x, Object::CircleObj(_))).count();
-        let vertex_cnt = self.objects.iter().map(Draw::vertices).fold(0usize, |acc,verts| acc + verts.len());
-        let frametime = get_frame_time();
-        format!(r"
-num. of lines: {line_cnt}
-num. of circles: {circle_cnt}
-num. of vertices: {vertex_cnt}
-frametime: {frametime}
-")
+
+    fn triangulation_poly(&self) -> Polygon {
+        self.triangulation.to_polygon(GRAY, ORANGE, SKYBLUE.with_alpha(0.1))
     }
 }
 
-fn square_grid(spacing: f32, breadth: (Range<f32>, Range<f32>)) -> Vec<Line2D> {
-    let mut x = breadth.0.start;
-    let mut y = breadth.1.start;
-
-    let mut lines = vec![];
-    while breadth.1.contains(&y) {
-        let ny = y + spacing;
-        while breadth.0.contains(&x) {
-            let nx = x + spacing;
-            if breadth.0.contains(&nx) {
-                lines.push(Line2D {
-                    a: Vertex::new(x,y, Some(RED)),
-                    b: Vertex::new(nx, y, Some(RED)),
-                    thickness: 1.0,
-                });
-            }
-            if breadth.1.contains(&ny) {
-                lines.push(Line2D {
-                    a: Vertex::new(x, y, Some(YELLOW.with_alpha(0.1))),
-                    b: Vertex::new(x, ny, Some(YELLOW)),
-                    thickness: 2.0,
-                });
-            }
-            x = nx;
+/// Every vertex of the scene's objects, then its lines and polygon edges as
+/// constraints, so the obstacles end up in the mesh.
+fn triangulation_steps(scene: &Scene) -> Vec<Step> {
+    let mut steps: Vec<Step> = scene.iter()
+        .flat_map(|(_, object)| object.vertices())
+        .map(|v| Step::Insert(v.pos.into()))
+        .collect();
+    for (_, object) in scene.iter() {
+        match object {
+            Object::LineObj(line) => steps.push(Step::Segment(line.a.pos.into(), line.b.pos.into())),
+            Object::PolyObj(poly) => steps.extend(poly.edges.iter().map(|&(a, b, _)| {
+                Step::Segment(poly.verts[a].pos.into(), poly.verts[b].pos.into())
+            })),
+            Object::Point(_) | Object::CircleObj(_) => {}
         }
-        x = breadth.0.start;
-        y = ny;
-    };
-    lines
+    }
+    steps
 }
 
-#[derive(Debug)]
-enum LogTag {
-    Mouse, FrameTime, Select,
-}
+// macroquad's default window size
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 600.0;
 
-#[macroquad::main("BasicShapes")]
-async fn main() {
+/// The shapes, loaded from `--scene` or the animated demo. The demo's line
+/// and rectangle handles come back too so the loop can move them.
+/// Doesn't touch macroquad, so it works without a window.
+fn setup(args: &Args, seed: u64) -> (State, Option<genmap::Handle>, Option<genmap::Handle>) {
     let mut state: State = Default::default();
-    let startup = Instant::now();
-    let mut prev_mouse_pos = mouse_position();
-
-    state.add_line(Line2D {
-        a: Vertex::new(40.0, 40.0, Some(BLUE)),
-        b: Vertex::new(100.0, 100.0, None),
-        thickness: 1.0,
-    });
-
-    state.add_circle(geom::Circle { center: Vertex::new(screen_width() - 30.0, screen_height() - 30.0, Some(YELLOW)), radius: 15.0 });
-    state.add_poly(random_polar_poly(Vertex::new(screen_width()/2.0, screen_height()/2.0, None), 50, 20.0..60.0));
-    state.add_poly(rect_poly(
-        Vector2D::new(screen_width()/2.0 - 60.0, screen_height()/2.0 - 30.0),
-        Vector2D::new(screen_width()/2.0 + 60.0, screen_height()/2.0 + 30.0),
-        GREEN,
-        YELLOW,
-        4.0));
-    let rect_idx = state.objects.len() - 1;
-
-    let mut logfile = std::fs::File::create("./log.txt").expect("can't create \"./log.txt\" log file!");
+    let mut rng = utils::seeded_rng(seed);
 
-    loop {
-        let tick_time = {
-            let t = Instant::now().duration_since(startup);
-            (t.as_secs(), t.subsec_nanos())
-        };
+    // the demo shapes animate, loaded ones stay put
+    let (mut line_handle, mut rect_handle) = (None, None);
+    if let Some(scene_path) = args.option("scene") {
+        let file = SceneFile::load(scene_path).unwrap_or_else(|e| panic!("couldn't load {scene_path}: {e}"));
+        file.add_to(&mut state.scene);
+    } else {
+        line_handle = Some(state.scene.add_line(Line2D {
+            a: Vertex::new(40.0, 40.0, Some(BLUE)),
+            b: Vertex::new(100.0, 100.0, None),
+            thickness: 1.0,
+        }));
+
+        state.scene.add_circle(geom::Circle { center: Vertex::new(WIDTH - 30.0, HEIGHT - 30.0, Some(YELLOW)), radius: 15.0 });
+        state.scene.add_poly(random_polar_poly(&mut rng, Vertex::new(WIDTH/2.0, HEIGHT/2.0, None), 50, 20.0..60.0));
+        rect_handle = Some(state.scene.add_poly(rect_poly(
+            Vector2D::new(WIDTH/2.0 - 60.0, HEIGHT/2.0 - 30.0),
+            Vector2D::new(WIDTH/2.0 + 60.0, HEIGHT/2.0 + 30.0),
+            GREEN,
+            YELLOW,
+            4.0)));
+    }
+    (state, line_handle, rect_handle)
+}
+
+fn main() {
+    let args = Args::from_env(&["scene", "seed", "svg", "log", "log-level"]);
+    if args.flag("headless") {
+        headless(&args);
+    } else {
+        macroquad::Window::new("BasicShapes", windowed(args));
+    }
+}
+
+/// Builds the scene and prints its digest.
+fn headless(args: &Args) {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let (mut state, _, _) = setup(args, seed);
+    println!("seed: {seed}");
+    println!("{}", state.scene.text_digest());
+
+    state.restart_triangulation();
+    while let Some(step) = state.triangulation_steps.pop_front() {
+        state.triangulation.apply(step);
+    }
+    println!("triangulation: {} vertices, {} triangles, {} constrained edges",
+             state.triangulation.len(), state.triangulation.triangles().len(), state.triangulation.constraints().len());
+
+    if args.flag("svg") {
+        let svg_path = args.option("svg").unwrap_or(render::DEFAULT_SVG_PATH);
+        state.save_svg(svg_path).unwrap_or_else(|e| panic!("couldn't export {svg_path}: {e}"));
+        println!("exported the scene to {svg_path}");
+    }
+}
+
+async fn windowed(args: Args) {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let scene_path = args.option("scene").unwrap_or(scene_file::DEFAULT_PATH).to_string();
+    let svg_path = args.option("svg").unwrap_or(render::DEFAULT_SVG_PATH).to_string();
+    let (mut state, mut line_handle, mut rect_handle) = setup(&args, seed);
+    let mut prev_mouse_pos = state.viewport.mouse_world();
 
-        let mut log_line = |tag: LogTag, msg: &str| {
-            writeln!(&mut logfile, "({tag:?}) [{}s{}ns]: {msg}", tick_time.0, tick_time.1).expect("couldn't write log line")
-        };
+    let mut logger = Logger::from_args(&args, "./log.jsonl");
+    logger.log(Event::Seed { seed });
 
-        log_line(LogTag::FrameTime, &format!("{} / {}", get_frame_time(), get_fps()));
+    loop {
+        logger.log(Event::FrameTime { frame_time: get_frame_time(), fps: get_fps() });
+        state.hud.update();
 
         if is_quit_requested() { break }
         clear_background(state.clear_color);
 
-        for object in &state.objects {
-            object.draw();
+        state.viewport.update(|| state.scene.bounds());
+        state.viewport.activate();
+        state.scene.draw();
+
+        // the triangulation is of the scene when T was pressed, S adds the
+        // next point or segment and Enter all that's left
+        if is_key_released(KeyCode::T) {
+            state.show_triangulation = !state.show_triangulation;
+            if state.show_triangulation {
+                state.restart_triangulation();
+            }
+        }
+        if state.show_triangulation {
+            if is_key_released(KeyCode::S) {
+                if let Some(step) = state.triangulation_steps.pop_front() {
+                    state.triangulation.apply(step);
+                }
+            }
+            if is_key_released(KeyCode::Enter) {
+                while let Some(step) = state.triangulation_steps.pop_front() {
+                    state.triangulation.apply(step);
+                }
+            }
+            state.triangulation_poly().draw();
+            state.triangulation.cavity_polygon(YELLOW.with_alpha(0.3)).draw();
         }
 
         { // Mouse handling
-            let mouse_pos = mouse_position();
+            let mouse_pos = state.viewport.mouse_world();
             if mouse_pos != prev_mouse_pos {
-                log_line(LogTag::Mouse, &format!("pos {},{}", mouse_pos.0, mouse_pos.1));
+                logger.log(Event::Mouse { action: MouseAction::Pos, x: mouse_pos.0, y: mouse_pos.1 });
                 prev_mouse_pos = mouse_pos;
             }
 
             if is_mouse_button_pressed(MouseButton::Left) {
-                log_line(LogTag::Mouse, &format!("clicked {},{}", mouse_pos.0, mouse_pos.1));
+                logger.log(Event::Mouse { action: MouseAction::Clicked, x: mouse_pos.0, y: mouse_pos.1 });
 
-                for element in state.all_elements() {
-                    if element.contains_point(&Vector2D::new(mouse_pos.0, mouse_pos.1)) {
-                        log_line(LogTag::Select, &format!("selected {:?}", element));
-                    }
+                for handle in state.scene.hits(&Vector2D::new(mouse_pos.0, mouse_pos.1)) {
+                    logger.log(Event::Select { object: format!("{:?}", state.scene.get(handle).unwrap()) });
                 }
 
             }
         }
 
-        if let Object::LineObj(line) = state.objects.get_mut(0).unwrap() {
+        if is_key_released(KeyCode::F5) {
+            let saved = SceneFile::from_scene(&state.scene).save(&scene_path);
+            logger.log(Event::file(FileAction::Save, &scene_path, &saved));
+        }
+
+        if is_key_released(KeyCode::F6) {
+            logger.log(Event::file(FileAction::Export, &svg_path, &state.save_svg(&svg_path)));
+        }
+
+        if is_key_released(KeyCode::F9) {
+            let loaded = SceneFile::load(&scene_path);
+            if let Ok(file) = &loaded {
+                state.scene.clear_layer(Scene::<Object>::DEFAULT_LAYER);
+                file.add_to(&mut state.scene);
+                (line_handle, rect_handle) = (None, None);
+            }
+            logger.log(Event::file(FileAction::Load, &scene_path, &loaded));
+        }
+
+        if let Some(Object::LineObj(line)) = line_handle.and_then(|h| state.scene.get_mut(h)) {
             line.a.pos += Vector2D::new(10.0*get_frame_time(), 0.0);
         }
 
-        if let Object::PolyObj(rect) = state.objects.get_mut(rect_idx).unwrap() {
+        if let Some(Object::PolyObj(rect)) = rect_handle.and_then(|h| state.scene.get_mut(h)) {
             let center = rect.verts.iter().fold(Vector2D::zero(), |lhs,rhs| lhs + rhs.pos).component_div(Vector2D::splat(rect.verts.len() as f32));
-            let delta = Vector2D::from(mouse_position()) - center;
+            let delta = Vector2D::from(state.viewport.mouse_world()) - center;
             for vert in rect.verts.iter_mut() {
                 vert.pos += delta * 2.0 * get_frame_time()
             }
         }
 
-        draw_text("IT WORKS!", 20.0, 20.0, 30.0, DARKGRAY);
-
-        if is_key_released(KeyCode::R) {
-            println!("{}", state.text_digest())
+        set_default_camera();
+        if state.show_triangulation {
+            state.hud.draw(&[&state.scene, &state.triangulation]);
+        } else {
+            state.hud.draw(&[&state.scene]);
         }
         next_frame().await
     }
@@ -220,7 +254,7 @@ fn rect_poly(ne: Vector2D<f32>, sw: Vector2D<f32>, fill_clr: Color, stroke_clr:
     }
 }
 
-fn random_polar_poly(origin: Vertex, vert_count: usize, dist_range: Range<f32>) -> Polygon {
+fn random_polar_poly(rng: &mut impl ::rand::Rng, origin: Vertex, vert_count: usize, dist_range: Range<f32>) -> Polygon {
     let mut verts = Vec::with_capacity(vert_count);
     let mut edges = vec![];
     let mut faces = vec![];
@@ -228,18 +262,15 @@ fn random_polar_poly(origin: Vertex, vert_count: usize, dist_range: Range<f32>)
     verts.push(origin.clone());
     for i in 1..vert_count {
         let a = (i as f32)*std::f32::consts::TAU/(vert_count as f32);
-        let d = rand::gen_range(dist_range.start, dist_range.end);
+        let d = rng.random_range(dist_range.clone());
         verts.push(Vertex::new(
             origin.pos.x + a.cos()*d,
             origin.pos.y + a.sin()*d,
-            Some(Color::from_vec(glam::Vec4::from_array(
-                random_color::RandomColor::new().to_f32_rgba_array())))
+            Some(utils::random_color(rng))
                 ));
         if i > 1 && i < vert_count {
-            edges.push((i-1, i, Color::from_vec(glam::Vec4::from_array(
-                random_color::RandomColor::new().to_f32_rgba_array()))));
-            faces.push((0, i-1, i, Color::from_vec(glam::Vec4::from_array(
-                random_color::RandomColor::new().to_f32_rgba_array()))));
+            edges.push((i-1, i, utils::random_color(rng)));
+            faces.push((0, i-1, i, utils::random_color(rng)));
         }
     }
 
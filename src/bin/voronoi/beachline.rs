@@ -7,10 +7,9 @@ use petgraph::{
 use std::fmt::Display;
 
 use crate::Point;
+use stales_geom_viewer::{dcel::HalfEdgeId, predicates::orient2d};
 type TripleSite = (Point, Point, Point);
 
-const NIL: usize = !0;
-
 #[derive(Debug)]
 pub struct DummyEdgeWeight;
 impl Display for DummyEdgeWeight {
@@ -57,7 +56,7 @@ impl BeachNode {
 }
 
 #[derive(Debug, Clone)]
-pub struct Breakpoint { pub left: Point, pub right: Point, pub edge_idx: usize, }
+pub struct Breakpoint { pub left: Point, pub right: Point, pub edge_idx: HalfEdgeId, }
 impl Breakpoint {
     pub fn get_x(&self, yl: f64) -> f64 {
         let ax = self.left.x();
@@ -98,16 +97,10 @@ impl Breakpoint {
     }
     
     // see http://www.kmschaal.de/Diplomarbeit_KevinSchaal.pdf, pg 27
+    // (the condition there is that a, b, c turn clockwise)
     pub fn breakpoints_converge(triple_site: TripleSite) -> bool {
         let (a, b, c) = triple_site;
-        let ax = a.x();
-        let ay = a.y();
-        let bx = b.x();
-        let by = b.y();
-        let cx = c.x();
-        let cy = c.y();
-
-        (ay - by) * (bx - cx) > (by - cy) * (ax - bx)
+        orient2d(a, b, c) < 0.
     }
 }
 
@@ -125,7 +118,7 @@ impl Display for BeachItem {
         let or_nothing = |opt: Option<usize>| opt.map_or("nothing".to_string(), |x| x.to_string());
         match self {
             BeachItem::Arc(ref arc) => write!(f, "arc focus {:?} event {}", arc.site, or_nothing(arc.site_event)),
-            BeachItem::Breakpoint(ref bp) => write!(f, "breakp l {:?} r {:?} edge {}", bp.left, bp.right, bp.edge_idx),
+            BeachItem::Breakpoint(ref bp) => write!(f, "breakp l {:?} r {:?} edge {}", bp.left, bp.right, bp.edge_idx.index()),
         }
     }
 }
@@ -279,7 +272,7 @@ impl Beachline {
         })
     }
 
-    pub fn get_edge(&self, node: usize) -> usize {
+    pub fn get_edge(&self, node: usize) -> HalfEdgeId {
         if let BeachItem::Breakpoint(ref bp) = self.graph[node_index(node)].item {
             bp.edge_idx
         } else {
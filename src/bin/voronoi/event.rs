@@ -124,4 +124,14 @@ impl EventQueue {
     pub fn remove(&mut self, event_id: usize) {
         self.removed_event_ids.insert(event_id);
     }
+
+    /// The events still to come, leaving out removed circle events that
+    /// haven't been popped yet.
+    pub fn len(&self) -> usize {
+        self.events.len().saturating_sub(self.removed_event_ids.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
@@ -0,0 +1,118 @@
+//! The region the diagram is clipped to: a simple polygon, by default the
+//! window.
+
+use std::{error::Error, fmt, str::FromStr};
+use stales_geom_viewer::{arrangement, geom::Segment, point::Point, predicates::orient2d};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    /// Counter-clockwise, none repeated.
+    corners: Vec<Point>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegionError {
+    /// A corner that isn't an `x,y` pair of numbers.
+    Corner(String),
+    TooFewCorners,
+    /// Edges that meet other than at the corner between them.
+    NotSimple,
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::Corner(corner) => write!(f, "{corner:?} isn't an x,y pair"),
+            RegionError::TooFewCorners => write!(f, "a region needs three corners or more"),
+            RegionError::NotSimple => write!(f, "the region's edges cross or touch"),
+        }
+    }
+}
+
+impl Error for RegionError {}
+
+impl Region {
+    /// The polygon through `corners`, in either direction.
+    pub fn new(mut corners: Vec<Point>) -> Result<Self, RegionError> {
+        corners.dedup();
+        if corners.len() > 1 && corners.first() == corners.last() {
+            corners.pop();
+        }
+        let n = corners.len();
+        if n < 3 { return Err(RegionError::TooFewCorners); }
+
+        let mut region = Self { corners };
+        // each corner is where the edges on either side of it meet, and nothing else is
+        let edges: Vec<Segment> = region.edges().collect();
+        let meetings = arrangement::intersections(&edges);
+        let simple = meetings.len() == n && meetings.iter().all(|meeting| match meeting.segments[..] {
+            [s, t] if t == s + 1 => meeting.point == region.corners[t],
+            [0, t] if t == n - 1 => meeting.point == region.corners[0],
+            _ => false,
+        });
+        if !simple { return Err(RegionError::NotSimple); }
+        if region.signed_area() < 0.0 {
+            region.corners.reverse();
+        }
+        Ok(region)
+    }
+
+    /// The rectangle from the origin to `(width, height)`, like the window.
+    pub fn rectangle(width: f64, height: f64) -> Self {
+        let corners = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+        Self { corners: corners.map(|(x, y)| Point::new(x, y)).to_vec() }
+    }
+
+    pub fn corners(&self) -> &[Point] {
+        &self.corners
+    }
+
+    /// The edges, counter-clockwise.
+    pub fn edges(&self) -> impl Iterator<Item = Segment> + '_ {
+        let n = self.corners.len();
+        (0..n).map(move |i| [self.corners[i], self.corners[(i + 1) % n]])
+    }
+
+    pub fn signed_area(&self) -> f64 {
+        self.edges().map(|[a, b]| a.cross(b)).sum::<f64>() / 2.0
+    }
+
+    /// Whether `p` is inside; a point on the boundary may go either way.
+    pub fn contains(&self, p: Point) -> bool {
+        let mut winding = 0;
+        for [a, b] in self.edges() {
+            if a.y() <= p.y() {
+                if b.y() > p.y() && orient2d(a, b, p) > 0.0 { winding += 1; }
+            } else if b.y() <= p.y() && orient2d(a, b, p) < 0.0 {
+                winding -= 1;
+            }
+        }
+        winding != 0
+    }
+
+    /// The center and radius of a circle the region is inside.
+    pub fn bounding_circle(&self) -> (Point, f64) {
+        let (mut min, mut max) = (self.corners[0], self.corners[0]);
+        for p in &self.corners {
+            min = Point::new(min.x().min(p.x()), min.y().min(p.y()));
+            max = Point::new(max.x().max(p.x()), max.y().max(p.y()));
+        }
+        (min.lerp(&max, 0.5), (max - min).magnitude() / 2.0)
+    }
+}
+
+/// Corners as `x,y` pairs separated by `;`, e.g. `0,0;800,0;400,600`.
+impl FromStr for Region {
+    type Err = RegionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let corner = |pair: &str| {
+            let (x, y) = pair.split_once(',').ok_or_else(|| RegionError::Corner(pair.to_string()))?;
+            match (x.trim().parse(), y.trim().parse()) {
+                (Ok(x), Ok(y)) => Ok(Point::new(x, y)),
+                _ => Err(RegionError::Corner(pair.to_string())),
+            }
+        };
+        Self::new(s.split(';').map(corner).collect::<Result<_, _>>()?)
+    }
+}
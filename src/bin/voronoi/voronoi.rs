@@ -1,119 +1,116 @@
 use macroquad::prelude::*;
-use genmap::GenMap;
 
 pub mod event;
 pub mod beachline;
-pub mod dcel;
+pub mod region;
 
 use event::*;
 use beachline::{BeachItem, Beachline, Breakpoint};
+use region::Region;
 use petgraph::{graph::node_index, visit::{EdgeRef, IntoNodeReferences}};
 use stales_geom_viewer::point::Point;
 
 use std::{
-    cmp::{Ord, Ordering}, collections::HashMap, default::Default, fmt::Debug, io::Write, iter::Iterator, time::Instant
+    cmp::{Ord, Ordering}, collections::{hash_map::Entry, HashMap}, default::Default, fmt::Debug, io::Write, iter::Iterator, time::Instant
 };
 
 use stales_geom_viewer::{
     utils,
+    bench::Bench,
     common_traits::*,
+    camera::Viewport,
+    cli::Args,
+    dcel::{Dcel, HalfEdgeId},
+    logging::{self, FileAction, Logger, MouseAction},
     geom::{self, *, Vertex},
+    hud::{Hud, Metrics},
+    predicates::orient2d,
+    raster::{Raster, Recorder},
+    render::{self, Renderer, Svg},
+    scene::{Object, Scene},
+    arrangement::{self, Overlap},
+    scene_file::{self, SceneFile, SceneFileError},
+    triangulation::Triangulation,
 };
 use euclid::default::Vector2D;
+use ::rand::{rngs::StdRng, Rng};
 
 type Color = macroquad::color::Color;
 
-enum Object {
-    Point(geom::Vertex),
-    CircleObj(geom::Circle),
-    LineObj(geom::Line2D),
-    PolyObj(geom::Polygon),
-}
-
-impl Draw for Object {
-    fn draw(&self) {
-        match self {
-            Object::Point(p) => p.draw(),
-            Object::CircleObj(c) => c.draw(),
-            Object::LineObj(l) => l.draw(),
-            Object::PolyObj(p) => p.draw(),
-        }
-    }
-    fn vertices(&self) -> Vec<geom::Vertex> {
-        match self {
-            Object::Point(p) => p.vertices(),
-            Object::CircleObj(c) => c.vertices(),
-            Object::LineObj(l) => l.vertices(),
-            Object::PolyObj(p) => p.vertices(),
-        }
-    }
-}
-
 struct State {
-    pub objects: GenMap<Object>,
+    pub scene: Scene,
     pub clear_color: Color,
-    pub startup: Instant,
     pub prev_mouse_pos: (f32, f32),
-    pub logfile: std::fs::File,
+    pub logger: Logger,
+    pub viewport: Viewport,
+    pub hud: Hud,
+    pub scene_path: String, // saved to and loaded from with F5 and F9
+    pub svg_path: String, // exported to with F6
+    pub show_delaunay: bool, // toggled with D
+    pub show_overlay: bool, // toggled with O
+    pub clip: Region, // the diagram is cut to this, set with --clip
+    pub rng: StdRng,
 }
 
-impl Default for State {
-    fn default() -> Self {
+impl State {
+    fn new(seed: u64, logger: Logger) -> Self {
         Self {
-            objects: GenMap::with_capacity(1000),
+            scene: Scene::with_capacity(1000),
             clear_color: BLACK,
-            startup: Instant::now(),
-            prev_mouse_pos: mouse_position(),
-            logfile: std::fs::File::create("./log.txt").expect("can't create \"./log.txt\" log file!"),
+            prev_mouse_pos: (0.0, 0.0),
+            logger,
+            viewport: Viewport::default(),
+            hud: Hud::new(),
+            scene_path: scene_file::DEFAULT_PATH.to_string(),
+            svg_path: render::DEFAULT_SVG_PATH.to_string(),
+            show_delaunay: false,
+            show_overlay: false,
+            clip: window(),
+            rng: utils::seeded_rng(seed),
         }
     }
 }
 
 impl State {
-    fn add_line(&mut self, l: geom::Line2D) -> genmap::Handle {
-        self.objects.insert(Object::LineObj(l))
-
-    }
-    fn add_circle(&mut self, c: geom::Circle) -> genmap::Handle {
-        self.objects.insert(Object::CircleObj(c))
-    }
-    fn add_poly(&mut self, p: geom::Polygon) -> genmap::Handle {
-        self.objects.insert(Object::PolyObj(p))
-    }
-    fn all_elements(&self) -> impl Iterator<Item = (genmap::Handle, &dyn Element)> {
-        self.objects.iter().flat_map(|x| {
-            self.objects.get(x).and_then(|obj|
-                match obj {
-                    Object::Point(_p) => None,
-                    Object::CircleObj(c) => Some(c as &dyn Element),
-                    Object::LineObj(l) => Some(l as &dyn Element),
-                    Object::PolyObj(_p) => None,
-                }).map(|elem| (x, elem))
-            })
-    }
-    fn text_digest(&self) -> String {
-        let line_cnt = self.objects
-                           .iter().flat_map(|x| self.objects.get(x))
-                           .filter(|x| matches!(x, Object::LineObj(_))).count();
-        let circle_cnt = self.objects
-                             .iter().flat_map(|x| self.objects.get(x))
-                             .filter(|x| matches!(x, Object::CircleObj(_))).count();
-        let vertex_cnt = self.objects
-                             .iter().flat_map(|x| self.objects.get(x))
-                                    .map(Draw::vertices).fold(0usize, |acc,verts| acc + verts.len());
-        let frametime = get_frame_time();
-        format!(r"
-num. of lines: {line_cnt}
-num. of circles: {circle_cnt}
-num. of vertices: {vertex_cnt}
-frametime: {frametime}
-")
+    fn save_scene(&self) -> Result<(), SceneFileError> {
+        SceneFile::from_scene(&self.scene).save(&self.scene_path)
     }
-}
 
-#[derive(Debug)]
-enum LogTag {
-    Mouse, FrameTime, Select,
+    /// Replaces the sites with the ones in the scene file.
+    fn load_scene(&mut self) -> Result<(), SceneFileError> {
+        let file = SceneFile::load(&self.scene_path)?;
+        self.scene.clear_layer(Scene::<Object>::DEFAULT_LAYER);
+        file.add_to(&mut self.scene);
+        Ok(())
+    }
+
+    fn save_scene_event(&self) -> logging::Event {
+        logging::Event::file(FileAction::Save, &self.scene_path, &self.save_scene())
+    }
+
+    /// Exports the sites with `overlays`, the diagram and the beachline, drawn over them.
+    fn save_svg(&self, overlays: &[&dyn Draw]) -> std::io::Result<()> {
+        let mut svg = Svg::with_background(self.clear_color);
+        self.scene.render(&mut svg);
+        for overlay in overlays {
+            overlay.render(&mut svg);
+        }
+        svg.save(&self.svg_path)
+    }
+
+    /// Writes the sites with `overlays` drawn over them as the next frame of `recorder`.
+    fn record_frame(&self, overlays: &[&dyn Draw], recorder: &mut Recorder) -> std::io::Result<()> {
+        let mut frame = Raster::new(WIDTH as usize, HEIGHT as usize, self.clear_color);
+        self.scene.render(&mut frame);
+        for overlay in overlays {
+            overlay.render(&mut frame);
+        }
+        recorder.save(&frame).map(|_| ())
+    }
+
+    fn save_svg_event(&self, overlays: &[&dyn Draw]) -> logging::Event {
+        logging::Event::file(FileAction::Export, &self.svg_path, &self.save_svg(overlays))
+    }
 }
 
 #[derive(PartialEq, Eq, Ord, Debug)]
@@ -131,25 +128,111 @@ impl<T: Ord + Eq> PartialOrd for EventPrio<T> {
         self.x.partial_cmp(&other.x)
     }
 }
+/// The Voronoi diagram, each half-edge labelled with the site of the cell
+/// on its side, as an index into [`Algo::sites`], and once clipped, each
+/// cell with its site too.
+pub type Diagram = Dcel<Option<usize>, Option<usize>>;
+
 #[derive(Debug)]
 pub struct Algo {
     pub event_queue: EventQueue,
     pub beachline: Beachline,
-    pub output: dcel::DCEL,
+    pub output: Diagram,
+    /// The input, which the halfedges' labels index into.
+    pub sites: Vec<Point>,
+    site_ids: HashMap<Point, usize>,
 }
 
 impl Algo {
     pub fn new(points: &Vec<Point>) -> Self {
         let mut queue = EventQueue::new();
-        for point in points {
+        // a repeated site would split its own arc with nothing between,
+        // so only the first copy goes in and names the cell
+        let mut site_ids = HashMap::new();
+        for (i, point) in points.iter().enumerate() {
+            if let Entry::Vacant(id) = site_ids.entry(*point) {
+                id.insert(i);
                 queue.push(Event::Site(*point));
+            }
         }
         let beachline = Beachline::new();
         Self {
             event_queue: queue,
             beachline,
-            output: dcel::DCEL::new(),
+            output: Diagram::new(),
+            sites: points.clone(),
+            site_ids,
+        }
+    }
+
+    /// The Delaunay triangles of the sites whose circle events were processed
+    /// so far, see [`delaunay_triangles`].
+    pub fn delaunay_triangles(&self) -> Vec<[usize; 3]> {
+        delaunay_triangles(&self.output, &self.sites)
+    }
+
+    /// Whether the library's Bowyer–Watson triangulation of the sites has
+    /// the same triangles as [`Algo::delaunay_triangles`], once the events
+    /// are all processed. Four or more sites on a circle can be triangulated
+    /// either way, so they may disagree there.
+    pub fn agrees_with_bowyer_watson(&self) -> bool {
+        let sorted = |triangles: Vec<[usize; 3]>| {
+            let mut triangles: Vec<[usize; 3]> = triangles.into_iter().map(|mut t| { t.sort(); t }).collect();
+            triangles.sort();
+            triangles
+        };
+        let bowyer_watson = Triangulation::from_points(self.sites.iter().copied());
+        // its vertices skip repeated points, the sites don't
+        let as_sites = bowyer_watson.triangles().into_iter()
+            .map(|t| t.map(|v| self.site_ids[&bowyer_watson.point(v).unwrap()]))
+            .collect();
+        sorted(self.delaunay_triangles()) == sorted(as_sites)
+    }
+
+    /// The diagram so far, its edges still on the beachline running out
+    /// past `region`, cut to it, with its faces labelled with their sites.
+    pub fn clipped_diagram(&self, region: &Region) -> Diagram {
+        let mut diagram = self.output.clone();
+        extend_edges(&self.beachline, region, &mut diagram);
+        clip(region, &mut diagram);
+        diagram.build_faces();
+
+        let cells: Vec<_> = diagram.bounded_faces().collect();
+        for face in cells {
+            let site = diagram.face_edges(face).find_map(|edge| *diagram.data(edge)).or_else(|| {
+                // a region inside a single cell has no edge of it to go by
+                let p = diagram.point_in(face)?;
+                (0..self.sites.len()).min_by(|&a, &b| (self.sites[a] - p).magnitude().total_cmp(&(self.sites[b] - p).magnitude()))
+            });
+            *diagram.face_data_mut(face) = site;
+        }
+        diagram
+    }
+
+    /// The sites whose cells are unbounded, those with an arc on the
+    /// beachline once the sweep is done, in increasing order. Mid-sweep,
+    /// those with an arc so far.
+    pub fn unbounded_sites(&self) -> Vec<usize> {
+        let Some(root) = self.beachline.root else { return vec![] };
+        let mut sites = vec![];
+        let mut node = Some(self.beachline.tree_minimum(root));
+        while let Some(current) = node {
+            if let BeachItem::Arc(ref arc) = self.beachline.graph[node_index(current)].item {
+                sites.push(self.site_ids[&arc.site]);
+            }
+            node = self.beachline.successor(current);
         }
+        sites.sort();
+        sites.dedup();
+        sites
+    }
+
+    /// Labels the halfedge a breakpoint between `left` and `right` traces
+    /// with the site on its side, and its twin with the other one.
+    fn set_input_vertices(&mut self, edge: HalfEdgeId, left: Point, right: Point) {
+        let twin = self.output.twin(edge);
+        *self.output.data_mut(edge) = Some(self.site_ids[&left]);
+        *self.output.data_mut(twin) = Some(self.site_ids[&right]);
     }
 
     // returns true if it still has work to do
@@ -261,9 +344,7 @@ impl Algo {
         let (twin1, twin2) = self.output.add_twins();
 
         // make a vertex at the circle center
-        let center_vertex = dcel::Vertex { coordinates: data.center, incident_edge: twin1, alive: true};
-        let center_vertex_ind = self.output.vertices.len();
-        self.output.vertices.push(center_vertex);
+        let center_vertex = self.output.add_vertex(data.center);
 
         // hook up next pointers on halfedges
         let pred_edge = self.beachline.get_edge(pred);
@@ -271,20 +352,23 @@ impl Algo {
         let parent_edge = self.beachline.get_edge(parent);
         let other_edge = self.beachline.get_edge(other);
 
-        let pred_edge_twin = self.output.halfedges[pred_edge].twin;
-        let succ_edge_twin = self.output.halfedges[succ_edge].twin;
+        let pred_edge_twin = self.output.twin(pred_edge);
+        let succ_edge_twin = self.output.twin(succ_edge);
 
-        self.output.halfedges[parent_edge].origin = center_vertex_ind;
-        self.output.halfedges[other_edge].origin = center_vertex_ind;
-        self.output.halfedges[twin1].origin = center_vertex_ind;
+        self.output.set_origin(twin1, center_vertex);
+        self.output.set_origin(parent_edge, center_vertex);
+        self.output.set_origin(other_edge, center_vertex);
 
-        self.output.halfedges[pred_edge_twin].next = succ_edge;
-        self.output.halfedges[succ_edge_twin].next = twin1;
-        self.output.halfedges[twin2].next = pred_edge;
+        self.output.link(pred_edge_twin, succ_edge);
+        self.output.link(succ_edge_twin, twin1);
+        self.output.link(twin2, pred_edge);
 
         if let BeachItem::Breakpoint(ref mut breakpoint) = self.beachline.graph[node_index(other)].item {
             breakpoint.edge_idx = twin2;
         }
+        let left_site = self.beachline.get_site(Some(left_neighbor)).unwrap();
+        let right_site = self.beachline.get_site(Some(right_neighbor)).unwrap();
+        self.set_input_vertices(twin2, left_site, right_site);
 
         if let Some(left_triple) = self.beachline.get_centered_triple(left_neighbor) {
             trace!("Checking leftward triple {:?}, {:?}, {:?}", left_triple.0, left_triple.1, left_triple.2);
@@ -330,6 +414,7 @@ impl Algo {
 
         let breakpoint_AB = Breakpoint { left: arc_pt, right: site, edge_idx: twin1 };
         let breakpoint_BA = Breakpoint { left: site, right: arc_pt, edge_idx: twin2 };
+        self.set_input_vertices(twin1, arc_pt, site);
 
         let internal_AB = BeachItem::Breakpoint(breakpoint_AB);
         let internal_BA = BeachItem::Breakpoint(breakpoint_BA);
@@ -401,8 +486,18 @@ impl Algo {
     }
 }
 
+impl Metrics for Algo {
+    fn metrics(&self) -> Vec<(String, String)> {
+        vec![
+            ("events queued".to_string(), self.event_queue.len().to_string()),
+            ("beachline nodes".to_string(), self.beachline.graph.node_count().to_string()),
+            ("diagram".to_string(), format!("{} vertices, {} half-edges", self.output.vertex_count(), self.output.halfedges().count())),
+        ]
+    }
+}
+
 impl Draw for Algo {
-    fn draw(&self) {
+    fn render(&self, target: &mut dyn Renderer) {
         let graph_it = self.beachline.graph.node_references();
         for (node_idx,node) in graph_it {
             if let BeachItem::Arc(ref arc) = node.item {
@@ -447,7 +542,8 @@ impl Draw for Algo {
                             let v0 = (x as f64)/(V_COUNT as f64);
                             let v1 = ((x+1) as f64)/(V_COUNT as f64);
                             let a = calc_v(v0); let b = calc_v(v1);
-                            draw_line(a.x() as f32, a.y() as f32, b.x() as f32, b.y() as f32, 1.0, WHITE);
+                            let (a, b) = (Vec2::from(a), Vec2::from(b));
+                            target.line(a, b, 1.0, WHITE);
                         }
                     },
                     _ => (),
@@ -461,12 +557,133 @@ impl Draw for Algo {
     }
 }
 
-#[macroquad::main("Voronoi")]
-async fn main() {
-    const WIDTH: f32 = 1800.0;
-    const HEIGHT: f32 = 1000.0;
+const WIDTH: f32 = 1800.0;
+const HEIGHT: f32 = 1000.0;
+const CIRCLE_RADIUS: f32 = 1.0;
+
+/// The region the diagram is clipped to unless `--clip` says otherwise.
+fn window() -> Region {
+    Region::rectangle(WIDTH.into(), HEIGHT.into())
+}
+
+/// Loads the sites from the scene file or scatters them at random. Doesn't
+/// touch macroquad, so it works without a window.
+fn setup(args: &Args) -> State {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let mut state = State::new(seed, Logger::from_args(args, "./log.jsonl"));
+    state.logger.log(logging::Event::Seed { seed });
+    if let Some(path) = args.option("svg") {
+        state.svg_path = path.to_string();
+    }
+    if let Some(clip) = args.option("clip") {
+        state.clip = clip.parse().unwrap_or_else(|e| panic!("couldn't use --clip {clip}: {e}"));
+    }
+    if let Some(path) = args.option("scene") {
+        state.scene_path = path.to_string();
+        state.load_scene().unwrap_or_else(|e| panic!("couldn't load {path}: {e}"));
+    } else {
+        let bounds = (0.0..WIDTH, 0.0..HEIGHT);
+        for p in utils::random_points(&mut state.rng, 1000, bounds) {
+            let clr = utils::random_color(&mut state.rng);
+            state.scene.add_circle(geom::Circle {
+                center: Vertex::new(p.x, p.y, Some(clr)),
+                radius: CIRCLE_RADIUS,
+            });
+        }
+    }
+    state
+}
+
+fn main() {
+    let args = Args::from_env(&["scene", "seed", "svg", "clip", "record", "log", "log-level", "bench", "runs", "sizes"]);
+    if args.flag("bench") {
+        bench(&args);
+    } else if args.flag("headless") {
+        headless(&args);
+    } else {
+        macroquad::Window::new("Voronoi", windowed(args));
+    }
+}
+
+/// Runs Fortune's algorithm to completion, logging the timing as usual and
+/// printing the size of the diagram.
+fn headless(args: &Args) {
+    let mut state = setup(args);
+    let input_verts: Vec<Point> = state.scene.elements().map(|(_,elem)| {
+        Point::from(elem.compute_aabb().center())
+    }).collect();
+
+    let before = Instant::now();
+    let mut voronoi_state = Algo::new(&input_verts);
+    let mut event_count = 0;
+    while voronoi_state.process_next_event() { event_count += 1; }
+    let dcel = voronoi_state.clipped_diagram(&state.clip);
+    let d = Instant::now() - before;
+
+    let site_count = input_verts.len();
+    state.logger.log(logging::Event::timing("voronoi", site_count, d));
+    let triangles = voronoi_state.delaunay_triangles();
+    println!("{site_count} sites, {event_count} events, {} cells ({} unbounded), {} edges, {} delaunay triangles in {}s{}ns",
+             make_cells(&dcel).len(), voronoi_state.unbounded_sites().len(), make_line_segments(&dcel).len(), triangles.len(),
+             d.as_secs(), d.subsec_nanos());
+    let agreement = if voronoi_state.agrees_with_bowyer_watson() { "agrees" } else { "disagrees" };
+    println!("bowyer-watson {agreement} with the delaunay triangles of the diagram");
+    let outlines = scene_outlines(&state.scene);
+    if !outlines.is_empty() {
+        let overlay = arrangement::overlay(&dcel, &arrangement::arrangement::<()>(&outlines));
+        let inside = overlay.bounded_faces().filter(|&face| overlay.face_data(face).1.is_some()).count();
+        println!("overlaid with {} outline segments: {} faces, {inside} inside the outlines", outlines.len(), overlay.bounded_faces().count());
+    }
+
+    if args.flag("svg") {
+        let voronoi_poly = dcel_to_wire_poly(&dcel, &mut state.rng);
+        let delaunay_poly = delaunay_polygon(&input_verts, &triangles, &mut state.rng);
+        state.save_svg(&[&delaunay_poly, &voronoi_poly]).unwrap_or_else(|e| panic!("couldn't export {}: {e}", state.svg_path));
+        println!("exported the diagram to {}", state.svg_path);
+    }
+
+    if let Some(dir) = args.option("record") {
+        // again, a step at a time, as the interactive mode shows it
+        let mut recorder = Recorder::new(dir).unwrap_or_else(|e| panic!("couldn't record to {dir}: {e}"));
+        let mut voronoi_state = Algo::new(&input_verts);
+        loop {
+            let more = voronoi_state.process_next_event();
+            let interim_dcel = voronoi_state.clipped_diagram(&state.clip);
+            let voronoi_poly = dcel_to_wire_poly(&interim_dcel, &mut state.rng);
+            state.record_frame(&[&voronoi_poly, &voronoi_state], &mut recorder)
+                .unwrap_or_else(|e| panic!("couldn't record to {dir}: {e}"));
+            if !more { break; }
+        }
+        println!("recorded {} frames in {dir}", recorder.frames());
+    }
+}
+
+/// Sweeps Fortune's algorithm, up to the faces of the clipped diagram, over
+/// growing sets of random sites, writing the timings to `--bench`.
+fn bench(args: &Args) {
+    let seed = args.parsed_option("seed").unwrap_or_else(utils::fresh_seed);
+    let mut rng = utils::seeded_rng(seed);
+    let mut bench = Bench::from_args(args);
+    let region = window();
+    println!("seed: {seed}, {} runs per size", bench.runs());
+    for n in bench.sizes(&[500, 1000, 2000, 4000, 8000]) {
+        let sites = utils::random_sites(&mut rng, n, (0.0..WIDTH, 0.0..HEIGHT));
+        bench.measure("voronoi", n, || {
+            let mut voronoi_state = Algo::new(&sites);
+            while voronoi_state.process_next_event() {}
+            voronoi_state.clipped_diagram(&region)
+        });
+    }
+    print!("{}", bench.report());
+
+    let path = args.option("bench").unwrap_or("./bench-voronoi.csv");
+    bench.save(path).unwrap_or_else(|e| panic!("couldn't write {path}: {e}"));
+    println!("wrote the timings to {path}");
+}
+
+async fn windowed(args: Args) {
     request_new_screen_size(WIDTH, HEIGHT);
-    let state = std::rc::Rc::new(std::sync::RwLock::new(State::default()));
+    let state = std::rc::Rc::new(std::sync::RwLock::new(setup(&args)));
 
     #[cfg(debug_assertions)]
     {
@@ -477,165 +694,166 @@ async fn main() {
     log::log!(log::Level::Error, "aaaaarhg");
     log::info!("up and running");
     }
-    
-    const CIRCLE_RADIUS: f32 = 1.0;
 
-    let bounds = (0.0..WIDTH, 0.0..HEIGHT);
-    let random_float_points = utils::random_points(1000, bounds.clone());
+    state.write().unwrap().prev_mouse_pos = mouse_position();
 
-    {
-        let mut state = state.write().unwrap();
-        for p in &random_float_points {
-            state.add_circle(geom::Circle {
-                center: Vertex::new(p.x, p.y,
-                                    Some(utils::random_color())),
-                radius: CIRCLE_RADIUS,
-            });
-        }
-    }
-        
     let instant_voronoi = move |state: std::rc::Rc<std::sync::RwLock<State>>| async move {
         let mut state = state.write().unwrap();
+        let clip = state.clip.clone();
         let mut voronoi_state = Algo::new(&vec![]);
-        let mut voronoi_calc = |state: &State| {
-            let input_verts = state.all_elements().map(|(_,elem)| {
-                let center = elem.compute_aabb().center();
-                Point::new(center.x as f64, center.y as f64)
+        let mut voronoi_calc = |state: &mut State| {
+            let input_verts = state.scene.elements().map(|(_,elem)| {
+                Point::from(elem.compute_aabb().center())
             }).collect();
             voronoi_state = Algo::new(&input_verts);
             while voronoi_state.process_next_event() {};
 
-            let mut interim_dcel = voronoi_state.output.clone();
-            add_bounding_box(WIDTH.max(HEIGHT).into(), &voronoi_state.beachline, &mut interim_dcel);
-            dcel::add_faces(&mut interim_dcel);
-
-            let poly = dcel_to_wire_poly(&interim_dcel);
-
-            // let delauney = {
-            //     // all the vertices are the same as the input to the voronoi algo
-            //     // we only need the voronoi result to know which edges to create
-            //     let mut poly = Polygon {
-            //         verts: input_verts.iter().map(|v| Vertex::new(v.x() as f32, v.y() as f32, Some(utils::random_color()))).collect(),
-            //         ..Default::default()
-            //     };
-            //     //for 
-            // }
-            poly
+            let interim_dcel = voronoi_state.clipped_diagram(&clip);
+
+            let poly = dcel_to_wire_poly(&interim_dcel, &mut state.rng);
+            // the sites are the same as the diagram's input, the diagram
+            // only tells which of them to connect
+            let delaunay = delaunay_polygon(&voronoi_state.sites, &voronoi_state.delaunay_triangles(), &mut state.rng);
+            (poly, delaunay)
         };
 
-        let mut voronoi_poly = voronoi_calc(&state);
+        let (mut voronoi_poly, mut delaunay_poly) = voronoi_calc(&mut state);
 
         loop {
-            let tick_time = {
-                let t = Instant::now().duration_since(state.startup);
-                (t.as_secs(), t.subsec_nanos())
-            };
-
-            let log_line = |state: &mut State, tag: LogTag, msg: &str| {
-                writeln!(&mut state.logfile, "({tag:?}) [{}s{}ns]: {msg}", tick_time.0, tick_time.1).expect("couldn't write log line")
-            };
-
-            log_line(&mut state, LogTag::FrameTime, &format!("{} / {}", get_frame_time(), get_fps()));
+            state.logger.log(logging::Event::FrameTime { frame_time: get_frame_time(), fps: get_fps() });
+            state.hud.update();
 
             if is_quit_requested() { break }
             clear_background(state.clear_color);
 
-            for handle in state.objects.iter() {
-                let object = state.objects.get(handle).unwrap();
-                object.draw();
-            }
+            let State { viewport, scene, .. } = &mut *state;
+            viewport.update(|| scene.bounds());
+            viewport.activate();
 
+            state.scene.draw();
+
+            if state.show_delaunay {
+                delaunay_poly.draw();
+            }
             voronoi_poly.draw();
 
             { // Mouse handling
-                let mouse_pos = mouse_position();
+                let mouse_pos = state.viewport.mouse_world();
                 if mouse_pos != state.prev_mouse_pos {
-                    log_line(&mut state, LogTag::Mouse, &format!("pos {},{}", mouse_pos.0, mouse_pos.1));
+                    state.logger.log(logging::Event::Mouse { action: MouseAction::Pos, x: mouse_pos.0, y: mouse_pos.1 });
                     state.prev_mouse_pos = mouse_pos;
                 }
 
                 if is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_pressed(MouseButton::Right) {
-                    log_line(&mut state, LogTag::Mouse, &format!("clicked {},{}", mouse_pos.0, mouse_pos.1));
+                    state.logger.log(logging::Event::Mouse { action: MouseAction::Clicked, x: mouse_pos.0, y: mouse_pos.1 });
 
-                    let mut hit_elem = None;
                     let delete = is_mouse_button_pressed(MouseButton::Right);
-                    for (handle,element) in state.all_elements() {
-                        if element.contains_point(&Vector2D::new(mouse_pos.0, mouse_pos.1)) {
-                            hit_elem = Some(handle);
-                            break;
-                        }
-                    }
+                    let hit_elem = state.scene.pick(&Vector2D::new(mouse_pos.0, mouse_pos.1));
                     if let Some(elem) = hit_elem {
-                            log_line(&mut state, LogTag::Select, &format!("selected {:?}", elem));
+                        state.logger.log(logging::Event::Select { object: format!("{:?}", elem) });
                         if delete {
-                            state.objects.remove(elem);
+                            state.scene.remove(elem);
                         }
                     } else {
-                        state.add_circle(geom::Circle {
-                            center: Vertex::new(mouse_pos.0, mouse_pos.1, Some(utils::random_color())),
+                        let clr = utils::random_color(&mut state.rng);
+                        state.scene.add_circle(geom::Circle {
+                            center: Vertex::new(mouse_pos.0, mouse_pos.1, Some(clr)),
                             radius: CIRCLE_RADIUS,
                         });
                     }
 
-                    voronoi_poly = voronoi_calc(&state);
+                    (voronoi_poly, delaunay_poly) = voronoi_calc(&mut state);
                 }
             }
 
-            draw_text("IT WORKS!", 20.0, 20.0, 30.0, DARKGRAY);
+            if is_key_released(KeyCode::D) {
+                state.show_delaunay = !state.show_delaunay;
+            }
 
-            if is_key_released(KeyCode::R) {
-                println!("{}", state.text_digest())
+            if is_key_released(KeyCode::F5) {
+                let event = state.save_scene_event();
+                state.logger.log(event);
             }
+
+            if is_key_released(KeyCode::F6) {
+                let event = if state.show_delaunay {
+                    state.save_svg_event(&[&delaunay_poly, &voronoi_poly])
+                } else {
+                    state.save_svg_event(&[&voronoi_poly])
+                };
+                state.logger.log(event);
+            }
+
+            if is_key_released(KeyCode::F9) {
+                let loaded = state.load_scene();
+                if loaded.is_ok() {
+                    (voronoi_poly, delaunay_poly) = voronoi_calc(&mut state);
+                }
+                let event = logging::Event::file(FileAction::Load, &state.scene_path, &loaded);
+                state.logger.log(event);
+            }
+
+            set_default_camera();
+            state.hud.draw(&[&state.scene]);
             next_frame().await
         }
     };
     let interactive_voronoi = move |state: std::rc::Rc<std::sync::RwLock<State>>| async move {
         let mut state = state.write().unwrap();
+        let clip = state.clip.clone();
         let voronoi_reset = |state: &State| {
-            let input_verts = state.all_elements().map(|(_,elem)| {
-                let center = elem.compute_aabb().center();
-                Point::new(center.x as f64, center.y as f64)
+            let input_verts = state.scene.elements().map(|(_,elem)| {
+                Point::from(elem.compute_aabb().center())
             }).collect();
             Algo::new(&input_verts)
         };
 
         let mut voronoi_state = voronoi_reset(&state);
 
-        let voronoi_step = |voronoi_state: &mut Algo| {
+        let voronoi_step = |voronoi_state: &mut Algo, rng: &mut StdRng| {
             voronoi_state.process_next_event();
-            let mut interim_dcel = voronoi_state.output.clone();
-            add_bounding_box(WIDTH.max(HEIGHT).into(), &voronoi_state.beachline, &mut interim_dcel);
-            dcel::add_faces(&mut interim_dcel);
+            let interim_dcel = voronoi_state.clipped_diagram(&clip);
 
-            let poly = dcel_to_wire_poly(&interim_dcel);
-            poly
+            let poly = dcel_to_wire_poly(&interim_dcel, rng);
+            let delaunay = delaunay_polygon(&voronoi_state.sites, &voronoi_state.delaunay_triangles(), rng);
+            (poly, delaunay)
         };
 
         let mut voronoi_poly = Polygon::default();
+        let mut delaunay_poly = Polygon::default();
+        let mut overlay_poly = Polygon::default();
 
         loop {
-            let tick_time = {
-                let t = Instant::now().duration_since(state.startup);
-                (t.as_secs(), t.subsec_nanos())
-            };
-
-            let log_line = |state: &mut State, tag: LogTag, msg: &str| {
-                writeln!(&mut state.logfile, "({tag:?}) [{}s{}ns]: {msg}", tick_time.0, tick_time.1).expect("couldn't write log line")
-            };
-
-            log_line(&mut state, LogTag::FrameTime, &format!("{} / {}", get_frame_time(), get_fps()));
+            state.logger.log(logging::Event::FrameTime { frame_time: get_frame_time(), fps: get_fps() });
+            state.hud.update();
 
             if is_quit_requested() { break }
             clear_background(state.clear_color);
 
-            for handle in state.objects.iter() {
-                let object = state.objects.get(handle).unwrap();
-                object.draw();
-            }
+            let State { viewport, scene, .. } = &mut *state;
+            viewport.update(|| scene.bounds());
+            viewport.activate();
+
+            state.scene.draw();
 
 
             if is_key_released(KeyCode::S) {
-                voronoi_poly = voronoi_step(&mut voronoi_state);
+                (voronoi_poly, delaunay_poly) = voronoi_step(&mut voronoi_state, &mut state.rng);
+                if state.show_overlay {
+                    overlay_poly = overlay_polygon(&voronoi_state.clipped_diagram(&clip), &state.scene);
+                }
+            }
+
+            if is_key_released(KeyCode::D) {
+                state.show_delaunay = !state.show_delaunay;
+            }
+
+            // the diagram so far, cut up by the scene's lines and polygons
+            if is_key_released(KeyCode::O) {
+                state.show_overlay = !state.show_overlay;
+                if state.show_overlay {
+                    overlay_poly = overlay_polygon(&voronoi_state.clipped_diagram(&clip), &state.scene);
+                }
             }
 
             if is_key_released(KeyCode::R) {
@@ -654,36 +872,37 @@ async fn main() {
                 write!(file, "{}", dot).unwrap();
             }
             
+            if state.show_overlay {
+                overlay_poly.draw();
+            }
+            if state.show_delaunay {
+                delaunay_poly.draw();
+            }
             voronoi_poly.draw();
             voronoi_state.draw();
 
             { // Mouse handling
-                let mouse_pos = mouse_position();
+                let mouse_pos = state.viewport.mouse_world();
                 if mouse_pos != state.prev_mouse_pos {
-                    log_line(&mut state, LogTag::Mouse, &format!("pos {},{}", mouse_pos.0, mouse_pos.1));
+                    state.logger.log(logging::Event::Mouse { action: MouseAction::Pos, x: mouse_pos.0, y: mouse_pos.1 });
                     state.prev_mouse_pos = mouse_pos;
                 }
 
                 if is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_pressed(MouseButton::Right) {
-                    log_line(&mut state, LogTag::Mouse, &format!("clicked {},{}", mouse_pos.0, mouse_pos.1));
+                    state.logger.log(logging::Event::Mouse { action: MouseAction::Clicked, x: mouse_pos.0, y: mouse_pos.1 });
 
-                    let mut hit_elem = None;
                     let delete = is_mouse_button_pressed(MouseButton::Right);
-                    for (handle,element) in state.all_elements() {
-                        if element.contains_point(&Vector2D::new(mouse_pos.0, mouse_pos.1)) {
-                            hit_elem = Some(handle);
-                            break;
-                        }
-                    }
+                    let hit_elem = state.scene.pick(&Vector2D::new(mouse_pos.0, mouse_pos.1));
                     if let Some(elem) = hit_elem {
-                        log_line(&mut state, LogTag::Select, &format!("selected {:?}", elem));
+                        state.logger.log(logging::Event::Select { object: format!("{:?}", elem) });
                         if delete {
-                            state.objects.remove(elem);
+                            state.scene.remove(elem);
                             voronoi_state = voronoi_reset(&state);
                         }
                     } else {
-                        state.add_circle(geom::Circle {
-                            center: Vertex::new(mouse_pos.0, mouse_pos.1, Some(utils::random_color())),
+                        let clr = utils::random_color(&mut state.rng);
+                        state.scene.add_circle(geom::Circle {
+                            center: Vertex::new(mouse_pos.0, mouse_pos.1, Some(clr)),
                             radius: CIRCLE_RADIUS,
                         });
                         voronoi_state = voronoi_reset(&state);
@@ -691,11 +910,38 @@ async fn main() {
                 }
             }
 
-            draw_text("IT WORKS!", 20.0, 20.0, 30.0, DARKGRAY);
+            if is_key_released(KeyCode::F5) {
+                let event = state.save_scene_event();
+                state.logger.log(event);
+            }
 
-            if is_key_released(KeyCode::R) {
-                println!("{}", state.text_digest())
+            if is_key_released(KeyCode::F6) {
+                let mut overlays: Vec<&dyn Draw> = vec![];
+                if state.show_overlay {
+                    overlays.push(&overlay_poly);
+                }
+                if state.show_delaunay {
+                    overlays.push(&delaunay_poly);
+                }
+                overlays.extend([&voronoi_poly as &dyn Draw, &voronoi_state]);
+                let event = state.save_svg_event(&overlays);
+                state.logger.log(event);
+            }
+
+            if is_key_released(KeyCode::F9) {
+                let loaded = state.load_scene();
+                if loaded.is_ok() {
+                    voronoi_state = voronoi_reset(&state);
+                    voronoi_poly = Polygon::default();
+                    delaunay_poly = Polygon::default();
+                    overlay_poly = Polygon::default();
+                }
+                let event = logging::Event::file(FileAction::Load, &state.scene_path, &loaded);
+                state.logger.log(event);
             }
+
+            set_default_camera();
+            state.hud.draw(&[&state.scene, &voronoi_state]);
             next_frame().await
         }
     };
@@ -704,21 +950,16 @@ async fn main() {
     interactive_voronoi(state.clone()).await
 }
 
-pub fn dcel_to_wire_poly(source: &dcel::DCEL) -> Polygon {
+pub fn dcel_to_wire_poly(source: &Diagram, rng: &mut impl Rng) -> Polygon {
     let mut poly = Polygon::default();
     let mut verts_map = HashMap::new();
-    for face in make_polygons(&source) {
+    for (_, face) in make_cells(source) {
         let mut vert_ids = vec![];
         for vert in face.iter() {
             let idx = verts_map
-                .entry(Point::new(vert.x(), vert.y()))
+                .entry(*vert)
                 .or_insert_with_key(|vert| {
-                    poly.verts.push(
-                        Vertex::new(
-                            vert.x() as f32, vert.y() as f32,
-                            Some(utils::random_color())
-                        )
-                    );
+                    poly.verts.push(Vertex { clr: Some(utils::random_color(rng)), ..(*vert).into() });
                     poly.verts.len()-1
                 });
             vert_ids.push(*idx);
@@ -726,107 +967,328 @@ pub fn dcel_to_wire_poly(source: &dcel::DCEL) -> Polygon {
         for (a,b) in vert_ids.iter()
                              .take(vert_ids.len()-1)
                              .zip(vert_ids.iter().skip(1).cycle()) {
-            poly.edges.push((*a, *b, utils::random_color()));
+            poly.edges.push((*a, *b, utils::random_color(rng)));
         }
     }
     poly
 }
 
-// impl for our DCEL newtype
-pub fn make_polygons(dcel: &dcel::DCEL) -> Vec<Vec<Point>> {
-    let mut result = vec![];
-    for face in &dcel.faces {
-        if !face.alive { continue; }
-        let mut this_poly = vec![];
-        let start_edge = face.outer_component;
-        let mut current_edge = start_edge;
-        loop {
-            this_poly.push(dcel.get_origin(current_edge));
-            current_edge = dcel.halfedges[current_edge].next;
-            if current_edge == start_edge { break; }
+/// The triangles, indexing into `sites`, as faces of a polygon over the
+/// sites, with their edges drawn once each.
+pub fn delaunay_polygon(sites: &[Point], triangles: &[[usize; 3]], rng: &mut impl Rng) -> Polygon {
+    let mut poly = Polygon {
+        verts: sites.iter().map(|site| Vertex { clr: Some(utils::random_color(rng)), ..(*site).into() }).collect(),
+        ..Default::default()
+    };
+    let mut seen_edges = std::collections::HashSet::new();
+    for &[a, b, c] in triangles {
+        poly.faces.push((a, b, c, utils::random_color(rng).with_alpha(0.3)));
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            if seen_edges.insert((from.min(to), from.max(to))) {
+                poly.edges.push((from, to, GRAY));
+            }
         }
-        result.push(this_poly);
     }
+    poly
+}
+
+/// The scene's lines and polygon edges.
+pub fn scene_outlines(scene: &Scene) -> Vec<Segment> {
+    let mut outlines = vec![];
+    for (_, object) in scene.iter() {
+        match object {
+            Object::LineObj(line) => outlines.push([line.a.pos.into(), line.b.pos.into()]),
+            Object::PolyObj(poly) => outlines.extend(poly.edges.iter().map(|&(a, b, _)| {
+                [poly.verts[a].pos.into(), poly.verts[b].pos.into()]
+            })),
+            Object::Point(_) | Object::CircleObj(_) => {}
+        }
+    }
+    outlines
+}
 
-    // remove the outer face
-    result.sort_by(|a, b| a.len().cmp(&b.len()));
-    result.pop();
+/// The diagram overlaid with the scene's outlines, every face coloured by
+/// the cell and the region of the outlines it's in, brighter inside one.
+pub fn overlay_polygon(diagram: &Diagram, scene: &Scene) -> Polygon {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    let outlines = arrangement::arrangement::<()>(&scene_outlines(scene));
+    let overlay = arrangement::overlay(diagram, &outlines);
+    overlay.to_polygon(LIGHTGRAY, |face| {
+        let overlap: Overlap = *overlay.face_data(face);
+        let mut hasher = DefaultHasher::new();
+        overlap.hash(&mut hasher);
+        let clr = utils::random_color(&mut utils::seeded_rng(hasher.finish()));
+        Some(clr.with_alpha(if overlap.1.is_some() { 0.6 } else { 0.15 }))
+    })
+}
 
-    return result;
+/// The cells of the clipped diagram, as their sites and corners.
+pub fn make_cells(dcel: &Diagram) -> Vec<(usize, Vec<Point>)> {
+    dcel.bounded_faces()
+        .filter_map(|face| Some(((*dcel.face_data(face))?, dcel.face_points(face).collect())))
+        .collect()
 }
 
 /// Constructs the line segments of the Voronoi diagram.
-pub fn make_line_segments(dcel: &dcel::DCEL) -> Vec<Segment> {
-    const NIL: usize = !0;
+pub fn make_line_segments(dcel: &Diagram) -> Vec<Segment> {
+    dcel.edges().filter_map(|edge| dcel.segment(edge)).collect()
+}
+
+/// The Delaunay triangulation of `sites`, as the dual of their Voronoi diagram.
+///
+/// Every vertex of the diagram is the center of a circle through the sites
+/// of the cells meeting there, and nothing inside it, so those sites make a
+/// Delaunay triangle. A vertex with more than three cocircular sites around
+/// it is fanned into several. The sites come from the halfedges' labels,
+/// so `dcel` is the diagram as Fortune's algorithm leaves it: vertices
+/// clipping adds only have one edge between two sites.
+///
+/// The triangles index into `sites` and run counter-clockwise.
+pub fn delaunay_triangles(dcel: &Diagram, sites: &[Point]) -> Vec<[usize; 3]> {
+    // mid-sweep the edges around a vertex aren't all linked up yet, so
+    // this goes by the halfedges' origins
+    let mut sites_around: HashMap<_, Vec<usize>> = HashMap::new();
+    for halfedge in dcel.halfedges() {
+        let Some(origin) = dcel.origin(halfedge) else { continue };
+        let around = sites_around.entry(origin).or_default();
+        for &site in [dcel.data(halfedge), dcel.data(dcel.twin(halfedge))].into_iter().flatten() {
+            if !around.contains(&site) {
+                around.push(site);
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
     let mut result = vec![];
-    for halfedge in &dcel.halfedges {
-        if halfedge.origin != NIL && halfedge.next != NIL && halfedge.alive {
-            if dcel.halfedges[halfedge.next].origin != NIL {
-                result.push([dcel.vertices[halfedge.origin].coordinates,
-                    dcel.get_origin(halfedge.next)])
+    for vertex in dcel.vertices() {
+        let Some(around) = sites_around.get_mut(&vertex) else { continue };
+        if around.len() < 3 { continue; }
+        let center = dcel.pos(vertex);
+        let angle = |site: &usize| (sites[*site].y() - center.y()).atan2(sites[*site].x() - center.x());
+        around.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        for i in 1..around.len() - 1 {
+            let mut triangle = [around[0], around[i], around[i + 1]];
+            let orientation = orient2d(sites[triangle[0]], sites[triangle[1]], sites[triangle[2]]);
+            if orientation == 0. { continue; }
+            if orientation < 0. {
+                triangle.swap(1, 2);
+            }
+            let mut key = triangle;
+            key.sort();
+            if seen.insert(key) {
+                result.push(triangle);
             }
         }
     }
     result
 }
 
-fn outside_bb(pt: Point, box_size: f64) -> bool {
-    let delta = 0.1;
-    pt.x() < 0. - delta || pt.x() > box_size + delta || pt.y() < 0. - delta || pt.y() > box_size + delta
+/// Cuts `dcel` down to what's inside `region`, its edges running out past
+/// it, and walls it in along the region's edges.
+fn clip(region: &Region, dcel: &mut Diagram) {
+    let chains: Vec<Vec<_>> = region.edges().map(|[a, b]| dcel.insert_segment(a, b)).collect();
+    // the later edges can't split the earlier ones, the region being simple
+    let boundary: Vec<HalfEdgeId> = chains.iter()
+        .flat_map(|chain| chain.windows(2))
+        .filter_map(|pair| dcel.find_edge(pair[0], pair[1]))
+        .collect();
+
+    // every other edge is inside or outside whole, having been split where it crossed
+    let outside: Vec<HalfEdgeId> = dcel.edges()
+        .filter(|edge| !boundary.iter().any(|b| b == edge || dcel.twin(*b) == *edge))
+        .filter(|&edge| dcel.segment(edge).is_some_and(|[a, b]| !region.contains(a.lerp(&b, 0.5))))
+        .collect();
+    for edge in outside {
+        dcel.remove_edge(edge);
+    }
+    let isolated: Vec<_> = dcel.vertices().filter(|&vert| dcel.vertex_edge(vert).is_none()).collect();
+    for vert in isolated {
+        dcel.remove_vertex(vert);
+    }
+}
+
+/// Gives the edges still on the beachline an end far enough out that it's
+/// past `region`, in the direction their breakpoints move off in.
+fn extend_edges(beachline: &Beachline, region: &Region, dcel: &mut Diagram) {
+    let Some(root) = beachline.root else { return };
+    let (center, radius) = region.bounding_circle();
+
+    // an edge traced by two breakpoints has neither end yet, and its
+    // first one mustn't be mistaken for where the second starts
+    let mut ends = vec![];
+    let mut node = Some(beachline.tree_minimum(root));
+    while let Some(current) = node {
+        if let BeachItem::Breakpoint(ref breakpoint) = beachline.graph[node_index(current)].item {
+            let this_edge = breakpoint.edge_idx;
+            trace!("Extending halfedge {:?} with breakpoint {:?}, {:?}", this_edge, breakpoint.left, breakpoint.right);
+            let start = match dcel.origin(dcel.twin(this_edge)) {
+                Some(vert) => dcel.pos(vert),
+                None => breakpoint.left.lerp(&breakpoint.right, 0.5),
+            };
+            // the sweep runs towards -y, so the breakpoint heads off with
+            // the left site's arc to its -x side
+            let across = breakpoint.right - breakpoint.left;
+            let direction = Point::new(across.y(), -across.x());
+            let distance = 2.0 * radius + (start - center).magnitude();
+            ends.push((this_edge, start + direction * (distance / direction.magnitude())));
+        }
+        node = beachline.successor(current);
+    }
+
+    for (this_edge, end) in ends {
+        let vert = dcel.add_vertex(end);
+        dcel.set_origin(this_edge, vert);
+        let this_twin = dcel.twin(this_edge);
+        dcel.link(this_twin, this_edge);
+    }
 }
 
-fn add_bounding_box(boxsize: f64, beachline: &beachline::Beachline, dcel: &mut dcel::DCEL) {
-    extend_edges(beachline, dcel);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use region::RegionError;
+    use stales_geom_viewer::{hull::convex_hull, predicates::{incircle, orient2d}};
 
-    let delta = 50.;
-    let bb_top =    [Point::new(0. - delta, 0.),         Point::new(boxsize + delta, 0.)];
-    let bb_bottom = [Point::new(0. - delta, boxsize),    Point::new(boxsize + delta, boxsize)];
-    let bb_left =   [Point::new(0.,         0. - delta), Point::new(0.,              boxsize + delta)];
-    let bb_right =  [Point::new(boxsize,    0. - delta), Point::new(boxsize,         boxsize + delta)];
+    fn random_sites(seed: u64, count: usize) -> Vec<Point> {
+        utils::random_sites(&mut utils::seeded_rng(seed), count, (0.0..WIDTH, 0.0..HEIGHT))
+    }
 
-    dcel::add_line(bb_top, dcel);
-    dcel::add_line(bb_right, dcel);
-    dcel::add_line(bb_left, dcel);
-    dcel::add_line(bb_bottom, dcel);
+    fn run(sites: &[Point]) -> Algo {
+        let mut voronoi_state = Algo::new(&sites.to_vec());
+        while voronoi_state.process_next_event() {}
+        voronoi_state
+    }
 
-    dcel.set_prev();
+    fn nearest(sites: &[Point], p: Point) -> f64 {
+        sites.iter().map(|s| (p - *s).magnitude()).fold(f64::INFINITY, f64::min)
+    }
 
-    for vert in 0..dcel.vertices.len() {
-        let this_pt = dcel.vertices[vert].coordinates;
-        if outside_bb(this_pt, boxsize) {
-            dcel.remove_vertex(vert);
+    #[test]
+    fn cells_know_their_sites() {
+        let sites = random_sites(7, 100);
+        let voronoi_state = run(&sites);
+        let dcel = voronoi_state.clipped_diagram(&window());
+        dcel.check_invariants().unwrap();
+        let cells = make_cells(&dcel);
+        assert_eq!(cells.len(), sites.len());
+
+        let mut cell_sites: Vec<usize> = cells.iter().map(|&(site, _)| site).collect();
+        cell_sites.sort();
+        assert_eq!(cell_sites, (0..sites.len()).collect::<Vec<_>>());
+        for (site, corners) in cells {
+            // every corner of a cell is at least as close to its site as to any other
+            for corner in corners {
+                let to_site = (corner - sites[site]).magnitude();
+                let nearest = nearest(&sites, corner);
+                assert!(to_site <= nearest + 1e-6, "{corner:?} is {to_site} from its site, {nearest} from the nearest");
+            }
         }
+        let area: f64 = dcel.bounded_faces().map(|face| dcel.signed_area(face)).sum();
+        assert!((area - window().signed_area()).abs() < 1e-6 * area, "{area}");
     }
 
-}
+    #[test]
+    fn unbounded_cells_are_the_hulls() {
+        for seed in [1, 2, 3] {
+            let sites = random_sites(seed, 200);
+            let mut hull = convex_hull(&sites);
+            hull.sort();
+            assert_eq!(run(&sites).unbounded_sites(), hull, "seed {seed}");
+        }
+    }
 
-// This just extends the edges past the end of the bounding box
-fn extend_edges(beachline: &Beachline, dcel: &mut dcel::DCEL) {
-    if beachline.root.is_none() { return; }
-    let mut current_node = beachline.tree_minimum(beachline.root.unwrap());
-    trace!("\n\n");
-    loop {
-        match beachline.graph[node_index(current_node)].item {
-            BeachItem::Arc(_) => {},
-            BeachItem::Breakpoint(ref breakpoint) => {
-                let this_edge = breakpoint.edge_idx;
-                trace!("Extending halfedge {:?} with breakpoint {:?}, {:?}", this_edge, breakpoint.left, breakpoint.right);
-                let this_x = breakpoint.get_x(-1000.0);
-                let this_y = breakpoint.get_y(-1000.0);
+    #[test]
+    fn clips_to_a_simple_region() {
+        // a window with a notch cut into its top, clockwise
+        let region: Region = "100,100;100,900;900,900;900,100;600,100;500,600;400,100".parse().unwrap();
+        let sites = random_sites(9, 150);
+        let dcel = run(&sites).clipped_diagram(&region);
+        dcel.check_invariants().unwrap();
+
+        let area: f64 = dcel.bounded_faces().map(|face| dcel.signed_area(face)).sum();
+        assert!((area - region.signed_area()).abs() < 1e-6 * area, "{area} {}", region.signed_area());
+        for face in dcel.bounded_faces() {
+            // a cell cut in two by the notch is two faces of the same site
+            let p = dcel.point_in(face).unwrap();
+            assert!(region.contains(p));
+            let site = dcel.face_data(face).expect("a cell without a site");
+            assert!((p - sites[site]).magnitude() <= nearest(&sites, p) + 1e-6, "{p:?} isn't nearest {site}");
+        }
 
-                let vert = dcel::Vertex {coordinates: Point::new(this_x, this_y), incident_edge: this_edge, alive: true};
-                let vert_ind = dcel.vertices.len();
+        // inside a single cell, the region is all of it
+        let dcel = run(&sites[..3]).clipped_diagram(&"0,0;10,0;0,10".parse().unwrap());
+        let cells = make_cells(&dcel);
+        assert_eq!(cells.len(), 1);
+        assert_eq!(cells[0].0, (0..3).min_by(|&a, &b| sites[a].magnitude().total_cmp(&sites[b].magnitude())).unwrap());
+    }
 
-                dcel.halfedges[this_edge].origin = vert_ind;
-                let this_twin = dcel.halfedges[this_edge].twin;
-                dcel.halfedges[this_twin].next = this_edge;
+    #[test]
+    fn regions_are_simple_polygons() {
+        let square: Region = "0,0;0,1;1,1;1,0;0,0".parse().unwrap();
+        assert_eq!(square.corners().len(), 4);
+        assert_eq!(square.signed_area(), 1.0);
+        assert!(square.contains(Point::new(0.5, 0.5)) && !square.contains(Point::new(1.5, 0.5)));
+
+        assert_eq!("0,0;1,1;1,0;0,1".parse::<Region>(), Err(RegionError::NotSimple));
+        assert_eq!("0,0;1,1;2,2".parse::<Region>(), Err(RegionError::NotSimple));
+        assert_eq!("0,0;1,1;0,0".parse::<Region>(), Err(RegionError::TooFewCorners));
+        assert_eq!("0,0;1,x;0,1".parse::<Region>(), Err(RegionError::Corner("1,x".to_string())));
+    }
 
-                dcel.vertices.push(vert);
+    #[test]
+    fn delaunay_triangles_have_empty_circumcircles() {
+        for seed in [1, 2, 3] {
+            let sites = random_sites(seed, 200);
+            let triangles = run(&sites).delaunay_triangles();
+            // Euler's formula, for sites in general position
+            assert_eq!(triangles.len(), 2 * sites.len() - 2 - convex_hull(&sites).len());
+            for &[a, b, c] in &triangles {
+                let (a, b, c) = (sites[a], sites[b], sites[c]);
+                assert!(orient2d(a, b, c) > 0.0);
+                for &d in &sites {
+                    assert!(incircle(a, b, c, d) <= 0.0, "{d:?} is inside the circumcircle of {a:?}, {b:?}, {c:?}");
+                }
             }
         }
-        if let Some(next_node) = beachline.successor(current_node) {
-            current_node = next_node;
-        } else { break; }
     }
 
+    #[test]
+    fn fortune_and_bowyer_watson_agree() {
+        for seed in [4, 5, 6] {
+            assert!(run(&random_sites(seed, 300)).agrees_with_bowyer_watson(), "seed {seed}");
+        }
+        // a site repeated, and three on a line
+        let mut sites = random_sites(7, 50);
+        sites.push(sites[10]);
+        sites.extend([(100.0, 100.0), (200.0, 100.0), (300.0, 100.0)].map(|(x, y)| Point::new(x, y)));
+        assert!(run(&sites).agrees_with_bowyer_watson());
+    }
+
+    #[test]
+    fn overlay_cuts_cells_along_the_outlines() {
+        let diagram = run(&random_sites(8, 60)).clipped_diagram(&window());
+        let mut scene: Scene = Scene::new();
+        scene.add_poly(Polygon::rectangle(Vector2D::new(300.0, 200.0), Vector2D::new(700.0, 500.0), RED, BLANK));
+        let overlay = arrangement::overlay(&diagram, &arrangement::arrangement::<()>(&scene_outlines(&scene)));
+        overlay.check_invariants().unwrap();
+
+        let inside: Vec<_> = overlay.bounded_faces().filter(|&face| overlay.face_data(face).1.is_some()).collect();
+        assert!(inside.len() > 1);
+        let area: f64 = inside.iter().map(|&face| overlay.signed_area(face)).sum();
+        assert!((area - 400.0 * 300.0).abs() < 1e-6, "{area}");
+        // every piece is in a cell, and the cells are cut up without gaps
+        assert!(overlay.bounded_faces().all(|face| overlay.face_data(face).0.is_some()));
+        let cells: f64 = diagram.bounded_faces().map(|face| diagram.signed_area(face)).sum();
+        let pieces: f64 = overlay.bounded_faces().map(|face| overlay.signed_area(face)).sum();
+        assert!((cells - pieces).abs() < 1e-6 * cells, "{cells} {pieces}");
+    }
+
+    #[test]
+    fn delaunay_polygon_draws_shared_edges_once() {
+        let sites = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)].map(|(x, y)| Point::new(x, y));
+        let poly = delaunay_polygon(&sites, &[[0, 1, 2], [0, 2, 3]], &mut utils::seeded_rng(0));
+        assert_eq!(poly.verts.len(), 4);
+        assert_eq!(poly.faces.len(), 2);
+        assert_eq!(poly.edges.len(), 5);
+    }
 }
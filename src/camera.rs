@@ -0,0 +1,185 @@
+//! A pannable, zoomable view onto the world, shared by the viewers.
+//!
+//! Without a [`Viewport`] world coordinates are screen pixels. A viewport
+//! keeps that mapping at startup (the world origin on the top left corner, one
+//! pixel per unit, y down) and then follows the mouse: the wheel zooms around
+//! the cursor, dragging with the middle button pans and `F` fits the view to
+//! the content.
+
+use euclid::default::Box2D;
+use macroquad::prelude::*;
+
+/// How much a single wheel step zooms in or out.
+pub const ZOOM_STEP: f32 = 1.1;
+pub const MIN_ZOOM: f32 = 1.0 / 64.0;
+pub const MAX_ZOOM: f32 = 256.0;
+/// The part of the screen left free on each side by [`Viewport::fit`].
+pub const FIT_MARGIN: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// The world point on the top left corner of the screen.
+    pub origin: Vec2,
+    /// Screen pixels per world unit.
+    pub zoom: f32,
+    // mouse position the last frame the middle button was down
+    drag: Option<Vec2>,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self { origin: Vec2::ZERO, zoom: 1.0, drag: None }
+    }
+}
+
+impl Viewport {
+    pub fn world_to_screen(&self, world: Vec2) -> Vec2 {
+        (world - self.origin) * self.zoom
+    }
+
+    pub fn screen_to_world(&self, screen: Vec2) -> Vec2 {
+        screen / self.zoom + self.origin
+    }
+
+    /// Scales the view by `factor` while keeping the world point under `screen` in place.
+    pub fn zoom_at(&mut self, screen: Vec2, factor: f32) {
+        let anchor = self.screen_to_world(screen);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.origin = anchor - screen / self.zoom;
+    }
+
+    /// Moves the view so the world follows the mouse by `screen_delta` pixels.
+    pub fn pan(&mut self, screen_delta: Vec2) {
+        self.origin -= screen_delta / self.zoom;
+    }
+
+    /// Centers `aabb` on a screen of the given size, as large as it fits.
+    /// Empty boxes are centered without changing the zoom.
+    pub fn fit(&mut self, aabb: &Box2D<f32>, screen_size: Vec2) {
+        let (min, max) = (vec2(aabb.min.x, aabb.min.y), vec2(aabb.max.x, aabb.max.y));
+        if !(min.is_finite() && max.is_finite()) || aabb.is_negative() { return; }
+        let available = screen_size * (1.0 - 2.0 * FIT_MARGIN);
+        let size = max - min;
+        let fits = |avail: f32, extent: f32| if extent > 0.0 { avail / extent } else { f32::INFINITY };
+        let zoom = fits(available.x, size.x).min(fits(available.y, size.y));
+        if zoom.is_finite() {
+            self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+        self.origin = (min + max) / 2.0 - screen_size / (2.0 * self.zoom);
+    }
+
+    /// The macroquad camera showing this view on a screen of the given size.
+    pub fn camera(&self, screen_size: Vec2) -> Camera2D {
+        Camera2D {
+            target: self.screen_to_world(screen_size / 2.0),
+            zoom: 2.0 * self.zoom / screen_size,
+            ..Default::default()
+        }
+    }
+
+    /// Draws through this view until `set_default_camera` is called.
+    pub fn activate(&self) {
+        set_camera(&self.camera(vec2(screen_width(), screen_height())));
+    }
+
+    /// The mouse position in world coordinates, shaped like `mouse_position()`.
+    pub fn mouse_world(&self) -> (f32, f32) {
+        self.screen_to_world(mouse_position().into()).into()
+    }
+
+    /// Handles zooming, panning and fitting for this frame. `content` is
+    /// only asked for its bounds when the view is fitted.
+    pub fn update(&mut self, content: impl FnOnce() -> Option<Box2D<f32>>) {
+        let mouse = Vec2::from(mouse_position());
+        let wheel = mouse_wheel().1;
+        if wheel != 0.0 {
+            self.zoom_at(mouse, ZOOM_STEP.powf(wheel.signum()));
+        }
+
+        if is_mouse_button_down(MouseButton::Middle) {
+            if let Some(prev) = self.drag {
+                self.pan(mouse - prev);
+            }
+            self.drag = Some(mouse);
+        } else {
+            self.drag = None;
+        }
+
+        if is_key_released(KeyCode::F) {
+            if let Some(aabb) = content() {
+                self.fit(&aabb, vec2(screen_width(), screen_height()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-3, "{a} != {b}");
+    }
+
+    #[test]
+    fn starts_as_screen_pixels() {
+        let view = Viewport::default();
+        assert_eq!(view.world_to_screen(vec2(12.0, 34.0)), vec2(12.0, 34.0));
+        assert_eq!(view.screen_to_world(vec2(12.0, 34.0)), vec2(12.0, 34.0));
+    }
+
+    #[test]
+    fn zoom_keeps_the_cursor_in_place() {
+        let mut view = Viewport::default();
+        let cursor = vec2(300.0, 200.0);
+        let under = view.screen_to_world(cursor);
+        view.zoom_at(cursor, 4.0);
+        view.pan(vec2(-50.0, 10.0));
+        view.zoom_at(vec2(100.0, 100.0), 0.5);
+        assert_close(view.world_to_screen(view.screen_to_world(cursor)), cursor);
+        let mut fresh = Viewport::default();
+        fresh.zoom_at(cursor, 3.0);
+        assert_close(fresh.screen_to_world(cursor), under);
+        assert_eq!(fresh.zoom, 3.0);
+
+        fresh.zoom_at(cursor, 1e9);
+        assert_eq!(fresh.zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn pan_follows_the_mouse() {
+        let mut view = Viewport { zoom: 2.0, ..Default::default() };
+        let grabbed = view.screen_to_world(vec2(10.0, 10.0));
+        view.pan(vec2(30.0, -20.0));
+        assert_close(view.world_to_screen(grabbed), vec2(40.0, -10.0));
+    }
+
+    #[test]
+    fn fit_centers_content() {
+        let mut view = Viewport::default();
+        let screen = vec2(1000.0, 500.0);
+        view.fit(&Box2D::new((0.0, 0.0).into(), (10.0, 10.0).into()), screen);
+        assert_eq!(view.zoom, 500.0 * (1.0 - 2.0 * FIT_MARGIN) / 10.0);
+        assert_close(view.world_to_screen(vec2(5.0, 5.0)), screen / 2.0);
+
+        // a single point keeps the zoom
+        view.fit(&Box2D::new((7.0, 7.0).into(), (7.0, 7.0).into()), screen);
+        assert_eq!(view.zoom, 45.0);
+        assert_close(view.world_to_screen(vec2(7.0, 7.0)), screen / 2.0);
+    }
+
+    #[test]
+    fn camera_matches_the_transform() {
+        let mut view = Viewport::default();
+        view.zoom_at(vec2(200.0, 100.0), 2.5);
+        view.pan(vec2(13.0, 7.0));
+        let screen = vec2(800.0, 600.0);
+        let matrix = view.camera(screen).matrix();
+        for world in [vec2(0.0, 0.0), vec2(123.0, -45.0), vec2(400.0, 300.0)] {
+            let ndc = matrix.transform_point3(vec3(world.x, world.y, 0.0));
+            // the same mapping from clip space to window pixels macroquad uses
+            let pixel = vec2((ndc.x / 2.0 + 0.5) * screen.x, (0.5 - ndc.y / 2.0) * screen.y);
+            assert_close(pixel, view.world_to_screen(world));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Command line arguments shared by the viewers: positional arguments plus
+//! `--name value` (or `--name=value`) options and bare `--name` flags.
+
+use std::{collections::HashMap, str::FromStr};
+
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    /// Splits `args`, leaving out the program name. Only the options named in
+    /// `valued` take the following argument as their value, and only when it
+    /// isn't another option, so they work as bare flags too.
+    pub fn parse(args: impl IntoIterator<Item = String>, valued: &[&str]) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(option) => match option.split_once('=') {
+                    Some((name, value)) => { parsed.options.insert(name.to_string(), Some(value.to_string())); }
+                    None => {
+                        let value = if valued.contains(&option) { args.next_if(|a| !a.starts_with("--")) } else { None };
+                        parsed.options.insert(option.to_string(), value);
+                    }
+                },
+                None => parsed.positional.push(arg),
+            }
+        }
+        parsed
+    }
+
+    /// The arguments the program was started with.
+    pub fn from_env(valued: &[&str]) -> Self {
+        Self::parse(std::env::args(), valued)
+    }
+
+    /// The `i`th positional argument, or `default` when there are fewer.
+    /// Panics when it doesn't parse, like a bad argument should.
+    pub fn positional<T: FromStr>(&self, i: usize, default: T) -> T {
+        self.positional.get(i).map_or(default, |arg| {
+            arg.parse().unwrap_or_else(|_| panic!("couldn't parse argument {} ({arg:?})", i + 1))
+        })
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name)?.as_deref()
+    }
+
+    /// The value of option `name` parsed as a `T`, panicking when it doesn't parse.
+    pub fn parsed_option<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.option(name).map(|value| {
+            value.parse().unwrap_or_else(|_| panic!("couldn't parse --{name} ({value:?})"))
+        })
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Args {
+        Args::parse(line.split_whitespace().map(String::from), &["scene", "bench", "runs"])
+    }
+
+    #[test]
+    fn options_mix_with_positionals() {
+        let a = args("prog 30 --scene maze.json 20 --fast --tag=x --seed=42");
+        assert_eq!(a.positional(0, 0usize), 30);
+        assert_eq!(a.positional(1, 0usize), 20);
+        assert_eq!(a.positional(2, 5usize), 5);
+        assert_eq!(a.option("scene"), Some("maze.json"));
+        assert_eq!(a.option("tag"), Some("x"));
+        assert!(a.flag("fast") && a.flag("scene"));
+        assert!(!a.flag("slow"));
+        assert_eq!(a.option("fast"), None);
+        assert_eq!(a.parsed_option::<u64>("seed"), Some(42));
+        assert_eq!(a.parsed_option::<u64>("nope"), None);
+
+        // a valued option followed by another option is a bare flag
+        let a = args("prog --bench --runs 3");
+        assert!(a.flag("bench"));
+        assert_eq!(a.option("bench"), None);
+        assert_eq!(a.option("runs"), Some("3"));
+    }
+}
@@ -1,9 +1,14 @@
 use euclid::default::{Vector2D, Box2D};
-use crate::geom::Vertex;
+use crate::{geom::Vertex, render::{Renderer, Screen}};
 
 pub trait Draw {
-    fn draw(&self);
+    fn render(&self, target: &mut dyn Renderer);
     fn vertices(&self) -> Vec<Vertex>;
+
+    /// Renders straight to the window.
+    fn draw(&self) {
+        self.render(&mut Screen);
+    }
 }
 
 pub trait Select {
@@ -12,6 +17,94 @@ pub trait Select {
     fn contains_point(&self, point: &Vector2D<f32>) -> bool {
         self.sample_signed_distance_field(point) <= 0.0
     }
+
+    /// Everything inside either shape.
+    fn union<S: Select>(self, other: S) -> Union<Self, S> where Self: Sized {
+        Union(self, other)
+    }
+
+    /// Everything inside both shapes.
+    fn intersection<S: Select>(self, other: S) -> Intersection<Self, S> where Self: Sized {
+        Intersection(self, other)
+    }
+
+    /// The shape grown by `radius`, rounding its corners; negative radii shrink it.
+    fn offset(self, radius: f32) -> Offset<Self> where Self: Sized {
+        Offset(self, radius)
+    }
+
+    /// Everything outside the shape.
+    fn invert(self) -> Invert<Self> where Self: Sized {
+        Invert(self)
+    }
+}
+
+impl<S: Select + ?Sized> Select for &S {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        (**self).compute_aabb()
+    }
+
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        (**self).sample_signed_distance_field(global_sample_point)
+    }
+}
+
+/// See [`Select::union`]. The field is exact outside both shapes.
+#[derive(Debug, Clone)]
+pub struct Union<A, B>(pub A, pub B);
+
+impl<A: Select, B: Select> Select for Union<A, B> {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        self.0.compute_aabb().union(&self.1.compute_aabb())
+    }
+
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        self.0.sample_signed_distance_field(global_sample_point)
+            .min(self.1.sample_signed_distance_field(global_sample_point))
+    }
+}
+
+/// See [`Select::intersection`]. The field is exact inside both shapes.
+#[derive(Debug, Clone)]
+pub struct Intersection<A, B>(pub A, pub B);
+
+impl<A: Select, B: Select> Select for Intersection<A, B> {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        self.0.compute_aabb().intersection(&self.1.compute_aabb()).unwrap_or_else(Box2D::zero)
+    }
+
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        self.0.sample_signed_distance_field(global_sample_point)
+            .max(self.1.sample_signed_distance_field(global_sample_point))
+    }
+}
+
+/// See [`Select::offset`].
+#[derive(Debug, Clone)]
+pub struct Offset<S>(pub S, pub f32);
+
+impl<S: Select> Select for Offset<S> {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        self.0.compute_aabb().inflate(self.1, self.1)
+    }
+
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        self.0.sample_signed_distance_field(global_sample_point) - self.1
+    }
+}
+
+/// See [`Select::invert`]. Its bounding box covers the whole plane.
+#[derive(Debug, Clone)]
+pub struct Invert<S>(pub S);
+
+impl<S: Select> Select for Invert<S> {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        Box2D::new((f32::NEG_INFINITY, f32::NEG_INFINITY).into(), (f32::INFINITY, f32::INFINITY).into())
+    }
+
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        -self.0.sample_signed_distance_field(global_sample_point)
+    }
 }
 
 pub trait Element: Draw + Select + std::fmt::Debug {}
@@ -19,3 +112,47 @@ pub trait Element: Draw + Select + std::fmt::Debug {}
 impl<T: Draw + Select + std::fmt::Debug> Element for T {
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::{Circle, Line2D};
+    use euclid::vec2;
+
+    fn circle(x: f32, y: f32, radius: f32) -> Circle {
+        Circle { center: Vertex::new(x, y, None), radius }
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let (a, b) = (circle(0.0, 0.0, 2.0), circle(3.0, 0.0, 2.0));
+        let union = (&a).union(&b);
+        let lens = (&a).intersection(&b);
+        assert!(union.contains_point(&vec2(-1.5, 0.0)) && union.contains_point(&vec2(4.5, 0.0)));
+        assert!(!lens.contains_point(&vec2(-1.5, 0.0)) && lens.contains_point(&vec2(1.5, 0.0)));
+        assert_eq!(union.sample_signed_distance_field(&vec2(8.0, 0.0)), 3.0);
+        assert_eq!(lens.compute_aabb(), Box2D::new((1.0, -2.0).into(), (2.0, 2.0).into()));
+        assert_eq!(union.compute_aabb(), Box2D::new((-2.0, -2.0).into(), (5.0, 2.0).into()));
+        assert_eq!(circle(0.0, 0.0, 1.0).intersection(circle(9.0, 0.0, 1.0)).compute_aabb(), Box2D::zero());
+    }
+
+    #[test]
+    fn offset_rounds_a_segment_into_a_capsule() {
+        let line = Line2D { a: Vertex::new(0.0, 0.0, None), b: Vertex::new(10.0, 0.0, None), thickness: 0.0 };
+        let capsule = line.offset(2.0);
+        assert_eq!(capsule.sample_signed_distance_field(&vec2(5.0, 0.0)), -2.0);
+        assert_eq!(capsule.sample_signed_distance_field(&vec2(13.0, 4.0)), 3.0);
+        assert_eq!(capsule.compute_aabb(), Box2D::new((-2.0, -2.0).into(), (12.0, 2.0).into()));
+    }
+
+    #[test]
+    fn invert_swaps_inside_and_outside() {
+        // a ring: the big disc minus the small one
+        let ring = circle(0.0, 0.0, 5.0).intersection(circle(0.0, 0.0, 3.0).invert());
+        assert!(!ring.contains_point(&vec2(0.0, 0.0)));
+        assert!(ring.contains_point(&vec2(4.0, 0.0)));
+        assert!(!ring.contains_point(&vec2(6.0, 0.0)));
+        assert_eq!(ring.sample_signed_distance_field(&vec2(1.0, 0.0)), 2.0);
+        assert_eq!(ring.compute_aabb(), circle(0.0, 0.0, 5.0).compute_aabb());
+    }
+}
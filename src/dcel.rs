@@ -0,0 +1,994 @@
+//! A doubly connected edge list: a subdivision of the plane into vertices,
+//! edges and the faces between them, where every edge is a pair of
+//! half-edges running along either side of it.
+//!
+//! A half-edge has its face on its left, so bounded faces go round
+//! counter-clockwise and the unbounded face around each connected piece
+//! clockwise. At a vertex, the half-edge after one coming in is the next
+//! one going out clockwise. Faces are the boundary cycles: a piece floating
+//! inside a face has a face of its own around it rather than making a hole
+//! in that one.
+//!
+//! Vertices, half-edges and faces are named by typed ids, which stay valid
+//! when others are removed. Half-edges and faces carry data of the caller's
+//! choosing, which the pieces of a split edge or face inherit.
+
+use std::{error::Error, f64::consts::TAU, fmt, iter};
+use macroquad::color::Color;
+use crate::{
+    geom::{segment_intersection, Polygon, Segment, Vertex},
+    point::Point,
+    predicates::{opposite_signs, orient2d, strictly_on_segment},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VertexId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HalfEdgeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FaceId(usize);
+
+impl VertexId {
+    /// The vertex added `index`th, counting from 0.
+    pub fn new(index: usize) -> Self {
+        Self(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl HalfEdgeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl FaceId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// The invariant [`Dcel::check_invariants`] found broken, and where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DcelError {
+    /// A half-edge isn't its twin's twin, or starts where its twin does.
+    Twin(HalfEdgeId),
+    /// A half-edge has no origin or next half-edge, or a removed one.
+    Unlinked(HalfEdgeId),
+    /// The next half-edge doesn't start where this one ends, or doesn't
+    /// have it as its previous one.
+    Next(HalfEdgeId),
+    /// A half-edge has no face, or not the one of the half-edge after it.
+    Face(HalfEdgeId),
+    /// A vertex's half-edge doesn't start at it, or the edges around it
+    /// aren't in counter-clockwise order.
+    Vertex(VertexId),
+    /// A face's half-edge was removed or is in another face.
+    FaceEdge(FaceId),
+    /// Some boundary cycle doesn't have a face of its own.
+    Cycles { cycles: usize, faces: usize },
+}
+
+impl fmt::Display for DcelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DcelError::Twin(h) => write!(f, "half-edge {} and its twin don't pair up", h.0),
+            DcelError::Unlinked(h) => write!(f, "half-edge {} isn't linked up", h.0),
+            DcelError::Next(h) => write!(f, "half-edge {} doesn't lead into its next one", h.0),
+            DcelError::Face(h) => write!(f, "half-edge {} isn't in the face of its cycle", h.0),
+            DcelError::Vertex(v) => write!(f, "the edges around vertex {} are out of order", v.0),
+            DcelError::FaceEdge(face) => write!(f, "face {} doesn't bound itself", face.0),
+            DcelError::Cycles { cycles, faces } => write!(f, "{cycles} boundary cycles but {faces} faces"),
+        }
+    }
+}
+
+impl Error for DcelError {}
+
+#[derive(Debug, Clone)]
+struct VertexSlot {
+    pos: Point,
+    /// A half-edge going out of it, none while it's isolated.
+    edge: Option<HalfEdgeId>,
+    alive: bool,
+}
+
+#[derive(Debug, Clone)]
+struct HalfEdge<E> {
+    origin: Option<VertexId>,
+    twin: HalfEdgeId,
+    next: Option<HalfEdgeId>,
+    prev: Option<HalfEdgeId>,
+    face: Option<FaceId>,
+    data: E,
+    alive: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Face<F> {
+    /// A half-edge of its boundary.
+    edge: HalfEdgeId,
+    data: F,
+    alive: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dcel<E = (), F = ()> {
+    vertices: Vec<VertexSlot>,
+    halfedges: Vec<HalfEdge<E>>,
+    faces: Vec<Face<F>>,
+    /// Whether the edits keep the faces up to date, which linking
+    /// half-edges by hand stops until [`Dcel::build_faces`].
+    tracks_faces: bool,
+}
+
+impl<E, F> Default for Dcel<E, F> {
+    fn default() -> Self {
+        Self { vertices: vec![], halfedges: vec![], faces: vec![], tracks_faces: true }
+    }
+}
+
+fn angle(d: Point) -> f64 {
+    d.y().atan2(d.x())
+}
+
+/// Cuts a counter-clockwise polygon into triangles by clipping ears, as
+/// indices into `points`. Polygons touching themselves can be left with a
+/// piece no ear can be cut from, which is left out.
+fn ear_clip(points: &[Point]) -> Vec<[usize; 3]> {
+    let mut left: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = vec![];
+    while left.len() >= 3 {
+        let n = left.len();
+        let corner = |i: usize| [left[(i + n - 1) % n], left[i], left[(i + 1) % n]];
+        let inside = |[a, b, c]: [usize; 3], p: usize| {
+            ![a, b, c].contains(&p)
+                && orient2d(points[a], points[b], points[p]) >= 0.0
+                && orient2d(points[b], points[c], points[p]) >= 0.0
+                && orient2d(points[c], points[a], points[p]) >= 0.0
+        };
+        let ear = (0..n).find(|&i| {
+            let [a, b, c] = corner(i);
+            orient2d(points[a], points[b], points[c]) > 0.0 && !left.iter().any(|&p| inside([a, b, c], p))
+        });
+        if let Some(i) = ear {
+            triangles.push(corner(i));
+            left.remove(i);
+            continue;
+        }
+        // a corner with nothing to it goes without a triangle
+        match (0..n).find(|&i| { let [a, b, c] = corner(i); orient2d(points[a], points[b], points[c]) == 0.0 }) {
+            Some(i) => { left.remove(i); }
+            None => break,
+        }
+    }
+    triangles
+}
+
+impl<E, F> Dcel<E, F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.iter().filter(|v| v.alive).count()
+    }
+
+    /// The edges, each a pair of half-edges.
+    pub fn edge_count(&self) -> usize {
+        self.halfedges.iter().filter(|h| h.alive).count() / 2
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.faces.iter().filter(|f| f.alive).count()
+    }
+
+    pub fn vertices(&self) -> impl Iterator<Item = VertexId> + '_ {
+        (0..self.vertices.len()).filter(|&v| self.vertices[v].alive).map(VertexId)
+    }
+
+    pub fn halfedges(&self) -> impl Iterator<Item = HalfEdgeId> + '_ {
+        (0..self.halfedges.len()).filter(|&h| self.halfedges[h].alive).map(HalfEdgeId)
+    }
+
+    /// A half-edge of every edge.
+    pub fn edges(&self) -> impl Iterator<Item = HalfEdgeId> + '_ {
+        self.halfedges().filter(|&h| h < self.twin(h))
+    }
+
+    pub fn faces(&self) -> impl Iterator<Item = FaceId> + '_ {
+        (0..self.faces.len()).filter(|&f| self.faces[f].alive).map(FaceId)
+    }
+
+    /// The faces with a counter-clockwise boundary, leaving out the
+    /// unbounded ones around the pieces.
+    pub fn bounded_faces(&self) -> impl Iterator<Item = FaceId> + '_ {
+        self.faces().filter(|&f| self.signed_area(f) > 0.0)
+    }
+
+    pub fn pos(&self, v: VertexId) -> Point {
+        self.vertices[v.0].pos
+    }
+
+    /// A half-edge going out of `v`, `None` while nothing does.
+    pub fn vertex_edge(&self, v: VertexId) -> Option<HalfEdgeId> {
+        self.vertices[v.0].edge
+    }
+
+    pub fn origin(&self, h: HalfEdgeId) -> Option<VertexId> {
+        self.halfedges[h.0].origin
+    }
+
+    /// Where `h` ends: its twin's origin.
+    pub fn dest(&self, h: HalfEdgeId) -> Option<VertexId> {
+        self.origin(self.twin(h))
+    }
+
+    pub fn twin(&self, h: HalfEdgeId) -> HalfEdgeId {
+        self.halfedges[h.0].twin
+    }
+
+    pub fn next(&self, h: HalfEdgeId) -> Option<HalfEdgeId> {
+        self.halfedges[h.0].next
+    }
+
+    pub fn prev(&self, h: HalfEdgeId) -> Option<HalfEdgeId> {
+        self.halfedges[h.0].prev
+    }
+
+    /// The face on the left of `h`, `None` until there are faces.
+    pub fn face(&self, h: HalfEdgeId) -> Option<FaceId> {
+        self.halfedges[h.0].face
+    }
+
+    /// A half-edge of the face's boundary.
+    pub fn face_edge(&self, f: FaceId) -> HalfEdgeId {
+        self.faces[f.0].edge
+    }
+
+    pub fn segment(&self, h: HalfEdgeId) -> Option<Segment> {
+        Some([self.pos(self.origin(h)?), self.pos(self.dest(h)?)])
+    }
+
+    pub fn data(&self, h: HalfEdgeId) -> &E {
+        &self.halfedges[h.0].data
+    }
+
+    pub fn data_mut(&mut self, h: HalfEdgeId) -> &mut E {
+        &mut self.halfedges[h.0].data
+    }
+
+    pub fn face_data(&self, f: FaceId) -> &F {
+        &self.faces[f.0].data
+    }
+
+    pub fn face_data_mut(&mut self, f: FaceId) -> &mut F {
+        &mut self.faces[f.0].data
+    }
+
+    /// The vertex at `p`, the first one added where there are several.
+    pub fn find_vertex(&self, p: Point) -> Option<VertexId> {
+        self.vertices().find(|&v| self.pos(v) == p)
+    }
+
+    /// The half-edge from `a` to `b`.
+    pub fn find_edge(&self, a: VertexId, b: VertexId) -> Option<HalfEdgeId> {
+        self.outgoing(a).find(|&h| self.dest(h) == Some(b))
+    }
+
+    /// The boundary cycle `h` is on, from `h` along the next half-edges.
+    pub fn cycle(&self, h: HalfEdgeId) -> impl Iterator<Item = HalfEdgeId> + '_ {
+        self.walk(h, |h| self.next(h))
+    }
+
+    pub fn face_edges(&self, f: FaceId) -> impl Iterator<Item = HalfEdgeId> + '_ {
+        self.cycle(self.face_edge(f))
+    }
+
+    pub fn face_vertices(&self, f: FaceId) -> impl Iterator<Item = VertexId> + '_ {
+        self.face_edges(f).filter_map(|h| self.origin(h))
+    }
+
+    pub fn face_points(&self, f: FaceId) -> impl Iterator<Item = Point> + '_ {
+        self.face_vertices(f).map(|v| self.pos(v))
+    }
+
+    /// The half-edges going out of `v`, counter-clockwise.
+    pub fn outgoing(&self, v: VertexId) -> impl Iterator<Item = HalfEdgeId> + '_ {
+        let start = self.vertex_edge(v);
+        start.into_iter().flat_map(move |h| self.walk(h, |h| Some(self.twin(self.prev(h)?))))
+    }
+
+    /// The vertices sharing an edge with `v`, counter-clockwise.
+    pub fn neighbours(&self, v: VertexId) -> impl Iterator<Item = VertexId> + '_ {
+        self.outgoing(v).filter_map(|h| self.dest(h))
+    }
+
+    /// The faces around `v`, counter-clockwise, a face coming up again
+    /// wherever it touches `v` again.
+    pub fn vertex_faces(&self, v: VertexId) -> impl Iterator<Item = FaceId> + '_ {
+        self.outgoing(v).filter_map(|h| self.face(h))
+    }
+
+    /// The faces across the edges of `f`'s boundary, in order.
+    pub fn adjacent_faces(&self, f: FaceId) -> impl Iterator<Item = FaceId> + '_ {
+        self.face_edges(f).filter_map(|h| self.face(self.twin(h)))
+    }
+
+    /// The area inside `f`'s boundary, negative for the unbounded faces.
+    pub fn signed_area(&self, f: FaceId) -> f64 {
+        let points: Vec<Point> = self.face_points(f).collect();
+        (0..points.len()).map(|i| points[i].cross(points[(i + 1) % points.len()])).sum::<f64>() / 2.0
+    }
+
+    /// Whether `f`'s boundary winds around `p`, so for an unbounded face
+    /// whether `p` is inside the piece it goes around.
+    pub fn encloses(&self, f: FaceId, p: Point) -> bool {
+        let mut winding = 0;
+        for h in self.face_edges(f) {
+            let Some([a, b]) = self.segment(h) else { continue };
+            if a.y() <= p.y() {
+                if b.y() > p.y() && orient2d(a, b, p) > 0.0 { winding += 1; }
+            } else if b.y() <= p.y() && orient2d(a, b, p) < 0.0 {
+                winding -= 1;
+            }
+        }
+        winding != 0
+    }
+
+    /// The bounded face `p` is in, the innermost where pieces float inside
+    /// faces, `None` outside them all. Which side a point on an edge goes to
+    /// is up to rounding.
+    pub fn locate(&self, p: Point) -> Option<FaceId> {
+        self.bounded_faces()
+            .filter(|&f| self.encloses(f, p))
+            .min_by(|&f, &g| self.signed_area(f).total_cmp(&self.signed_area(g)))
+    }
+
+    /// A point in `f`, just off the middle of the longest edge of its
+    /// boundary, so it's clear of the pieces floating inside. `None` if the
+    /// boundary has no length.
+    pub fn point_in(&self, f: FaceId) -> Option<Point> {
+        let [a, b] = self.face_edges(f)
+            .filter_map(|h| self.segment(h))
+            .max_by(|[a, b], [c, d]| (*b - *a).magnitude().total_cmp(&(*d - *c).magnitude()))?;
+        let d = b - a;
+        if d.magnitude() == 0.0 { return None; }
+        // the face is on the left
+        Some(a.lerp(&b, 0.5) + Point::new(-d.y(), d.x()) * 1e-6)
+    }
+
+    /// Steps from `start` until it comes back round, or a step leads
+    /// nowhere, or too many steps were taken for the structure to be sound.
+    fn walk(&self, start: HalfEdgeId, step: impl Fn(HalfEdgeId) -> Option<HalfEdgeId>) -> impl Iterator<Item = HalfEdgeId> {
+        let mut current = Some(start);
+        let mut steps = 0;
+        let limit = self.halfedges.len();
+        iter::from_fn(move || {
+            let h = current?;
+            steps += 1;
+            current = step(h).filter(|&n| n != start && steps < limit);
+            Some(h)
+        })
+    }
+
+    /// Checks the half-edges pair up and link into cycles around the faces,
+    /// and that the edges around every vertex go round it once.
+    pub fn check_invariants(&self) -> Result<(), DcelError> {
+        let live_half = |h: Option<HalfEdgeId>| h.filter(|h| self.halfedges[h.0].alive);
+        for h in self.halfedges() {
+            let twin = self.twin(h);
+            if twin == h || !self.halfedges[twin.0].alive || self.twin(twin) != h {
+                return Err(DcelError::Twin(h));
+            }
+            let (Some(origin), Some(next)) = (self.origin(h), live_half(self.next(h))) else {
+                return Err(DcelError::Unlinked(h));
+            };
+            if !self.vertices[origin.0].alive {
+                return Err(DcelError::Unlinked(h));
+            }
+            if self.origin(twin) == Some(origin) {
+                return Err(DcelError::Twin(h));
+            }
+            if self.prev(next) != Some(h) || self.origin(next) != self.dest(h) {
+                return Err(DcelError::Next(h));
+            }
+            if self.tracks_faces && (self.face(h).is_none() || self.face(next) != self.face(h)) {
+                return Err(DcelError::Face(h));
+            }
+        }
+
+        for v in self.vertices() {
+            if let Some(h) = self.vertex_edge(v) {
+                if !self.halfedges[h.0].alive || self.origin(h) != Some(v) {
+                    return Err(DcelError::Vertex(v));
+                }
+            }
+            // going counter-clockwise, the angle only drops once, past -π
+            let angles: Vec<f64> = self.outgoing(v).filter_map(|h| self.dest(h)).map(|w| angle(self.pos(w) - self.pos(v))).collect();
+            let drops = (0..angles.len()).filter(|&i| angles[(i + 1) % angles.len()] <= angles[i]).count();
+            if angles.len() > 1 && drops != 1 {
+                return Err(DcelError::Vertex(v));
+            }
+        }
+        let around: usize = self.vertices().map(|v| self.outgoing(v).count()).sum();
+        if around != self.halfedges().count() {
+            let stray = self.halfedges().find(|&h| !self.outgoing(self.origin(h).unwrap()).any(|o| o == h)).unwrap();
+            return Err(DcelError::Vertex(self.origin(stray).unwrap()));
+        }
+
+        if self.tracks_faces {
+            for f in self.faces() {
+                let h = self.face_edge(f);
+                if !self.halfedges[h.0].alive || self.face(h) != Some(f) {
+                    return Err(DcelError::FaceEdge(f));
+                }
+            }
+            let mut seen = vec![false; self.halfedges.len()];
+            let mut cycles = 0;
+            for h in self.halfedges() {
+                if seen[h.0] { continue; }
+                cycles += 1;
+                for e in self.cycle(h) {
+                    seen[e.0] = true;
+                }
+            }
+            if cycles != self.face_count() {
+                return Err(DcelError::Cycles { cycles, faces: self.face_count() });
+            }
+        }
+        Ok(())
+    }
+
+    /// The subdivision as a polygon with every vertex slot, so vertex ids
+    /// index its `verts`, an edge per edge, and the bounded faces cut into
+    /// triangles of the colour `face_color` gives them, or left out.
+    pub fn to_polygon(&self, edge_color: Color, mut face_color: impl FnMut(FaceId) -> Option<Color>) -> Polygon {
+        let verts = self.vertices.iter().map(|v| Vertex::from(v.pos)).collect();
+        let edges = self.edges()
+            .filter_map(|h| Some((self.origin(h)?.0, self.dest(h)?.0, edge_color)))
+            .collect();
+        let mut faces = vec![];
+        for f in self.bounded_faces() {
+            let Some(clr) = face_color(f) else { continue };
+            let corners: Vec<VertexId> = self.face_vertices(f).collect();
+            let points: Vec<Point> = corners.iter().map(|&v| self.pos(v)).collect();
+            faces.extend(ear_clip(&points).into_iter().map(|[a, b, c]| (corners[a].0, corners[b].0, corners[c].0, clr)));
+        }
+        Polygon { verts, edges, edge_thickness: 1.0, faces }
+    }
+}
+
+impl<E: Clone + Default, F: Clone + Default> Dcel<E, F> {
+    /// The outline of `poly`: its vertices, numbered as in `verts`, and its
+    /// edges, split where they cross or run through a vertex.
+    pub fn from_polygon(poly: &Polygon) -> Self {
+        let mut dcel = Self::new();
+        for v in &poly.verts {
+            dcel.add_vertex(Point::from(v.pos));
+        }
+        for &(a, b, _) in &poly.edges {
+            dcel.insert_segment(Point::from(poly.verts[a].pos), Point::from(poly.verts[b].pos));
+        }
+        dcel
+    }
+
+    /// A face per triangle, of corners indexing into `points`, which become
+    /// the vertices in order. The triangles mustn't overlap.
+    pub fn from_triangles(points: &[Point], triangles: &[[usize; 3]]) -> Self {
+        let mut dcel = Self::new();
+        for &p in points {
+            dcel.add_vertex(p);
+        }
+        for &[a, b, c] in triangles {
+            for (u, w) in [(a, b), (b, c), (c, a)].map(|(u, w)| (VertexId(u), VertexId(w))) {
+                if dcel.find_edge(u, w).is_none() {
+                    dcel.add_edge(u, w);
+                }
+            }
+        }
+        dcel
+    }
+
+    /// An isolated vertex.
+    pub fn add_vertex(&mut self, pos: Point) -> VertexId {
+        self.vertices.push(VertexSlot { pos, edge: None, alive: true });
+        VertexId(self.vertices.len() - 1)
+    }
+
+    /// Two unlinked half-edges, twins of each other, for building the
+    /// subdivision by hand with [`Dcel::set_origin`] and [`Dcel::link`].
+    /// The faces stop following the edits until [`Dcel::build_faces`].
+    pub fn add_twins(&mut self) -> (HalfEdgeId, HalfEdgeId) {
+        self.tracks_faces = false;
+        self.push_twins(E::default(), E::default())
+    }
+
+    /// Has `h` start at `v`, see [`Dcel::add_twins`].
+    pub fn set_origin(&mut self, h: HalfEdgeId, v: VertexId) {
+        self.tracks_faces = false;
+        self.halfedges[h.0].origin = Some(v);
+        self.vertices[v.0].edge.get_or_insert(h);
+    }
+
+    /// Has `next` follow `h`, see [`Dcel::add_twins`].
+    pub fn link(&mut self, h: HalfEdgeId, next: HalfEdgeId) {
+        self.tracks_faces = false;
+        self.set_next(h, next);
+    }
+
+    /// Makes a face of every boundary cycle, in place of any faces there
+    /// were, and has the edits keep them up to date from then on.
+    ///
+    /// # Panics
+    ///
+    /// If a half-edge doesn't have its next one.
+    pub fn build_faces(&mut self) {
+        self.faces.clear();
+        for h in self.halfedges.iter_mut() {
+            h.face = None;
+        }
+        for h in 0..self.halfedges.len() {
+            if !self.halfedges[h].alive || self.halfedges[h].face.is_some() { continue; }
+            let f = self.push_face(HalfEdgeId(h), F::default());
+            let mut current = h;
+            loop {
+                self.halfedges[current].face = Some(f);
+                current = self.halfedges[current].next.unwrap_or_else(|| panic!("half-edge {current} has no next one")).0;
+                if current == h { break; }
+            }
+        }
+        self.tracks_faces = true;
+    }
+
+    /// Connects two vertices by a straight edge, which mustn't cross any
+    /// other, and returns the half-edge from `a` to `b`. Where it closes off
+    /// part of a face, the part on its left becomes a new face.
+    pub fn add_edge(&mut self, a: VertexId, b: VertexId) -> HalfEdgeId {
+        assert!(a != b, "edge from vertex {} to itself", a.0);
+        let (h, t) = self.push_twins(E::default(), E::default());
+        self.halfedges[h.0].origin = Some(a);
+        self.halfedges[t.0].origin = Some(b);
+        let fa = self.splice(h);
+        let fb = self.splice(t);
+        if !self.tracks_faces { return h; }
+
+        if self.cycle(h).any(|e| e == t) {
+            // joins two pieces, or reaches into a face, so a face or two become one
+            let f = match (fa, fb) {
+                (Some(fa), Some(fb)) => {
+                    if fa != fb {
+                        self.faces[fb.0].alive = false;
+                    }
+                    fa
+                }
+                (Some(f), None) | (None, Some(f)) => f,
+                (None, None) => self.push_face(h, F::default()),
+            };
+            self.set_cycle_face(h, f);
+        } else {
+            let f = fa.expect("both sides of a split face have it");
+            self.set_cycle_face(t, f);
+            let data = self.faces[f.0].data.clone();
+            let new = self.push_face(h, data);
+            self.set_cycle_face(h, new);
+        }
+        h
+    }
+
+    /// Takes out the edge of `h`, joining the faces on its sides or, where
+    /// it was all that held two pieces together, giving the one without the
+    /// face's half-edge a new face.
+    pub fn remove_edge(&mut self, h: HalfEdgeId) {
+        let t = self.twin(h);
+        let (hn, tn) = (self.next(h).filter(|&n| n != t), self.next(t).filter(|&n| n != h));
+        for (e, after_twin) in [(h, tn), (t, hn)] {
+            // what came into e's origin now leads on to what left after the twin
+            if let (Some(p), Some(n)) = (self.prev(e), after_twin) {
+                self.set_next(p, n);
+            }
+            if let Some(v) = self.origin(e) {
+                if self.vertices[v.0].edge == Some(e) {
+                    self.vertices[v.0].edge = after_twin;
+                }
+            }
+        }
+        self.halfedges[h.0].alive = false;
+        self.halfedges[t.0].alive = false;
+        if !self.tracks_faces { return; }
+
+        let (fh, ft) = (self.face(h).unwrap(), self.face(t).unwrap());
+        let rest: Vec<HalfEdgeId> = [hn, tn].into_iter().flatten().collect();
+        if fh != ft {
+            self.faces[ft.0].alive = false;
+            self.set_cycle_face(rest[0], fh);
+        } else if rest.is_empty() {
+            self.faces[fh.0].alive = false;
+        } else if rest.len() == 2 && !self.cycle(rest[0]).any(|e| e == rest[1]) {
+            let edge = self.faces[fh.0].edge;
+            let (keep, split) = if self.cycle(rest[1]).any(|e| e == edge) { (rest[1], rest[0]) } else { (rest[0], rest[1]) };
+            self.set_cycle_face(keep, fh);
+            let data = self.faces[fh.0].data.clone();
+            let new = self.push_face(split, data);
+            self.set_cycle_face(split, new);
+        } else {
+            self.set_cycle_face(rest[0], fh);
+        }
+    }
+
+    /// Takes out `v` and its edges.
+    pub fn remove_vertex(&mut self, v: VertexId) {
+        while let Some(h) = self.vertex_edge(v) {
+            self.remove_edge(h);
+        }
+        self.vertices[v.0].alive = false;
+    }
+
+    /// Puts a vertex at `pos` on the edge of `h`, which doesn't check it's
+    /// on the way, and returns it. `h` and its twin become the pieces
+    /// towards their origins, and the new pieces copy their data.
+    pub fn split_edge(&mut self, h: HalfEdgeId, pos: Point) -> VertexId {
+        let t = self.twin(h);
+        let m = self.add_vertex(pos);
+        let (h2, t2) = self.push_twins(self.data(h).clone(), self.data(t).clone());
+        // h and the new piece after it, t and the one after it, pair up crosswise
+        self.halfedges[h.0].twin = t2;
+        self.halfedges[t2.0].twin = h;
+        self.halfedges[t.0].twin = h2;
+        self.halfedges[h2.0].twin = t;
+        for (e, piece) in [(h, h2), (t, t2)] {
+            self.halfedges[piece.0].origin = Some(m);
+            self.halfedges[piece.0].face = self.face(e);
+            match self.next(e) {
+                Some(n) => self.set_next(piece, n),
+                // a piece of a half-edge that ends nowhere yet does so too
+                None => self.halfedges[piece.0].next = None,
+            }
+            self.set_next(e, piece);
+        }
+        self.vertices[m.0].edge = Some(h2);
+        m
+    }
+
+    /// The reverse of [`Dcel::split_edge`]: joins the two edges at `v` into
+    /// one and takes `v` out. The half-edges that came into `v` run on past
+    /// it, and one of them is returned. `None`, leaving things be, unless
+    /// `v` has two edges to different vertices.
+    pub fn merge_edges(&mut self, v: VertexId) -> Option<HalfEdgeId> {
+        let out: Vec<HalfEdgeId> = self.outgoing(v).collect();
+        let &[o1, o2] = &out[..] else { return None };
+        let (i1, i2) = (self.twin(o1), self.twin(o2));
+        if self.origin(i1)? == self.origin(i2)? { return None; }
+
+        // i1 comes in and runs on where o2 went, i2 where o1 went
+        let (n1, n2) = (self.next(o1), self.next(o2));
+        self.halfedges[i1.0].twin = i2;
+        self.halfedges[i2.0].twin = i1;
+        for (e, n) in [(i1, n2), (i2, n1)] {
+            match n {
+                Some(n) => self.set_next(e, n),
+                None => self.halfedges[e.0].next = None,
+            }
+        }
+        for (gone, stays) in [(o2, i1), (o1, i2)] {
+            if let Some(f) = self.face(gone) {
+                if self.faces[f.0].edge == gone {
+                    self.faces[f.0].edge = stays;
+                }
+            }
+            self.halfedges[gone.0].alive = false;
+        }
+        self.vertices[v.0].alive = false;
+        self.vertices[v.0].edge = None;
+        Some(i1)
+    }
+
+    /// Turns the edge of `h`, the diagonal of the two triangles on its
+    /// sides, into their other diagonal, `h` running between the corners
+    /// that were off it. `false`, leaving things be, unless both faces are
+    /// triangles that make a convex quadrilateral.
+    pub fn flip_edge(&mut self, h: HalfEdgeId) -> bool {
+        let t = self.twin(h);
+        let triangle = |e: HalfEdgeId| {
+            let cycle: Vec<HalfEdgeId> = self.cycle(e).collect();
+            cycle.len() == 3 && self.next(cycle[2]) == Some(e)
+        };
+        if !triangle(h) || !triangle(t) { return false; }
+        let (h1, t1) = (self.next(h).unwrap(), self.next(t).unwrap());
+        let (h2, t2) = (self.next(h1).unwrap(), self.next(t1).unwrap());
+        let [Some(a), Some(b), Some(c), Some(d)] = [h, t, h2, t2].map(|e| self.origin(e)) else { return false };
+        let (pc, pd) = (self.pos(c), self.pos(d));
+        // c–d has to cross a–b for the quadrilateral to be convex
+        if !opposite_signs(orient2d(pc, pd, self.pos(a)), orient2d(pc, pd, self.pos(b))) { return false; }
+
+        self.halfedges[h.0].origin = Some(d);
+        self.halfedges[t.0].origin = Some(c);
+        for [x, y, z] in [[h, h2, t1], [t, t2, h1]] {
+            self.set_next(x, y);
+            self.set_next(y, z);
+            self.set_next(z, x);
+        }
+        let (fh, ft) = (self.face(h), self.face(t));
+        self.halfedges[t1.0].face = fh;
+        self.halfedges[h1.0].face = ft;
+        for (f, e) in [(fh, h), (ft, t)] {
+            if let Some(f) = f {
+                self.faces[f.0].edge = e;
+            }
+        }
+        if self.vertices[a.0].edge == Some(h) {
+            self.vertices[a.0].edge = Some(t1);
+        }
+        if self.vertices[b.0].edge == Some(t) {
+            self.vertices[b.0].edge = Some(h1);
+        }
+        true
+    }
+
+    /// Adds the segment from `a` to `b`, reusing the vertices already at
+    /// its ends, splitting the edges it crosses and running through the
+    /// vertices on its way, and where it runs along edges, along those.
+    /// Returns the vertices along it, from `a` to `b`.
+    pub fn insert_segment(&mut self, a: Point, b: Point) -> Vec<VertexId> {
+        let (va, vb) = (self.vertex_at(a), self.vertex_at(b));
+        if va == vb { return vec![va]; }
+        let param = |p: Point| (p - a).dot(b - a) / (b - a).dot(b - a);
+        let mut along: Vec<(f64, VertexId)> = vec![(0.0, va), (1.0, vb)];
+        for v in self.vertices() {
+            if strictly_on_segment(a, b, self.pos(v)) {
+                along.push((param(self.pos(v)), v));
+            }
+        }
+        let crossed: Vec<(HalfEdgeId, Segment)> = self.edges()
+            .filter_map(|h| Some((h, self.segment(h)?)))
+            .filter(|&(_, [p, q])| opposite_signs(orient2d(a, b, p), orient2d(a, b, q)) && opposite_signs(orient2d(p, q, a), orient2d(p, q, b)))
+            .collect();
+        for (h, [p, q]) in crossed {
+            let x = segment_intersection([a, b], [p, q]).unwrap_or_else(|| p.lerp(&q, 0.5));
+            along.push((param(x), self.split_edge(h, x)));
+        }
+
+        along.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let mut vertices: Vec<VertexId> = along.into_iter().map(|(_, v)| v).collect();
+        vertices.dedup();
+        for pair in vertices.windows(2) {
+            if self.find_edge(pair[0], pair[1]).is_none() {
+                self.add_edge(pair[0], pair[1]);
+            }
+        }
+        vertices
+    }
+
+    /// The vertex at `p`, splitting the edge it's on or adding an isolated
+    /// one where there's none.
+    fn vertex_at(&mut self, p: Point) -> VertexId {
+        if let Some(v) = self.find_vertex(p) { return v; }
+        let on = self.edges().find(|&h| self.segment(h).is_some_and(|[u, w]| strictly_on_segment(u, w, p)));
+        match on {
+            Some(h) => self.split_edge(h, p),
+            None => self.add_vertex(p),
+        }
+    }
+
+    fn push_twins(&mut self, a: E, b: E) -> (HalfEdgeId, HalfEdgeId) {
+        let (h, t) = (HalfEdgeId(self.halfedges.len()), HalfEdgeId(self.halfedges.len() + 1));
+        for (data, twin) in [(a, t), (b, h)] {
+            self.halfedges.push(HalfEdge { origin: None, twin, next: None, prev: None, face: None, data, alive: true });
+        }
+        (h, t)
+    }
+
+    fn push_face(&mut self, edge: HalfEdgeId, data: F) -> FaceId {
+        self.faces.push(Face { edge, data, alive: true });
+        FaceId(self.faces.len() - 1)
+    }
+
+    fn set_next(&mut self, h: HalfEdgeId, next: HalfEdgeId) {
+        self.halfedges[h.0].next = Some(next);
+        self.halfedges[next.0].prev = Some(h);
+    }
+
+    fn set_cycle_face(&mut self, h: HalfEdgeId, f: FaceId) {
+        let cycle: Vec<HalfEdgeId> = self.cycle(h).collect();
+        for e in cycle {
+            self.halfedges[e.0].face = Some(f);
+        }
+        self.faces[f.0].edge = h;
+    }
+
+    /// Fits `h` in among the edges around its origin, by its direction, and
+    /// returns the face of the wedge it went into.
+    fn splice(&mut self, h: HalfEdgeId) -> Option<FaceId> {
+        let (v, t) = (self.origin(h).unwrap(), self.twin(h));
+        let target = angle(self.pos(self.dest(h).unwrap()) - self.pos(v));
+        // the edge clockwise before h: the greatest angle below its own, going round
+        let before = self.outgoing(v)
+            .filter_map(|o| Some((o, angle(self.pos(self.dest(o)?) - self.pos(v)))))
+            .map(|(o, a)| (o, if a < target { a } else { a - TAU }))
+            .max_by(|x, y| x.1.total_cmp(&y.1))
+            .map(|(o, _)| o);
+        match before {
+            None => {
+                self.set_next(t, h);
+                self.vertices[v.0].edge = Some(h);
+                None
+            }
+            Some(before) => {
+                let into = self.prev(before).expect("the edges around a vertex are linked");
+                self.set_next(into, h);
+                self.set_next(t, before);
+                self.face(before)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::color::{BLANK, RED};
+
+    fn points(coords: &[(f64, f64)]) -> Vec<Point> {
+        coords.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    fn area_of(poly: &Polygon) -> f64 {
+        poly.faces.iter().map(|&(a, b, c, _)| {
+            let [a, b, c] = [a, b, c].map(|i| Point::<f64>::from(poly.verts[i].pos));
+            (b - a).cross(c - a) / 2.0
+        }).sum()
+    }
+
+    #[test]
+    fn polygons_convert_both_ways() {
+        // a square with an edge across it and one poking out of it
+        let mut poly = Polygon::rectangle(euclid::vec2(0.0, 0.0), euclid::vec2(4.0, 4.0), RED, BLANK);
+        poly.verts.extend([Vertex::new(-2.0, 2.0, None), Vertex::new(6.0, 2.0, None)]);
+        poly.edges.push((4, 5, RED));
+        let dcel: Dcel = Dcel::from_polygon(&poly);
+        dcel.check_invariants().unwrap();
+        // the line splits two sides of the square
+        assert_eq!((dcel.vertex_count(), dcel.edge_count(), dcel.face_count()), (8, 9, 3));
+        assert_eq!(dcel.bounded_faces().count(), 2);
+        for f in dcel.bounded_faces() {
+            assert_eq!(dcel.signed_area(f), 8.0);
+            assert_eq!(dcel.face_edges(f).count(), 4);
+            assert_eq!(dcel.adjacent_faces(f).filter(|&g| g != f).count(), 4);
+        }
+        assert_eq!(dcel.pos(VertexId::new(4)), Point::new(-2.0, 2.0));
+
+        let back = dcel.to_polygon(RED, |f| (dcel.signed_area(f) > 0.0).then_some(BLANK));
+        assert_eq!(back.edges.len(), 9);
+        assert_eq!(back.faces.len(), 4);
+        assert_eq!(area_of(&back), 16.0);
+
+        // a concave face is cut into triangles inside it
+        let l_shape = points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]);
+        let mut dcel: Dcel = Dcel::new();
+        let ids: Vec<VertexId> = l_shape.iter().map(|&p| dcel.add_vertex(p)).collect();
+        for i in 0..ids.len() {
+            dcel.add_edge(ids[i], ids[(i + 1) % ids.len()]);
+        }
+        dcel.check_invariants().unwrap();
+        let poly = dcel.to_polygon(RED, |_| Some(RED));
+        assert_eq!(poly.faces.len(), 4);
+        assert_eq!(area_of(&poly), 3.0);
+    }
+
+    #[test]
+    fn split_flip_and_merge() {
+        let corners = points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+        let mut dcel: Dcel<u32, u32> = Dcel::from_triangles(&corners, &[[0, 1, 2], [0, 2, 3]]);
+        dcel.check_invariants().unwrap();
+        let [a, b, c, d] = [0, 1, 2, 3].map(VertexId::new);
+        assert_eq!(dcel.neighbours(a).collect::<Vec<_>>(), vec![b, c, d]);
+        assert_eq!(dcel.vertex_faces(c).filter(|&f| dcel.signed_area(f) > 0.0).count(), 2);
+
+        let diagonal = dcel.find_edge(a, c).unwrap();
+        assert!(dcel.flip_edge(diagonal));
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.find_edge(a, c), None);
+        assert_eq!((dcel.origin(diagonal), dcel.dest(diagonal)), (Some(b), Some(d)));
+        for f in dcel.bounded_faces() {
+            assert_eq!(dcel.signed_area(f), 2.0);
+        }
+        // the outline isn't a diagonal of anything
+        assert!(!dcel.flip_edge(dcel.find_edge(a, b).unwrap()));
+
+        // splitting a triangle's edge keeps the data on both pieces
+        let side = dcel.find_edge(b, c).unwrap();
+        *dcel.data_mut(side) = 7;
+        let mid = dcel.split_edge(side, Point::new(2.0, 1.0));
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.find_edge(b, mid), Some(side));
+        assert_eq!(*dcel.data(dcel.find_edge(mid, c).unwrap()), 7);
+        assert_eq!(dcel.face_edges(dcel.face(side).unwrap()).count(), 4);
+        // a triangle with a vertex on its side isn't one any more
+        assert!(!dcel.flip_edge(diagonal));
+        let merged = dcel.merge_edges(mid).unwrap();
+        assert!(merged == side || dcel.twin(merged) == side);
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.vertex_count(), 4);
+        assert!(dcel.flip_edge(diagonal));
+        assert!(dcel.merge_edges(a).is_none());
+
+        // taking the diagonal out joins the triangles, and the new face
+        // splitting them again has the data of the one it came from
+        let f = dcel.face(diagonal).unwrap();
+        *dcel.face_data_mut(f) = 3;
+        dcel.remove_edge(diagonal);
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.face_count(), 2);
+        let square = dcel.bounded_faces().next().unwrap();
+        *dcel.face_data_mut(square) = 5;
+        let new = dcel.add_edge(b, d);
+        dcel.check_invariants().unwrap();
+        assert_eq!(*dcel.face_data(dcel.face(new).unwrap()), 5);
+        assert_eq!(*dcel.face_data(dcel.face(dcel.twin(new)).unwrap()), 5);
+
+        dcel.remove_vertex(c);
+        dcel.check_invariants().unwrap();
+        assert_eq!((dcel.vertex_count(), dcel.edge_count(), dcel.face_count()), (3, 3, 2));
+    }
+
+    #[test]
+    fn segments_run_through_vertices_and_along_edges() {
+        let mut dcel: Dcel = Dcel::new();
+        dcel.insert_segment(Point::new(0.0, 0.0), Point::new(4.0, 0.0));
+        // an isolated vertex on the way of a later segment, and a piece of its own
+        let lone = dcel.add_vertex(Point::new(2.0, 2.0));
+        dcel.insert_segment(Point::new(2.0, -1.0), Point::new(2.0, -3.0));
+        // a square, two sides of which run along what's there
+        let on = dcel.insert_segment(Point::new(0.0, 0.0), Point::new(0.0, 4.0));
+        assert_eq!(on.len(), 2);
+        dcel.insert_segment(Point::new(0.0, 4.0), Point::new(4.0, 4.0));
+        dcel.insert_segment(Point::new(4.0, 4.0), Point::new(4.0, -1.0));
+        dcel.insert_segment(Point::new(4.0, -1.0), Point::new(4.0, 0.0));
+        dcel.check_invariants().unwrap();
+        // inside the square, around it and around the other piece
+        assert_eq!(dcel.face_count(), 3);
+        assert_eq!(dcel.edge_count(), 6);
+
+        // halves the square and ties the other piece to it, ending inside it
+        let through = dcel.insert_segment(Point::new(2.0, 6.0), Point::new(2.0, -2.0));
+        dcel.check_invariants().unwrap();
+        assert_eq!(through.len(), 6);
+        assert_eq!(through[2], lone);
+        assert_eq!([3, 4, 5].map(|i| dcel.pos(through[i])), [(2.0, 0.0), (2.0, -1.0), (2.0, -2.0)].map(|(x, y)| Point::new(x, y)));
+        let areas: Vec<f64> = dcel.bounded_faces().map(|f| dcel.signed_area(f)).collect();
+        assert_eq!(areas, vec![8.0, 8.0]);
+        assert_eq!(dcel.face_count(), 3);
+    }
+
+    #[test]
+    fn broken_links_are_caught() {
+        let mut dcel: Dcel = Dcel::from_triangles(&points(&[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]), &[[0, 1, 2]]);
+        dcel.check_invariants().unwrap();
+        let h = dcel.find_edge(VertexId::new(0), VertexId::new(1)).unwrap();
+        let skipped = dcel.next(dcel.next(h).unwrap()).unwrap();
+        dcel.link(h, skipped);
+        assert_eq!(dcel.check_invariants(), Err(DcelError::Next(h)));
+
+        // linked by hand, without faces until they're built
+        let mut dcel: Dcel = Dcel::new();
+        let (u, w) = (dcel.add_vertex(Point::new(0.0, 0.0)), dcel.add_vertex(Point::new(1.0, 1.0)));
+        let (h, t) = dcel.add_twins();
+        dcel.set_origin(h, u);
+        assert_eq!(dcel.check_invariants(), Err(DcelError::Unlinked(h)));
+        dcel.set_origin(t, w);
+        dcel.link(h, t);
+        dcel.link(t, h);
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.face_count(), 0);
+        dcel.build_faces();
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.face_count(), 1);
+        assert_eq!(dcel.signed_area(FaceId(0)), 0.0);
+        assert_eq!(DcelError::Next(h).to_string(), "half-edge 0 doesn't lead into its next one");
+    }
+}
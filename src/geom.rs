@@ -1,8 +1,10 @@
 use euclid::{*, default::Vector2D, default::Box2D, vec2};
 use macroquad::prelude::{*};
 use crate::{
-    point::Point,
+    point::{Point, Scalar},
     common_traits::*,
+    predicates,
+    render::Renderer,
 };
 
 #[derive(Clone, Debug)]
@@ -24,11 +26,25 @@ impl Vertex {
     pub fn new(x: f32, y: f32, clr: Option<Color>) -> Self {
         Self { pos: vec2(x,y), clr}
     }
+
+    /// The position as a [`Point`] over any scalar.
+    pub fn point<T: Scalar>(&self) -> Point<T> {
+        self.pos.into()
+    }
+}
+
+impl<T: Scalar> From<Point<T>> for Vertex {
+    fn from(p: Point<T>) -> Self {
+        Self { pos: p.into(), clr: None }
+    }
 }
 
+/// Radius of the dot a [`Vertex`] is drawn as, and picked by.
+const VERTEX_RADIUS: f32 = 1.0;
+
 impl Draw for Vertex {
-    fn draw(&self) {
-        draw_circle(self.pos.x, self.pos.y, 1.0, self.clr.unwrap_or(BLACK));
+    fn render(&self, target: &mut dyn Renderer) {
+        target.circle(self.pos.to_array().into(), VERTEX_RADIUS, self.clr.unwrap_or(BLACK));
     }
 
     fn vertices(&self) -> Vec<Vertex> {
@@ -36,7 +52,18 @@ impl Draw for Vertex {
     }
 }
 
-#[derive(Debug)]
+impl Select for Vertex {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        let radius = Vector2D::splat(VERTEX_RADIUS);
+        Box2D::new((self.pos - radius).to_point(), (self.pos + radius).to_point())
+    }
+
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        (*global_sample_point - self.pos).length() - VERTEX_RADIUS
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Line2D {
     pub a: Vertex,
     pub b: Vertex,
@@ -44,11 +71,10 @@ pub struct Line2D {
 }
 
 impl Draw for Line2D {
-    fn draw(&self) {
-        draw_line(self.a.pos.x, self.a.pos.y,
-                  self.b.pos.x, self.b.pos.y,
-                  self.thickness,
-                  self.a.clr.or(self.b.clr).unwrap_or(BLACK));
+    fn render(&self, target: &mut dyn Renderer) {
+        target.line(self.a.pos.to_array().into(), self.b.pos.to_array().into(),
+                    self.thickness,
+                    self.a.clr.or(self.b.clr).unwrap_or(BLACK));
         //FIXME: detect when a.clr != b.clr and mix colors accordingly
     }
     fn vertices(&self) -> Vec<Vertex> {
@@ -58,43 +84,26 @@ impl Draw for Line2D {
 
 impl Select for Line2D {
     fn compute_aabb(&self) -> Box2D<f32> {
-        let xs = {
-            let mut xs = [self.a.pos.x, self.b.pos.x];
-            if xs[0] > xs[1] { xs.reverse(); }
-            xs
-        };
-        let ys = {
-            let mut ys = [self.a.pos.y, self.b.pos.y];
-            if ys[0] > ys[1] { ys.reverse(); }
-            ys
-        };
-        Box2D::new(
-            Point2D::new(xs[0],ys[0]),
-            Point2D::new(xs[1],ys[1]),
-        )
+        Box2D::from_points([self.a.pos.to_point(), self.b.pos.to_point()])
+            .inflate(self.thickness, self.thickness)
     }
 
+    /// Distance to the segment, with round caps at both ends.
     fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
-        let xs = [self.a.pos.x, self.b.pos.x];
-        let ys = [self.a.pos.y, self.b.pos.y];
-        let tx = global_sample_point.x;
-        let ty = global_sample_point.y;
-        let numer = ((ys[1] - ys[0])*tx - (xs[1] - xs[0])*ty + xs[1]*ys[0] - ys[1]*xs[0]).abs();
-        let denom = ((ys[1] - ys[0]).powi(2) + (xs[1] - xs[0]).powi(2)).sqrt();
-        numer/denom - self.thickness
+        segment_distance(*global_sample_point, self.a.pos, self.b.pos) - self.thickness
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Circle {
     pub center: Vertex,
     pub radius: f32,
 }
 
 impl Draw for Circle {
-    fn draw(&self) {
-        draw_circle(self.center.pos.x, self.center.pos.y,
-                    self.radius, self.center.clr.unwrap_or(BLACK));
+    fn render(&self, target: &mut dyn Renderer) {
+        target.circle(self.center.pos.to_array().into(),
+                      self.radius, self.center.clr.unwrap_or(BLACK));
     }
     fn vertices(&self) -> Vec<Vertex> {
         vec![self.center.clone()]
@@ -116,7 +125,7 @@ impl Select for Circle {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Polygon {
     pub verts: Vec<Vertex>,
     pub edges: Vec<(usize, usize, Color)>,
@@ -136,8 +145,8 @@ impl Polygon {
         }).collect();
 
         let edges = {
-            let srcs = (0..vert_count).into_iter();
-            let dsts = (1..vert_count).into_iter().chain(std::iter::once(0));
+            let srcs = 0..vert_count;
+            let dsts = (1..vert_count).chain(std::iter::once(0));
             srcs.zip(dsts).map(|e| (e.0, e.1, edge_color)).collect()
         };
 
@@ -160,6 +169,19 @@ impl Polygon {
     }
 }
 
+impl Polygon {
+    /// The face edges that aren't shared by two faces, i.e. the outline of the filled area.
+    fn face_outline(&self) -> Vec<(usize, usize)> {
+        let mut counts = std::collections::HashMap::new();
+        for &(a, b, c, _) in &self.faces {
+            for (u, v) in [(a, b), (b, c), (c, a)] {
+                *counts.entry((u.min(v), u.max(v))).or_insert(0usize) += 1;
+            }
+        }
+        counts.into_iter().filter(|(_, n)| *n == 1).map(|(e, _)| e).collect()
+    }
+}
+
 impl Default for Polygon {
     fn default() -> Self {
         Self { verts: vec![], edges: vec![], faces: vec![], edge_thickness: 2.0 }
@@ -167,14 +189,14 @@ impl Default for Polygon {
 }
 
 impl Draw for Polygon {
-    fn draw(&self) {
+    fn render(&self, target: &mut dyn Renderer) {
         for edge_data in &self.edges {
             let a = &self.verts[edge_data.0];
             let b = &self.verts[edge_data.1];
             let c = edge_data.2;
-            draw_line(
-                a.pos.x,a.pos.y,
-                b.pos.x,b.pos.y,
+            target.line(
+                a.pos.to_array().into(),
+                b.pos.to_array().into(),
                 self.edge_thickness, c,
             )
         }
@@ -186,7 +208,7 @@ impl Draw for Polygon {
                 Vec2::from(self.verts[face_data.2].pos.to_array()),
             );
             let clr = face_data.3;
-            draw_triangle(a,b,c, clr);
+            target.triangle(a,b,c, clr);
         }
 
     }
@@ -196,6 +218,44 @@ impl Draw for Polygon {
     }
 }
 
+impl Select for Polygon {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        let half = self.edge_thickness / 2.0;
+        Box2D::from_points(self.verts.iter().map(|v| v.pos.to_point())).inflate(half, half)
+    }
+
+    /// The union of the stroked edges and the filled faces.
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        let p = *global_sample_point;
+        let pos = |i: usize| self.verts[i].pos;
+        let edges = self.edges.iter()
+            .map(|&(a, b, _)| segment_distance(p, pos(a), pos(b)) - self.edge_thickness / 2.0)
+            .fold(f32::INFINITY, f32::min);
+        if self.faces.is_empty() { return edges; }
+
+        let outline = self.face_outline().into_iter()
+            .map(|(a, b)| segment_distance(p, pos(a), pos(b)))
+            .fold(f32::INFINITY, f32::min);
+        let inside = self.faces.iter().any(|&(a, b, c, _)| triangle_contains(p, [pos(a), pos(b), pos(c)]));
+        edges.min(if inside { -outline } else { outline })
+    }
+}
+
+/// Distance from `p` to the segment from `a` to `b`.
+fn segment_distance(p: Vector2D<f32>, a: Vector2D<f32>, b: Vector2D<f32>) -> f32 {
+    let (pa, ba) = (p - a, b - a);
+    let len2 = ba.square_length();
+    let h = if len2 > 0.0 { (pa.dot(ba) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    (pa - ba * h).length()
+}
+
+/// Whether `p` lies inside or on the triangle, in either winding.
+fn triangle_contains(p: Vector2D<f32>, [a, b, c]: [Vector2D<f32>; 3]) -> bool {
+    let side = |u: Vector2D<f32>, v: Vector2D<f32>| (v - u).cross(p - u);
+    let (s0, s1, s2) = (side(a, b), side(b, c), side(c, a));
+    (s0 >= 0.0 && s1 >= 0.0 && s2 >= 0.0) || (s0 <= 0.0 && s1 <= 0.0 && s2 <= 0.0)
+}
+
 use ordered_float::OrderedFloat;
 
 type TripleSite = (Point, Point, Point);
@@ -216,15 +276,13 @@ pub fn segment_intersection(seg1: Segment, seg2: Segment) -> Option<Point> {
     let t = numer_a / denom;
     let u = numer_c / denom;
 
-    if t < 0.0 || t > 1.0 || u < 0.0 || u > 1.0 { return None; }
+    if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) { return None; }
 
-    return Some(a + r * t);
+    Some(a + r * t)
 }
 
 pub fn circle_bottom(triple_site: TripleSite) -> Option<OrderedFloat<f64>> {
-    let circle_center = circle_center(triple_site);
-    if let None = circle_center { return None; }
-    let circle_center = circle_center.unwrap();
+    let circle_center = circle_center(triple_site)?;
 
     let (_, _, p3) = triple_site;
     let x3 = p3.x();
@@ -234,39 +292,27 @@ pub fn circle_bottom(triple_site: TripleSite) -> Option<OrderedFloat<f64>> {
 
     let r = ((x3 - x_cen) * (x3 - x_cen) + (y3 - y_cen) * (y3 - y_cen)).sqrt();
 
-    return Some(OrderedFloat::<f64>(y_cen - r));
+    Some(OrderedFloat::<f64>(y_cen - r))
 }
 
+/// Center of the circle through the three sites, or `None` when they are collinear.
 pub fn circle_center(triple_site: TripleSite) -> Option<Point> {
     let (p1, p2, p3) = triple_site;
-    let x1 = p1.x();
-    let x2 = p2.x();
-    let x3 = p3.x();
-    let y1 = p1.y();
-    let y2 = p2.y();
-    let y3 = p3.y();
-
-    let c1 = x3 * x3 + y3 * y3 - x1 * x1 - y1 * y1;
-    let c2 = x3 * x3 + y3 * y3 - x2 * x2 - y2 * y2;
-    let a1 = -2. * (x1 - x3);
-    let a2 = -2. * (x2 - x3);
-    let b1 = -2. * (y1 - y3);
-    let b2 = -2. * (y2 - y3);
-
-    let numer = c1 * a2 - c2 * a1;
-    let denom = b1 * a2 - b2 * a1;
-
-    if denom == 0.0 { return None; }
-    let y_cen = numer / denom;
+    // the degeneracy test has to be exact, a near-zero determinant from
+    // rounding would otherwise send the center off to a huge but finite spot
+    let det = predicates::orient2d(p1, p2, p3);
+    if det == 0.0 { return None; }
 
+    // work relative to p3 to keep the squared terms small
+    let d1 = p1 - p3;
+    let d2 = p2 - p3;
+    let l1 = d1.dot(d1);
+    let l2 = d2.dot(d2);
 
-    let x_cen = if a2 != 0.0 {
-        (c2 - b2 * y_cen) / a2
-    } else {
-        (c1 - b1 * y_cen) / a1
-    };
+    let x_cen = (d2.y() * l1 - d1.y() * l2) / (2. * det);
+    let y_cen = (d1.x() * l2 - d2.x() * l1) / (2. * det);
 
-    return Some(Point::new(x_cen, y_cen));
+    Some(p3 + Point::new(x_cen, y_cen))
 }
 
 
@@ -292,6 +338,73 @@ mod tests {
         assert_eq!(circle_bottom(circle_triple), None);
     }
 
+    #[test]
+    fn exactly_collinear_circle() {
+        // collinear in exact arithmetic, but the naive determinant rounds to ~3.6e-12
+        let circle_triple = (
+            Point::new(0.1225490019476243, 0.3676470058428729),
+            Point::new(20374.028446252356, 61122.08533875707),
+            Point::new(2.437592998279158e-7, 7.312778994837474e-7),
+        );
+        assert_eq!(circle_center(circle_triple), None);
+        assert_eq!(circle_bottom(circle_triple), None);
+    }
+
+    #[test]
+    fn nearly_collinear_circle() {
+        let p3 = Point::new(2.0, 2.0f64.next_up());
+        let center = circle_center((Point::new(0.0, 0.0), Point::new(1.0, 1.0), p3)).unwrap();
+        assert!(center.x().is_finite() && center.y().is_finite());
+        // the center sits on the bisector of the first two points
+        assert!((center.x() + center.y() - 1.0).abs() <= 1e-6 * center.x().abs());
+    }
+
+    #[test]
+    fn line_distance_is_capped() {
+        let line = Line2D { a: Vertex::new(0.0, 0.0, None), b: Vertex::new(10.0, 0.0, None), thickness: 1.0 };
+        assert_eq!(line.sample_signed_distance_field(&vec2(5.0, 3.0)), 2.0);
+        assert_eq!(line.sample_signed_distance_field(&vec2(13.0, 4.0)), 4.0);
+        assert_eq!(line.sample_signed_distance_field(&vec2(-3.0, 0.0)), 2.0);
+        assert!(!line.contains_point(&vec2(30.0, 0.0)), "the infinite line isn't part of the segment");
+    }
+
+    #[test]
+    fn zero_length_line() {
+        let line = Line2D { a: Vertex::new(2.0, 2.0, None), b: Vertex::new(2.0, 2.0, None), thickness: 1.0 };
+        assert_eq!(line.sample_signed_distance_field(&vec2(2.0, 5.0)), 2.0);
+        assert!(line.contains_point(&vec2(2.0, 2.5)));
+    }
+
+    #[test]
+    fn vertex_distance() {
+        let v = Vertex::new(1.0, 1.0, None);
+        assert_eq!(v.sample_signed_distance_field(&vec2(4.0, 5.0)), 5.0 - VERTEX_RADIUS);
+        assert!(v.contains_point(&vec2(1.0, 1.0)));
+    }
+
+    #[test]
+    fn filled_rectangle_distance() {
+        // two faces sharing a diagonal, which must not count as boundary
+        let mut rect = Polygon::rectangle(vec2(0.0, 0.0), vec2(10.0, 4.0), WHITE, WHITE);
+        rect.edges.clear();
+        assert_eq!(rect.sample_signed_distance_field(&vec2(5.0, 2.0)), -2.0);
+        assert_eq!(rect.sample_signed_distance_field(&vec2(1.0, 2.0)), -1.0);
+        assert_eq!(rect.sample_signed_distance_field(&vec2(13.0, 8.0)), 5.0);
+        assert_eq!(rect.sample_signed_distance_field(&vec2(5.0, -3.0)), 3.0);
+    }
+
+    #[test]
+    fn outline_polygon_distance() {
+        let ring = Polygon::circle(4, 10.0, WHITE);
+        let half = ring.edge_thickness / 2.0;
+        assert!(ring.contains_point(&vec2(10.0, 0.0)));
+        assert!(!ring.contains_point(&vec2(0.0, 0.0)), "unfilled polygons are only picked on their edges");
+        assert!((ring.sample_signed_distance_field(&vec2(0.0, 0.0)) - (50f32.sqrt() - half)).abs() < 1e-5);
+        assert_eq!(ring.sample_signed_distance_field(&vec2(13.0, 0.0)), 3.0 - half);
+        let aabb = ring.compute_aabb();
+        assert!(aabb.min.x <= -10.0 - half && aabb.max.y >= 10.0 + half);
+    }
+
     #[test]
     fn simple_segments_intersect() {
         let line1 = [Point::new(-1.0, 0.0), Point::new(1.0, 0.0)];
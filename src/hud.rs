@@ -0,0 +1,157 @@
+//! An in-window panel of live statistics: a graph of the recent frame times
+//! and the rows every [`Metrics`] source adds, like the scene's object counts
+//! or the size of a hull. `H` shows and hides it.
+
+use std::collections::VecDeque;
+use macroquad::prelude::*;
+use crate::render::{Renderer, Screen};
+
+pub const TOGGLE_KEY: KeyCode = KeyCode::H;
+/// How many frames the frame time graph goes back.
+pub const FRAME_HISTORY: usize = 120;
+pub const FONT_SIZE: f32 = 20.0;
+const PADDING: f32 = 8.0;
+const GRAPH_HEIGHT: f32 = 40.0;
+/// The frame time the graph always has room for, 30 fps.
+const GRAPH_MIN_SCALE: f32 = 1.0 / 30.0;
+
+/// Something with statistics worth watching while it runs.
+pub trait Metrics {
+    /// `(name, value)` rows, in the order they should be shown.
+    fn metrics(&self) -> Vec<(String, String)>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Hud {
+    pub visible: bool,
+    /// Where the panel's top left corner goes, in screen pixels.
+    pub pos: Vec2,
+    frame_times: VecDeque<f32>,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self { visible: true, pos: vec2(20.0, 20.0), frame_times: VecDeque::with_capacity(FRAME_HISTORY) }
+    }
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A visible panel with its top left corner at `pos`.
+    pub fn at(pos: Vec2) -> Self {
+        Self { pos, ..Self::default() }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn record_frame(&mut self, frame_time: f32) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// The recorded frame times, oldest first.
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    /// Records this frame's time and handles the toggle key; call once a frame.
+    pub fn update(&mut self) {
+        if is_key_released(TOGGLE_KEY) {
+            self.toggle();
+        }
+        self.record_frame(get_frame_time());
+    }
+
+    /// The rows the panel shows: the frame time, then those of `sources`.
+    pub fn rows(&self, sources: &[&dyn Metrics]) -> Vec<(String, String)> {
+        let mut rows = vec![];
+        if !self.frame_times.is_empty() {
+            let last = self.frame_times.back().unwrap();
+            let mean = self.frame_times().sum::<f32>() / self.frame_times.len() as f32;
+            rows.push(("frame time".to_string(), format!("{:.1} ms ({:.0} fps)", last * 1000.0, 1.0 / mean)));
+        }
+        rows.extend(sources.iter().flat_map(|source| source.metrics()));
+        rows
+    }
+
+    /// Draws the panel in screen coordinates, so through the default camera.
+    pub fn render(&self, target: &mut dyn Renderer, sources: &[&dyn Metrics]) {
+        if !self.visible { return; }
+        let lines: Vec<String> = self.rows(sources).into_iter().map(|(name, value)| format!("{name}: {value}")).collect();
+        // glyphs are about half as wide as they're tall
+        let text_width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as f32 * FONT_SIZE / 2.0;
+        let width = text_width.max(FRAME_HISTORY as f32) + 2.0 * PADDING;
+        let height = lines.len() as f32 * FONT_SIZE + GRAPH_HEIGHT + 3.0 * PADDING;
+        target.rectangle(self.pos, vec2(width, height), Color::new(0.0, 0.0, 0.0, 0.6));
+
+        let mut baseline = self.pos + vec2(PADDING, PADDING + FONT_SIZE * 0.75);
+        for line in &lines {
+            target.text(line, baseline, FONT_SIZE, LIGHTGRAY);
+            baseline.y += FONT_SIZE;
+        }
+
+        // a bar a frame, scaled so the slowest one fits
+        let bottom = self.pos.y + height - PADDING;
+        let scale = self.frame_times().fold(GRAPH_MIN_SCALE, f32::max);
+        let target_line = bottom - GRAPH_HEIGHT * (1.0 / 60.0) / scale;
+        target.line(vec2(self.pos.x + PADDING, target_line), vec2(self.pos.x + width - PADDING, target_line), 1.0, DARKGRAY);
+        for (i, frame_time) in self.frame_times().enumerate() {
+            let x = self.pos.x + PADDING + i as f32 + 0.5;
+            let clr = if frame_time > 1.0 / 55.0 { ORANGE } else { GREEN };
+            target.line(vec2(x, bottom), vec2(x, bottom - GRAPH_HEIGHT * frame_time / scale), 1.0, clr);
+        }
+    }
+
+    /// Renders straight to the window.
+    pub fn draw(&self, sources: &[&dyn Metrics]) {
+        self.render(&mut Screen, sources);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{geom::{Circle, Vertex}, render::Svg, scene::Scene};
+
+    struct Hull(usize);
+
+    impl Metrics for Hull {
+        fn metrics(&self) -> Vec<(String, String)> {
+            vec![("hull size".to_string(), self.0.to_string())]
+        }
+    }
+
+    #[test]
+    fn shows_frame_times_and_the_sources_rows() {
+        let mut scene: Scene = Scene::new();
+        scene.add_circle(Circle { center: Vertex::new(0.0, 0.0, None), radius: 1.0 });
+        let mut hud = Hud::at(vec2(10.0, 10.0));
+        for _ in 0..FRAME_HISTORY + 5 {
+            hud.record_frame(0.02);
+        }
+        assert_eq!(hud.frame_times().count(), FRAME_HISTORY);
+
+        let rows = hud.rows(&[&scene, &Hull(4)]);
+        assert_eq!(rows[0], ("frame time".to_string(), "20.0 ms (50 fps)".to_string()));
+        assert!(rows.contains(&("num. of circles".to_string(), "1".to_string())), "{rows:?}");
+        assert_eq!(rows.last().unwrap().1, "4");
+
+        let mut svg = Svg::new();
+        hud.render(&mut svg, &[&scene, &Hull(4)]);
+        let doc = svg.document();
+        assert!(doc.contains(">hull size: 4</text>"));
+        assert_eq!(doc.matches("<line ").count(), FRAME_HISTORY + 1);
+
+        hud.toggle();
+        let mut hidden = Svg::new();
+        hud.render(&mut hidden, &[&scene]);
+        assert_eq!(hidden.bounds(), None);
+    }
+}
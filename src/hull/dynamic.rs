@@ -0,0 +1,238 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    ops::Bound,
+};
+use ordered_float::OrderedFloat;
+use crate::point::Point;
+use super::{half_hull, orient, Trace};
+
+/// Orders like [`crate::order::LexXY`], which is what the monotone chain sweeps in.
+type LexKey = (OrderedFloat<f64>, OrderedFloat<f64>);
+
+fn lex(p: Point) -> LexKey {
+    (OrderedFloat(p.x()), OrderedFloat(p.y()))
+}
+
+fn unlex(k: &LexKey) -> Point {
+    Point::new(k.0.into_inner(), k.1.into_inner())
+}
+
+/// A convex hull kept up to date as keyed points come and go.
+///
+/// The lower and upper chains of the monotone chain are stored in ordered
+/// maps, so an insertion costs `O(log n)` plus the vertices it evicts. Removing
+/// a point that isn't a hull vertex is `O(1)`; removing a hull vertex rebuilds
+/// both chains from scratch in `O(n log n)`.
+///
+/// [`DynamicHull::hull`] follows the conventions of [`super::convex_hull`], with
+/// the smallest key standing in for duplicated points.
+#[derive(Debug, Clone)]
+pub struct DynamicHull<K> {
+    points: HashMap<K, Point>,
+    lower: BTreeMap<LexKey, K>,
+    upper: BTreeMap<LexKey, K>,
+    rebuilds: usize,
+}
+
+impl<K> Default for DynamicHull<K> {
+    fn default() -> Self {
+        Self { points: HashMap::new(), lower: BTreeMap::new(), upper: BTreeMap::new(), rebuilds: 0 }
+    }
+}
+
+impl<K: Copy + Ord + Hash> DynamicHull<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = (K, Point)>) -> Self {
+        let mut hull = Self { points: points.into_iter().collect(), ..Default::default() };
+        hull.rebuild();
+        hull
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn point(&self, key: K) -> Option<Point> {
+        self.points.get(&key).copied()
+    }
+
+    /// How many times a removal forced a full rebuild.
+    pub fn rebuilds(&self) -> usize {
+        self.rebuilds
+    }
+
+    pub fn is_hull_vertex(&self, key: K) -> bool {
+        self.points.get(&key).is_some_and(|p| {
+            let k = lex(*p);
+            self.lower.get(&k) == Some(&key) || self.upper.get(&k) == Some(&key)
+        })
+    }
+
+    /// Adds a point, or moves it if `key` is already present.
+    /// Returns whether the hull changed.
+    pub fn insert(&mut self, key: K, p: Point) -> bool {
+        let moved = self.points.contains_key(&key) && self.remove(key);
+        self.points.insert(key, p);
+        let lower = Self::insert_into_chain(&mut self.lower, key, p, 1.0);
+        let upper = Self::insert_into_chain(&mut self.upper, key, p, -1.0);
+        moved || lower || upper
+    }
+
+    /// Drops a point. Returns whether the hull changed.
+    pub fn remove(&mut self, key: K) -> bool {
+        let on_hull = self.is_hull_vertex(key);
+        if self.points.remove(&key).is_none() {
+            return false;
+        }
+        if on_hull {
+            self.rebuild();
+            self.rebuilds += 1;
+        }
+        on_hull
+    }
+
+    /// Keys of the hull vertices, counter-clockwise from the lowest-x point.
+    pub fn hull(&self) -> Vec<K> {
+        let mut hull: Vec<K> = self.lower.values().copied().collect();
+        if hull.len() > 1 {
+            let upper_len = self.upper.len();
+            hull.extend(self.upper.values().rev().skip(1).take(upper_len.saturating_sub(2)).copied());
+        }
+        hull
+    }
+
+    fn rebuild(&mut self) {
+        let mut sorted: Vec<(LexKey, K)> = self.points.iter().map(|(k, p)| (lex(*p), *k)).collect();
+        sorted.sort();
+        sorted.dedup_by(|a, b| a.0 == b.0);
+        let pts: Vec<Point> = sorted.iter().map(|(k, _)| unlex(k)).collect();
+
+        let chain_map = |chain: Vec<usize>| chain.into_iter().map(|i| sorted[i]).collect();
+        self.lower = chain_map(half_hull(&pts, 0..pts.len(), &[], &mut Trace::disabled()));
+        self.upper = chain_map(half_hull(&pts, (0..pts.len()).rev(), &[], &mut Trace::disabled()));
+    }
+
+    /// Inserts into a chain that runs by increasing x and turns towards `sign`
+    /// (positive for the lower chain, negative for the upper one).
+    fn insert_into_chain(chain: &mut BTreeMap<LexKey, K>, key: K, p: Point, sign: f64) -> bool {
+        let k = lex(p);
+        if let Some(existing) = chain.get_mut(&k) {
+            if key < *existing {
+                *existing = key;
+                return true;
+            }
+            return false;
+        }
+
+        let prev = chain.range(..k).next_back().map(|(k, _)| *k);
+        let next = chain.range((Bound::Excluded(k), Bound::Unbounded)).next().map(|(k, _)| *k);
+        if let (Some(prev), Some(next)) = (prev, next) {
+            if sign * orient(unlex(&prev), unlex(&next), p) >= 0.0 {
+                return false;
+            }
+        }
+
+        chain.insert(k, key);
+        loop {
+            let mut before = chain.range(..k).rev().map(|(k, _)| *k);
+            let (Some(b), Some(a)) = (before.next(), before.next()) else { break; };
+            if sign * orient(unlex(&a), unlex(&b), p) > 0.0 { break; }
+            chain.remove(&b);
+        }
+        loop {
+            let mut after = chain.range((Bound::Excluded(k), Bound::Unbounded)).map(|(k, _)| *k);
+            let (Some(b), Some(c)) = (after.next(), after.next()) else { break; };
+            if sign * orient(p, unlex(&b), unlex(&c)) > 0.0 { break; }
+            chain.remove(&b);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hull::convex_hull;
+
+    /// Replays inserts and removals on both a `DynamicHull` and a from-scratch hull.
+    fn check_against_static(ops: &[(bool, usize)], points: &[Point]) {
+        let mut dynamic = DynamicHull::new();
+        let mut alive = vec![false; points.len()];
+        for &(insert, i) in ops {
+            if insert {
+                dynamic.insert(i, points[i]);
+            } else {
+                dynamic.remove(i);
+            }
+            alive[i] = insert;
+
+            let remaining: Vec<usize> = (0..points.len()).filter(|&i| alive[i]).collect();
+            let subset: Vec<Point> = remaining.iter().map(|&i| points[i]).collect();
+            let expected: Vec<usize> = convex_hull(&subset).into_iter().map(|j| remaining[j]).collect();
+            assert_eq!(dynamic.hull(), expected, "after {} {i}", if insert { "inserting" } else { "removing" });
+        }
+    }
+
+    fn lattice(seed: u64, count: usize, side: u64) -> Vec<Point> {
+        let mut s = seed;
+        let mut next = move || {
+            s = s.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (s >> 33) % side
+        };
+        (0..count).map(|_| Point::new(next() as f64, next() as f64)).collect()
+    }
+
+    #[test]
+    fn insertions_match_static_hull() {
+        for seed in 0..20 {
+            let points = lattice(seed, 60, 12);
+            let ops: Vec<(bool, usize)> = (0..points.len()).map(|i| (true, i)).collect();
+            check_against_static(&ops, &points);
+        }
+    }
+
+    #[test]
+    fn removals_match_static_hull() {
+        for seed in 0..20 {
+            let points = lattice(seed, 40, 8);
+            let mut ops: Vec<(bool, usize)> = (0..points.len()).map(|i| (true, i)).collect();
+            ops.extend((0..points.len()).map(|i| (false, (i * 7 + seed as usize) % points.len())));
+            check_against_static(&ops, &points);
+        }
+    }
+
+    #[test]
+    fn interior_removal_does_not_rebuild() {
+        let mut hull = DynamicHull::from_points([
+            (0, Point::new(0.0, 0.0)),
+            (1, Point::new(4.0, 0.0)),
+            (2, Point::new(2.0, 3.0)),
+            (3, Point::new(2.0, 1.0)),
+        ]);
+        assert!(!hull.remove(3));
+        assert_eq!(hull.rebuilds(), 0);
+        assert!(hull.remove(2));
+        assert_eq!(hull.rebuilds(), 1);
+        assert_eq!(hull.hull(), vec![0, 1]);
+    }
+
+    #[test]
+    fn moving_a_point() {
+        let mut hull = DynamicHull::from_points([
+            (0, Point::new(0.0, 0.0)),
+            (1, Point::new(4.0, 0.0)),
+            (2, Point::new(2.0, 3.0)),
+        ]);
+        assert!(hull.insert(2, Point::new(2.0, -3.0)));
+        assert_eq!(hull.hull(), vec![0, 2, 1]);
+        assert_eq!(hull.len(), 3);
+    }
+}
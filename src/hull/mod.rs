@@ -0,0 +1,599 @@
+use std::fmt;
+use macroquad::color::Color;
+use crate::{
+    dcel::{Dcel, VertexId},
+    geom::{Polygon, Vertex},
+    order::{Angular, LexXY, LexYX, PointOrder},
+    point::Point,
+    predicates,
+};
+
+pub mod dynamic;
+pub use dynamic::DynamicHull;
+
+/// Computes the convex hull of `points` with Andrew's monotone chain.
+///
+/// Returns indices into `points`, counter-clockwise (positive cross product)
+/// starting from the point with the smallest x (ties broken by smallest y).
+/// Points lying on a hull edge are left out and duplicated points are only
+/// reported once. Inputs with fewer than 3 distinct points yield those
+/// distinct points, and fully collinear inputs yield the two extremes.
+pub fn convex_hull(points: &[Point]) -> Vec<usize> {
+    Algorithm::MonotoneChain.hull(points)
+}
+
+/// Builds the convex hull of `points` as a closed [`Polygon`] outline.
+///
+/// The polygon only holds the hull vertices, in the order given by
+/// [`convex_hull`], with every edge painted `edge_color`.
+pub fn convex_hull_polygon(points: &[Point], edge_color: Color) -> Polygon {
+    hull_polygon(points, &convex_hull(points), edge_color)
+}
+
+/// Turns hull indices, as returned by [`Algorithm::hull`], into a closed outline.
+pub fn hull_polygon(points: &[Point], hull: &[usize], edge_color: Color) -> Polygon {
+    let verts = hull.iter()
+        .map(|&i| Vertex::from(points[i]))
+        .collect();
+    let edges = match hull.len() {
+        0 | 1 => vec![],
+        2 => vec![(0, 1, edge_color)],
+        n => (0..n).map(|i| (i, (i + 1) % n, edge_color)).collect(),
+    };
+    Polygon { verts, edges, ..Default::default() }
+}
+
+/// Hull indices as a subdivision: the hull vertices, in order, and a face
+/// inside the hull as well as the one around it.
+pub fn hull_dcel(points: &[Point], hull: &[usize]) -> Dcel {
+    let mut dcel = Dcel::new();
+    let verts: Vec<VertexId> = hull.iter().map(|&i| dcel.add_vertex(points[i])).collect();
+    match verts.len() {
+        0 | 1 => {}
+        2 => { dcel.add_edge(verts[0], verts[1]); }
+        n => for i in 0..n {
+            dcel.add_edge(verts[i], verts[(i + 1) % n]);
+        },
+    }
+    dcel
+}
+
+/// Peels convex hulls off `points` until none are left (onion peeling).
+///
+/// Each layer holds every remaining point on the boundary of the remaining
+/// points' hull, counter-clockwise, so points lying on hull edges and
+/// duplicated points are peeled along with the vertices. The index of the
+/// layer a point ends up in is its hull depth, see [`hull_depths`].
+pub fn convex_layers(points: &[Point]) -> Vec<Vec<usize>> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut layers = vec![];
+    while !remaining.is_empty() {
+        let subset: Vec<Point> = remaining.iter().map(|&i| points[i]).collect();
+        let hull: Vec<usize> = convex_hull(&subset).into_iter().map(|j| remaining[j]).collect();
+
+        let layer = if hull.len() < 3 {
+            // a degenerate hull is a segment, whose boundary is all of it
+            let mut layer = remaining.clone();
+            LexXY.sort_indices(points, &mut layer);
+            layer
+        } else {
+            let mut layer = vec![];
+            for (&a, &b) in hull.iter().zip(hull.iter().cycle().skip(1)) {
+                let (pa, pb) = (points[a], points[b]);
+                let mut on_edge: Vec<usize> = remaining.iter().copied()
+                    .filter(|&i| {
+                        let p = points[i];
+                        orient(pa, pb, p) == 0.0 && (p - pa).dot(pb - pa) >= 0.0 && dist2(pa, p) < dist2(pa, pb)
+                    })
+                    .collect();
+                on_edge.sort_by(|&i, &j| dist2(pa, points[i]).total_cmp(&dist2(pa, points[j])));
+                layer.append(&mut on_edge);
+            }
+            layer
+        };
+
+        let mut peeled = vec![false; points.len()];
+        for &i in &layer { peeled[i] = true; }
+        remaining.retain(|&i| !peeled[i]);
+        layers.push(layer);
+    }
+    layers
+}
+
+/// The convex layer each point belongs to, 0 being the outermost hull.
+pub fn hull_depths(points: &[Point]) -> Vec<usize> {
+    let mut depths = vec![0; points.len()];
+    for (depth, layer) in convex_layers(points).iter().enumerate() {
+        for &i in layer {
+            depths[i] = depth;
+        }
+    }
+    depths
+}
+
+/// The convex hull algorithms available, all producing the same output
+/// conventions as [`convex_hull`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    MonotoneChain,
+    GrahamScan,
+    JarvisMarch,
+    QuickHull,
+    Chan,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 5] = [
+        Algorithm::MonotoneChain,
+        Algorithm::GrahamScan,
+        Algorithm::JarvisMarch,
+        Algorithm::QuickHull,
+        Algorithm::Chan,
+    ];
+
+    /// The algorithm after this one in [`Algorithm::ALL`], wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|a| *a == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn hull(self, points: &[Point]) -> Vec<usize> {
+        self.run(points, &mut Trace::disabled())
+    }
+
+    /// Runs the algorithm while recording a [`Step`] at every decision it makes.
+    /// The last step always holds the finished hull.
+    pub fn trace(self, points: &[Point]) -> (Vec<usize>, Vec<Step>) {
+        let mut trace = Trace::enabled();
+        let hull = self.run(points, &mut trace);
+        trace.groups.clear();
+        trace.record(&hull, None);
+        (hull, trace.steps.unwrap_or_default())
+    }
+
+    fn run(self, points: &[Point], trace: &mut Trace) -> Vec<usize> {
+        let candidates = distinct_sorted(points);
+        if candidates.len() < 3 {
+            return candidates;
+        }
+        match self {
+            Algorithm::MonotoneChain => monotone_chain(points, &candidates, trace),
+            Algorithm::GrahamScan => graham_scan(points, &candidates, trace),
+            Algorithm::JarvisMarch => jarvis_march(points, &candidates, trace),
+            Algorithm::QuickHull => quickhull(points, &candidates, trace),
+            Algorithm::Chan => chan(points, &candidates, trace),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Algorithm::MonotoneChain => "monotone chain",
+            Algorithm::GrahamScan => "Graham scan",
+            Algorithm::JarvisMarch => "Jarvis march",
+            Algorithm::QuickHull => "QuickHull",
+            Algorithm::Chan => "Chan's algorithm",
+        })
+    }
+}
+
+/// A snapshot of an algorithm's working state, all as indices into the input points.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Step {
+    /// Hull vertices accepted so far (the stack, or the wrapped chain).
+    pub chain: Vec<usize>,
+    /// The edge currently being tested, if any.
+    pub candidate: Option<(usize, usize)>,
+    /// Auxiliary hulls the algorithm works with, such as Chan's group hulls.
+    pub groups: Vec<Vec<usize>>,
+}
+
+struct Trace {
+    steps: Option<Vec<Step>>,
+    groups: Vec<Vec<usize>>,
+}
+
+impl Trace {
+    fn disabled() -> Self { Self { steps: None, groups: vec![] } }
+    fn enabled() -> Self { Self { steps: Some(vec![]), groups: vec![] } }
+
+    fn record(&mut self, chain: &[usize], candidate: Option<(usize, usize)>) {
+        if let Some(steps) = self.steps.as_mut() {
+            steps.push(Step { chain: chain.to_vec(), candidate, groups: self.groups.clone() });
+        }
+    }
+}
+
+fn orient(a: Point, b: Point, c: Point) -> f64 {
+    predicates::orient2d(a, b, c)
+}
+
+fn dist2(a: Point, b: Point) -> f64 {
+    (b - a).dot(b - a)
+}
+
+/// Indices of the distinct points, sorted by x then y.
+fn distinct_sorted(points: &[Point]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    LexXY.sort_indices(points, &mut order);
+    order.dedup_by(|a, b| points[*a] == points[*b]);
+    order
+}
+
+fn monotone_chain(points: &[Point], sorted: &[usize], trace: &mut Trace) -> Vec<usize> {
+    let mut lower = half_hull(points, sorted.iter().copied(), &[], trace);
+    lower.pop();
+    let mut upper = half_hull(points, sorted.iter().rev().copied(), &lower, trace);
+    // each half ends where the other starts
+    upper.pop();
+    lower.append(&mut upper);
+    lower
+}
+
+fn half_hull(points: &[Point], sorted: impl Iterator<Item = usize>, done: &[usize], trace: &mut Trace) -> Vec<usize> {
+    let mut chain: Vec<usize> = vec![];
+    let shown = |chain: &[usize]| [done, chain].concat();
+    for i in sorted {
+        while chain.len() >= 2 {
+            let a = chain[chain.len() - 2];
+            let b = chain[chain.len() - 1];
+            trace.record(&shown(&chain), Some((b, i)));
+            if orient(points[a], points[b], points[i]) > 0.0 { break; }
+            chain.pop();
+        }
+        chain.push(i);
+        trace.record(&shown(&chain), None);
+    }
+    chain
+}
+
+fn graham_scan(points: &[Point], sorted: &[usize], trace: &mut Trace) -> Vec<usize> {
+    // the lowest point (leftmost among ties) sees all the others within [0, pi)
+    let pivot = *sorted.iter().min_by(|&&a, &&b| LexYX.cmp(&points[a], &points[b])).unwrap();
+    let p0 = points[pivot];
+    let mut by_angle: Vec<usize> = sorted.iter().copied().filter(|&i| i != pivot).collect();
+    Angular { pivot: p0 }.sort_indices(points, &mut by_angle);
+
+    // only the farthest point along each ray from the pivot can be on the hull
+    let mut rays: Vec<usize> = Vec::with_capacity(by_angle.len());
+    for i in by_angle {
+        match rays.last_mut() {
+            Some(last) if orient(p0, points[*last], points[i]) == 0.0 => *last = i,
+            _ => rays.push(i),
+        }
+    }
+
+    let mut stack = vec![pivot];
+    for i in rays {
+        while stack.len() >= 2 {
+            let a = stack[stack.len() - 2];
+            let b = stack[stack.len() - 1];
+            trace.record(&stack, Some((b, i)));
+            if orient(points[a], points[b], points[i]) > 0.0 { break; }
+            stack.pop();
+        }
+        stack.push(i);
+        trace.record(&stack, None);
+    }
+    // start from the leftmost point, like the other algorithms
+    let start = stack.iter().position(|&i| i == sorted[0]).unwrap();
+    stack.rotate_left(start);
+    stack
+}
+
+/// Whether `r` should replace `cand` as the next vertex wrapped to from `cur`.
+fn wraps_tighter(points: &[Point], cur: usize, cand: usize, r: usize) -> bool {
+    let o = orient(points[cur], points[cand], points[r]);
+    o < 0.0 || (o == 0.0 && dist2(points[cur], points[r]) > dist2(points[cur], points[cand]))
+}
+
+fn jarvis_march(points: &[Point], sorted: &[usize], trace: &mut Trace) -> Vec<usize> {
+    let start = sorted[0];
+    let mut hull = vec![start];
+    let mut cur = start;
+    for _ in 0..sorted.len() {
+        let mut cand = if sorted[0] == cur { sorted[1] } else { sorted[0] };
+        for &r in sorted {
+            if r == cur || r == cand { continue; }
+            trace.record(&hull, Some((cur, r)));
+            if wraps_tighter(points, cur, cand, r) {
+                cand = r;
+            }
+        }
+        if cand == start { break; }
+        hull.push(cand);
+        trace.record(&hull, None);
+        cur = cand;
+    }
+    hull
+}
+
+fn quickhull(points: &[Point], sorted: &[usize], trace: &mut Trace) -> Vec<usize> {
+    let a = sorted[0];
+    let b = sorted[sorted.len() - 1];
+    let (pa, pb) = (points[a], points[b]);
+    let below: Vec<usize> = sorted.iter().copied().filter(|&i| orient(pa, pb, points[i]) < 0.0).collect();
+    let above: Vec<usize> = sorted.iter().copied().filter(|&i| orient(pa, pb, points[i]) > 0.0).collect();
+
+    let mut hull = vec![a, b];
+    trace.record(&hull, Some((a, b)));
+    find_hull(points, a, b, below, &mut hull, trace);
+    find_hull(points, b, a, above, &mut hull, trace);
+    hull
+}
+
+/// Inserts into `hull`, between `p` and `q`, the hull vertices among `outside`,
+/// which must all lie to the right of `p -> q`.
+fn find_hull(points: &[Point], p: usize, q: usize, outside: Vec<usize>, hull: &mut Vec<usize>, trace: &mut Trace) {
+    let (pp, pq) = (points[p], points[q]);
+    let farthest = outside.iter().copied()
+        .min_by(|&i, &j| orient(pp, pq, points[i]).total_cmp(&orient(pp, pq, points[j])));
+    let Some(c) = farthest else { return; };
+
+    let at = hull.iter().position(|&i| i == p).unwrap() + 1;
+    hull.insert(at, c);
+    trace.record(hull, Some((p, q)));
+
+    let pc = points[c];
+    let right_of_pc = outside.iter().copied().filter(|&i| orient(pp, pc, points[i]) < 0.0).collect();
+    let right_of_cq = outside.iter().copied().filter(|&i| orient(pc, pq, points[i]) < 0.0).collect();
+    find_hull(points, p, c, right_of_pc, hull, trace);
+    find_hull(points, c, q, right_of_cq, hull, trace);
+}
+
+fn chan(points: &[Point], sorted: &[usize], trace: &mut Trace) -> Vec<usize> {
+    let start = sorted[0];
+    let mut t = 1u32;
+    loop {
+        let m = 1usize.checked_shl(1u32 << t).unwrap_or(usize::MAX).min(sorted.len());
+        let groups: Vec<Vec<usize>> = sorted.chunks(m)
+            .map(|group| {
+                if group.len() < 3 { group.to_vec() }
+                else { graham_scan(points, group, &mut Trace::disabled()) }
+            })
+            .collect();
+        trace.groups = groups.clone();
+
+        // the chunks are sorted, so the starting point opens the first group's hull
+        let mut cur = (0, 0);
+        let mut hull = vec![start];
+        trace.record(&hull, None);
+        let mut closed = false;
+        for _ in 0..m {
+            let cur_idx = groups[cur.0][cur.1];
+            let mut next: Option<(usize, usize)> = None;
+            for (g, group) in groups.iter().enumerate() {
+                let j = if g == cur.0 {
+                    if group.len() < 2 { continue; }
+                    (cur.1 + 1) % group.len()
+                } else {
+                    tangent(points, group, cur_idx)
+                };
+                trace.record(&hull, Some((cur_idx, group[j])));
+                let better = match next {
+                    None => true,
+                    Some((ng, nj)) => wraps_tighter(points, cur_idx, groups[ng][nj], group[j]),
+                };
+                if better { next = Some((g, j)); }
+            }
+            let Some(next) = next else { closed = true; break; };
+            let next_idx = groups[next.0][next.1];
+            if next_idx == start { closed = true; break; }
+            hull.push(next_idx);
+            trace.record(&hull, None);
+            cur = next;
+        }
+
+        if closed {
+            return hull;
+        }
+        t += 1;
+    }
+}
+
+/// Finds the vertex of the convex polygon `group` (counter-clockwise, with no
+/// collinear vertices) such that the whole polygon lies left of `from -> vertex`.
+///
+/// Binary search after Dan Sunday's tangent routine, falling back to a linear
+/// scan whenever the result fails its local check (e.g. `from` touching the polygon).
+fn tangent(points: &[Point], group: &[usize], from: usize) -> usize {
+    let n = group.len();
+    let p = points[from];
+    let at = |i: usize| points[group[i % n]];
+    let is_tangent = |i: usize| {
+        orient(p, at(i), at(i + 1)) >= 0.0 && orient(p, at(i), at(i + n - 1)) >= 0.0
+    };
+    let farthest_along = |mut i: usize| {
+        while orient(p, at(i), at(i + 1)) == 0.0 && dist2(p, at(i + 1)) > dist2(p, at(i)) {
+            i = (i + 1) % n;
+        }
+        i
+    };
+
+    if n >= 3 {
+        // `above(a, b)`: b is left of p -> a, so a is the better tangent candidate
+        let above = |a: usize, b: usize| orient(p, at(a), at(b)) > 0.0;
+        let below = |a: usize, b: usize| orient(p, at(a), at(b)) < 0.0;
+        let mut found = None;
+        if below(1, 0) && !above(n - 1, 0) {
+            found = Some(0);
+        } else {
+            let (mut a, mut b) = (0, n);
+            while b - a > 1 {
+                let c = (a + b) / 2;
+                let down_c = below(c + 1, c);
+                if down_c && !above(c - 1, c) {
+                    found = Some(c);
+                    break;
+                }
+                let up_a = above(a + 1, a);
+                if up_a {
+                    if down_c || above(a, c) { b = c; } else { a = c; }
+                } else if !down_c || !below(a, c) {
+                    a = c;
+                } else {
+                    b = c;
+                }
+            }
+        }
+        if let Some(i) = found.filter(|&i| is_tangent(i)) {
+            return farthest_along(i);
+        }
+    }
+
+    let mut best = 0;
+    for i in 1..n {
+        if group[i] != from && (group[best] == from || wraps_tighter(points, from, group[best], group[i])) {
+            best = i;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pts(coords: &[(f64, f64)]) -> Vec<Point> {
+        coords.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    fn scattered(count: usize) -> Vec<Point> {
+        (0..count)
+            .map(|i| {
+                let t = i as f64 * 0.37;
+                Point::new((t * 1.3).sin() * (i as f64), (t * 0.7).cos() * (count - i) as f64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn square_with_inner_point() {
+        let points = pts(&[(0.0, 0.0), (2.0, 2.0), (1.0, 1.0), (2.0, 0.0), (0.0, 2.0)]);
+        assert_eq!(convex_hull(&points), vec![0, 3, 1, 4]);
+    }
+
+    #[test]
+    fn collinear_points_on_edges_are_skipped() {
+        let points = pts(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 2.0), (1.0, 2.0), (0.0, 2.0), (0.0, 1.0)]);
+        assert_eq!(convex_hull(&points), vec![0, 2, 3, 5]);
+    }
+
+    #[test]
+    fn duplicates_are_reported_once() {
+        let points = pts(&[(0.0, 0.0), (0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 0.0)]);
+        assert_eq!(convex_hull(&points), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn fewer_than_three_points() {
+        assert_eq!(convex_hull(&[]), Vec::<usize>::new());
+        assert_eq!(convex_hull(&pts(&[(1.0, 1.0)])), vec![0]);
+        assert_eq!(convex_hull(&pts(&[(3.0, 1.0), (1.0, 1.0)])), vec![1, 0]);
+        assert_eq!(convex_hull(&pts(&[(1.0, 1.0), (1.0, 1.0), (1.0, 1.0)])), vec![0]);
+    }
+
+    #[test]
+    fn all_collinear() {
+        let points = pts(&[(1.0, 1.0), (3.0, 3.0), (0.0, 0.0), (2.0, 2.0)]);
+        assert_eq!(convex_hull(&points), vec![2, 1]);
+    }
+
+    #[test]
+    fn hull_contains_every_point() {
+        let points = scattered(200);
+        let hull = convex_hull(&points);
+        for w in 0..hull.len() {
+            let a = points[hull[w]];
+            let b = points[hull[(w + 1) % hull.len()]];
+            for p in &points {
+                assert!((b - a).cross(*p - a) >= 0.0, "{p:?} is outside of edge {a:?} -> {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn polygon_is_closed() {
+        let points = pts(&[(0.0, 0.0), (2.0, 0.0), (1.0, 2.0), (1.0, 0.5)]);
+        let poly = convex_hull_polygon(&points, macroquad::color::RED);
+        assert_eq!(poly.verts.len(), 3);
+        assert_eq!(poly.edges.iter().map(|e| (e.0, e.1)).collect::<Vec<_>>(), vec![(0, 1), (1, 2), (2, 0)]);
+    }
+
+    #[test]
+    fn dcel_has_the_hull_as_face() {
+        let points = scattered(50);
+        let hull = convex_hull(&points);
+        let dcel = hull_dcel(&points, &hull);
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.face_count(), 2);
+        let inside: Vec<_> = dcel.bounded_faces().collect();
+        assert_eq!(inside.len(), 1);
+        assert_eq!(dcel.face_points(inside[0]).count(), hull.len());
+        assert_eq!(hull_dcel(&points, &hull[..2]).face_count(), 1);
+    }
+
+    #[test]
+    fn nested_squares_peel_in_order() {
+        let points = pts(&[
+            (0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0),
+            (1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0),
+            (2.0, 2.0),
+        ]);
+        assert_eq!(convex_layers(&points), vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8]]);
+        assert_eq!(hull_depths(&points), vec![0, 0, 0, 0, 1, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn edge_points_and_duplicates_share_a_layer() {
+        let points = pts(&[(0.0, 0.0), (2.0, 0.0), (1.0, 0.0), (2.0, 2.0), (0.0, 2.0), (2.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(convex_layers(&points), vec![vec![0, 2, 1, 5, 3, 4], vec![6]]);
+    }
+
+    #[test]
+    fn collinear_leftovers_form_one_layer() {
+        let points = pts(&[(0.0, 0.0), (6.0, 0.0), (3.0, 6.0), (2.0, 1.0), (3.0, 2.0), (4.0, 3.0)]);
+        assert_eq!(convex_layers(&points), vec![vec![0, 1, 2], vec![3, 4, 5]]);
+    }
+
+    #[test]
+    fn algorithms_agree() {
+        let inputs = [
+            scattered(7),
+            scattered(300),
+            pts(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 2.0), (1.0, 2.0), (0.0, 2.0), (0.0, 1.0), (1.0, 1.0)]),
+            pts(&[(1.0, 1.0), (3.0, 3.0), (0.0, 0.0), (2.0, 2.0), (2.0, 2.0)]),
+            pts(&[(0.0, 0.0), (0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 0.0)]),
+            // a grid has plenty of collinear triples
+            (0..100).map(|i| Point::new((i % 10) as f64, (i / 10) as f64)).collect(),
+        ];
+        for points in &inputs {
+            let expected = convex_hull(points);
+            for algorithm in Algorithm::ALL {
+                assert_eq!(algorithm.hull(points), expected, "{algorithm} disagrees on {points:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn traces_end_on_the_hull() {
+        let points = scattered(40);
+        for algorithm in Algorithm::ALL {
+            let (hull, steps) = algorithm.trace(&points);
+            assert_eq!(hull, algorithm.hull(&points));
+            assert!(!steps.is_empty());
+            assert_eq!(steps.last().unwrap().candidate, None, "{algorithm}");
+        }
+    }
+
+    #[test]
+    fn tangent_from_outside() {
+        let points = pts(&[(0.0, 0.0), (2.0, 0.0), (3.0, 1.0), (2.0, 2.0), (0.0, 2.0), (-1.0, 1.0), (5.0, 1.0), (1.0, -3.0)]);
+        let group = [0, 1, 2, 3, 4, 5];
+        // from the right, everything is left of the ray through the top right corner
+        assert_eq!(tangent(&points, &group, 6), 3);
+        // from below, the ray through the right corner
+        assert_eq!(tangent(&points, &group, 7), 2);
+    }
+}
@@ -1,4 +1,20 @@
 pub mod geom;
 pub mod point;
+pub mod order;
 pub mod common_traits;
 pub mod utils;
+pub mod scene;
+pub mod hull;
+pub mod predicates;
+pub mod spatial;
+pub mod camera;
+pub mod render;
+pub mod hud;
+pub mod raster;
+pub mod scene_file;
+pub mod cli;
+pub mod logging;
+pub mod bench;
+pub mod triangulation;
+pub mod dcel;
+pub mod arrangement;
@@ -0,0 +1,267 @@
+//! The viewers' logs: one typed record per line, as JSON Lines or CSV, so the
+//! notebooks in `graphs/` can load them without parsing free text.
+//!
+//! Every record has the seconds since the logger was made (`time`), a `level`
+//! and a `tag` naming the [`Event`] variant, followed by that variant's fields:
+//!
+//! ```text
+//! {"time":0.0021,"level":"info","tag":"Timing","operation":"bot paths","n":10,"m":900,"duration":0.0014}
+//! ```
+//!
+//! CSV logs have a column for every field of every event, in [`CSV_COLUMNS`]
+//! order, left empty where an event doesn't have it.
+
+use log::{Level, LevelFilter};
+use serde::Serialize;
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+use crate::cli::Args;
+
+/// The columns of CSV logs.
+pub const CSV_COLUMNS: &[&str] = &[
+    "time", "level", "tag",
+    "seed", "frame_time", "fps", "action", "x", "y", "object",
+    "operation", "n", "m", "duration", "command", "path", "error", "msg",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseAction {
+    /// The mouse moved here.
+    Pos,
+    Clicked,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileAction {
+    Save,
+    Load,
+    Export,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "tag")]
+pub enum Event {
+    /// What the random generators were seeded with, to replay the run.
+    Seed { seed: u64 },
+    FrameTime { frame_time: f32, fps: i32 },
+    Mouse { action: MouseAction, x: f32, y: f32 },
+    Select { object: String },
+    /// `operation` took `duration` seconds on `n` items. `m` is a second
+    /// count where there is one, like the cells of the grid bots walk on.
+    Timing {
+        operation: String,
+        n: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        m: Option<usize>,
+        duration: f64,
+    },
+    Command { command: String },
+    File {
+        action: FileAction,
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Error { msg: String },
+}
+
+impl Event {
+    pub fn timing(operation: impl Into<String>, n: usize, duration: Duration) -> Self {
+        Event::Timing { operation: operation.into(), n, m: None, duration: duration.as_secs_f64() }
+    }
+
+    /// Gives a [`Event::Timing`] its second size, leaving other events be.
+    pub fn with_m(mut self, size: usize) -> Self {
+        if let Event::Timing { m, .. } = &mut self {
+            *m = Some(size);
+        }
+        self
+    }
+
+    /// How `action` on the file at `path` went.
+    pub fn file<T, E: Display>(action: FileAction, path: impl Into<String>, result: &Result<T, E>) -> Self {
+        Event::File { action, path: path.into(), error: result.as_ref().err().map(E::to_string) }
+    }
+
+    pub fn level(&self) -> Level {
+        match self {
+            Event::Error { .. } => Level::Error,
+            Event::File { error: Some(_), .. } => Level::Warn,
+            Event::Seed { .. } | Event::Timing { .. } | Event::Command { .. } | Event::File { .. } => Level::Info,
+            Event::Select { .. } | Event::Mouse { action: MouseAction::Clicked, .. } => Level::Debug,
+            Event::FrameTime { .. } | Event::Mouse { action: MouseAction::Pos, .. } => Level::Trace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    JsonLines,
+    Csv,
+}
+
+impl Format {
+    /// CSV for `.csv` files, JSON Lines for anything else.
+    pub fn of(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Format::Csv,
+            _ => Format::JsonLines,
+        }
+    }
+}
+
+pub struct Logger<W: Write = File> {
+    out: W,
+    format: Format,
+    level: LevelFilter,
+    startup: Instant,
+}
+
+impl<W: Write> fmt::Debug for Logger<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logger")
+         .field("format", &self.format)
+         .field("level", &self.level)
+         .field("startup", &self.startup)
+         .finish()
+    }
+}
+
+impl Logger {
+    /// Logs to the file at `path`, in the format its extension asks for.
+    pub fn create(path: impl AsRef<Path>, level: LevelFilter) -> io::Result<Self> {
+        let format = Format::of(&path);
+        Logger::new(File::create(path)?, format, level)
+    }
+
+    /// Logs to `--log`, or `default_path` without it, keeping the events
+    /// `--log-level` lets through (all of them by default).
+    pub fn from_args(args: &Args, default_path: &str) -> Self {
+        let path = args.option("log").unwrap_or(default_path);
+        let level = args.parsed_option("log-level").unwrap_or(LevelFilter::Trace);
+        Logger::create(path, level).unwrap_or_else(|e| panic!("couldn't create the log file {path}: {e}"))
+    }
+}
+
+impl<W: Write> Logger<W> {
+    /// Writes the CSV header right away.
+    pub fn new(mut out: W, format: Format, level: LevelFilter) -> io::Result<Self> {
+        if format == Format::Csv {
+            writeln!(out, "{}", CSV_COLUMNS.join(","))?;
+        }
+        Ok(Self { out, format, level, startup: Instant::now() })
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    pub fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    /// Writes `event` when its level is let through. Each record goes out in
+    /// a single write, so a crash doesn't leave half of one behind.
+    pub fn log(&mut self, event: Event) {
+        let level = event.level();
+        if level > self.level { return; }
+        let time = self.startup.elapsed().as_secs_f64();
+        let record = match self.format {
+            Format::JsonLines => json_record(time, level, &event),
+            Format::Csv => csv_record(time, level, &event),
+        };
+        self.out.write_all(record.as_bytes()).expect("couldn't write log line");
+    }
+}
+
+fn level_name(level: Level) -> String {
+    level.as_str().to_lowercase()
+}
+
+fn json_record(time: f64, level: Level, event: &Event) -> String {
+    let fields = serde_json::to_string(event).expect("events always serialize");
+    // the event's own fields follow the header ones
+    format!("{{\"time\":{time},\"level\":\"{}\",{}\n", level_name(level), &fields[1..])
+}
+
+fn csv_record(time: f64, level: Level, event: &Event) -> String {
+    let serde_json::Value::Object(mut fields) = serde_json::to_value(event).expect("events always serialize") else {
+        unreachable!("events serialize to objects");
+    };
+    fields.insert("time".into(), time.into());
+    fields.insert("level".into(), level_name(level).into());
+    let cells = CSV_COLUMNS.iter().map(|column| match fields.get(*column) {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) if s.contains([',', '"', '\n']) => format!("\"{}\"", s.replace('"', "\"\"")),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    });
+    cells.collect::<Vec<_>>().join(",") + "\n"
+}
+
+/// `./log-<hour>-<minute>-<second>-<nanosecond>.jsonl`, so runs don't
+/// overwrite each other's logs.
+pub fn timestamped_path() -> String {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    format!("./log-{}-{}-{}-{}.jsonl", now.hour(), now.minute(), now.second(), now.nanosecond())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(logger: &Logger<Vec<u8>>) -> Vec<String> {
+        String::from_utf8(logger.get_ref().clone()).unwrap().lines().map(String::from).collect()
+    }
+
+    /// The record with its time, which changes from run to run, cut off.
+    fn after_time(line: &str, separator: char) -> &str {
+        line.split_once(separator).unwrap().1.split_once(separator).unwrap().1
+    }
+
+    #[test]
+    fn json_lines_have_typed_fields() {
+        let mut logger = Logger::new(Vec::new(), Format::JsonLines, LevelFilter::Trace).unwrap();
+        logger.log(Event::Seed { seed: 42 });
+        logger.log(Event::timing("bot paths", 10, Duration::from_millis(1500)).with_m(900));
+        logger.log(Event::file(FileAction::Save, "scene.txt", &Err::<(), _>("disk full")));
+        let lines = lines(&logger);
+        assert!(lines[0].starts_with("{\"time\":"));
+        assert_eq!(after_time(&lines[0], ','), "\"tag\":\"Seed\",\"seed\":42}");
+        assert_eq!(after_time(&lines[1], ','),
+                   "\"tag\":\"Timing\",\"operation\":\"bot paths\",\"n\":10,\"m\":900,\"duration\":1.5}");
+        assert!(lines[2].contains("\"level\":\"warn\",\"tag\":\"File\",\"action\":\"save\",\"path\":\"scene.txt\",\"error\":\"disk full\"}"));
+
+        for line in &lines {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(record["time"].as_f64().unwrap() >= 0.0);
+        }
+    }
+
+    #[test]
+    fn csv_has_a_column_per_field_and_levels_filter() {
+        let mut logger = Logger::new(Vec::new(), Format::Csv, LevelFilter::Debug).unwrap();
+        logger.log(Event::FrameTime { frame_time: 0.016, fps: 60 });
+        logger.log(Event::Mouse { action: MouseAction::Pos, x: 1.0, y: 2.0 });
+        logger.log(Event::Mouse { action: MouseAction::Clicked, x: 1.5, y: 2.0 });
+        logger.log(Event::Select { object: "Circle { radius: 1.0, \"x\" }".to_string() });
+        let lines = lines(&logger);
+        assert_eq!(lines.len(), 3, "{lines:?}");
+        assert_eq!(lines[0], CSV_COLUMNS.join(","));
+        assert_eq!(after_time(&lines[1], ','), "Mouse,,,,clicked,1.5,2.0,,,,,,,,,");
+        assert_eq!(after_time(&lines[2], ','), "Select,,,,,,,\"Circle { radius: 1.0, \"\"x\"\" }\",,,,,,,,");
+        assert!(lines[1].split(',').nth(1) == Some("debug"));
+        assert!(lines.iter().skip(1).all(|line| line.split(',').count() >= CSV_COLUMNS.len()));
+
+        assert_eq!(Format::of("runs/log.CSV"), Format::Csv);
+        assert_eq!(Format::of("log.jsonl"), Format::JsonLines);
+    }
+}
@@ -0,0 +1,276 @@
+//! Point orderings as comparator types.
+//!
+//! [`Point`] has no `Ord` of its own: each algorithm picks the order it needs
+//! and sorts with it explicitly, e.g. `points.sort_by(LexXY.comparator())`.
+
+use std::cmp::Ordering;
+use crate::point::{Point, Scalar};
+
+pub trait PointOrder<T: Scalar = f64> {
+    fn cmp(&self, a: &Point<T>, b: &Point<T>) -> Ordering;
+
+    /// The order as a closure, for `sort_by` and friends.
+    fn comparator(&self) -> impl Fn(&Point<T>, &Point<T>) -> Ordering + '_ {
+        move |a, b| self.cmp(a, b)
+    }
+
+    /// Sorts indices into `points` by the points they refer to.
+    fn sort_indices(&self, points: &[Point<T>], indices: &mut [usize]) {
+        indices.sort_by(|&a, &b| self.cmp(&points[a], &points[b]));
+    }
+}
+
+/// By increasing y, then by decreasing x: the sweep order of Fortune's algorithm.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SweepOrder;
+
+impl<T: Scalar> PointOrder<T> for SweepOrder {
+    fn cmp(&self, a: &Point<T>, b: &Point<T>) -> Ordering {
+        a.y().total_cmp(&b.y()).then_with(|| b.x().total_cmp(&a.x()))
+    }
+}
+
+/// Lexicographic, by x then by y.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexXY;
+
+impl<T: Scalar> PointOrder<T> for LexXY {
+    fn cmp(&self, a: &Point<T>, b: &Point<T>) -> Ordering {
+        a.x().total_cmp(&b.x()).then_with(|| a.y().total_cmp(&b.y()))
+    }
+}
+
+/// Lexicographic, by y then by x.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexYX;
+
+impl<T: Scalar> PointOrder<T> for LexYX {
+    fn cmp(&self, a: &Point<T>, b: &Point<T>) -> Ordering {
+        a.y().total_cmp(&b.y()).then_with(|| a.x().total_cmp(&b.x()))
+    }
+}
+
+/// Counter-clockwise around `pivot`, starting from the positive x direction.
+///
+/// The pivot itself comes first and points along the same ray are ordered by
+/// distance, nearest first. Orientation tests go through [`Scalar::orient`],
+/// so the order is exact.
+#[derive(Debug, Clone, Copy)]
+pub struct Angular<T: Scalar = f64> {
+    pub pivot: Point<T>,
+}
+
+impl<T: Scalar> Angular<T> {
+    /// 0 for the pivot, 1 for angles in `[0, pi)`, 2 for `[pi, 2pi)`.
+    fn half(&self, p: &Point<T>) -> u8 {
+        match (p.y().total_cmp(&self.pivot.y()), p.x().total_cmp(&self.pivot.x())) {
+            (Ordering::Equal, Ordering::Equal) => 0,
+            (Ordering::Greater, _) | (Ordering::Equal, Ordering::Greater) => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl<T: Scalar> PointOrder<T> for Angular<T> {
+    fn cmp(&self, a: &Point<T>, b: &Point<T>) -> Ordering {
+        self.half(a).cmp(&self.half(b))
+            .then_with(|| T::orient(self.pivot, *b, *a))
+            .then_with(|| {
+                let (da, db) = (*a - self.pivot, *b - self.pivot);
+                da.dot(da).total_cmp(&db.dot(db))
+            })
+    }
+}
+
+/// Bits per axis of the grid [`Morton`] and [`Hilbert`] quantize onto.
+const CURVE_BITS: u32 = 16;
+
+/// Maps points within a bounding box onto a `2^CURVE_BITS` square grid.
+#[derive(Debug, Clone, Copy)]
+struct CurveGrid {
+    min: (f64, f64),
+    scale: (f64, f64),
+}
+
+impl CurveGrid {
+    fn new<T: Scalar>(min: Point<T>, max: Point<T>) -> Self {
+        let cells = (1u64 << CURVE_BITS) as f64;
+        let scale = |lo: T, hi: T| {
+            let extent = hi.to_f64() - lo.to_f64();
+            if extent > 0.0 { cells / extent } else { 0.0 }
+        };
+        Self {
+            min: (min.x().to_f64(), min.y().to_f64()),
+            scale: (scale(min.x(), max.x()), scale(min.y(), max.y())),
+        }
+    }
+
+    fn covering<T: Scalar>(points: &[Point<T>]) -> Self {
+        let Some(first) = points.first() else { return Self::new(Point::<f64>::new(0.0, 0.0), Point::new(0.0, 0.0)); };
+        let (mut min, mut max) = (first.cast::<f64>(), first.cast::<f64>());
+        for p in points {
+            let p = p.cast::<f64>();
+            min = Point::new(min.x().min(p.x()), min.y().min(p.y()));
+            max = Point::new(max.x().max(p.x()), max.y().max(p.y()));
+        }
+        Self::new(min, max)
+    }
+
+    fn cell<T: Scalar>(&self, p: &Point<T>) -> (u32, u32) {
+        let last = ((1u64 << CURVE_BITS) - 1) as f64;
+        let q = |v: T, min: f64, scale: f64| ((v.to_f64() - min) * scale).clamp(0.0, last) as u32;
+        (q(p.x(), self.min.0, self.scale.0), q(p.y(), self.min.1, self.scale.1))
+    }
+}
+
+/// Along the Z-order (Morton) curve over a bounding box, ties broken by [`LexXY`].
+/// Points outside the box are clamped onto its border.
+#[derive(Debug, Clone, Copy)]
+pub struct Morton(CurveGrid);
+
+impl Morton {
+    pub fn new<T: Scalar>(min: Point<T>, max: Point<T>) -> Self {
+        Self(CurveGrid::new(min, max))
+    }
+
+    /// A Morton order over the bounding box of `points`.
+    pub fn covering<T: Scalar>(points: &[Point<T>]) -> Self {
+        Self(CurveGrid::covering(points))
+    }
+
+    pub fn code<T: Scalar>(&self, p: &Point<T>) -> u64 {
+        let spread = |v: u32| {
+            let mut v = v as u64;
+            v = (v | (v << 8)) & 0x00ff_00ff;
+            v = (v | (v << 4)) & 0x0f0f_0f0f;
+            v = (v | (v << 2)) & 0x3333_3333;
+            (v | (v << 1)) & 0x5555_5555
+        };
+        let (x, y) = self.0.cell(p);
+        spread(x) | (spread(y) << 1)
+    }
+}
+
+impl<T: Scalar> PointOrder<T> for Morton {
+    fn cmp(&self, a: &Point<T>, b: &Point<T>) -> Ordering {
+        self.code(a).cmp(&self.code(b)).then_with(|| LexXY.cmp(a, b))
+    }
+}
+
+/// Along the Hilbert curve over a bounding box, ties broken by [`LexXY`].
+/// Points outside the box are clamped onto its border.
+#[derive(Debug, Clone, Copy)]
+pub struct Hilbert(CurveGrid);
+
+impl Hilbert {
+    pub fn new<T: Scalar>(min: Point<T>, max: Point<T>) -> Self {
+        Self(CurveGrid::new(min, max))
+    }
+
+    /// A Hilbert order over the bounding box of `points`.
+    pub fn covering<T: Scalar>(points: &[Point<T>]) -> Self {
+        Self(CurveGrid::covering(points))
+    }
+
+    pub fn code<T: Scalar>(&self, p: &Point<T>) -> u64 {
+        let n = 1u32 << CURVE_BITS;
+        let (mut x, mut y) = self.0.cell(p);
+        let mut d = 0u64;
+        let mut s = n / 2;
+        while s > 0 {
+            let rx = (x & s > 0) as u32;
+            let ry = (y & s > 0) as u32;
+            d += (s as u64) * (s as u64) * ((3 * rx) ^ ry) as u64;
+            if ry == 0 {
+                if rx == 1 {
+                    x = n - 1 - x;
+                    y = n - 1 - y;
+                }
+                std::mem::swap(&mut x, &mut y);
+            }
+            s /= 2;
+        }
+        d
+    }
+}
+
+impl<T: Scalar> PointOrder<T> for Hilbert {
+    fn cmp(&self, a: &Point<T>, b: &Point<T>) -> Ordering {
+        self.code(a).cmp(&self.code(b)).then_with(|| LexXY.cmp(a, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pts(coords: &[(f64, f64)]) -> Vec<Point> {
+        coords.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    fn lattice(side: i64) -> Vec<Point<i64>> {
+        (0..side).flat_map(|x| (0..side).map(move |y| Point::new(x, y))).collect()
+    }
+
+    #[test]
+    fn sweep_is_y_then_reversed_x() {
+        let mut points = pts(&[(0.0, 1.0), (1.0, 0.0), (0.0, 0.0)]);
+        points.sort_by(SweepOrder.comparator());
+        assert_eq!(points, pts(&[(1.0, 0.0), (0.0, 0.0), (0.0, 1.0)]));
+    }
+
+    #[test]
+    fn lexicographic() {
+        let mut points = pts(&[(1.0, 0.0), (0.0, 1.0), (0.0, 0.0)]);
+        points.sort_by(LexXY.comparator());
+        assert_eq!(points, pts(&[(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)]));
+        points.sort_by(LexYX.comparator());
+        assert_eq!(points, pts(&[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]));
+    }
+
+    #[test]
+    fn angular_goes_around_once() {
+        let order = Angular { pivot: Point::new(0.0, 0.0) };
+        let mut points = pts(&[(0.0, -1.0), (-1.0, 0.0), (2.0, 0.0), (1.0, 1.0), (0.0, 0.0), (1.0, 0.0), (1.0, -1.0), (0.0, 1.0)]);
+        points.sort_by(order.comparator());
+        assert_eq!(points, pts(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (1.0, 1.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0), (1.0, -1.0)]));
+    }
+
+    #[test]
+    fn angular_resolves_nearly_parallel_rays() {
+        // the second ray is a single ulp steeper than the first
+        let order = Angular { pivot: Point::new(0.5, 0.5) };
+        let a = Point::new(24.0, 24.0);
+        let b = Point::new(12.0, 12.0f64.next_up());
+        assert_eq!(order.cmp(&a, &b), Ordering::Less);
+        assert_eq!(order.cmp(&b, &a), Ordering::Greater);
+    }
+
+    #[test]
+    fn morton_is_z_order() {
+        let order = Morton::new(Point::new(0, 0), Point::new(2, 2));
+        let mut points = lattice(2);
+        points.sort_by(order.comparator());
+        assert_eq!(points, vec![Point::new(0, 0), Point::new(1, 0), Point::new(0, 1), Point::new(1, 1)]);
+    }
+
+    #[test]
+    fn hilbert_visits_neighbours() {
+        let mut points = lattice(8);
+        let order = Hilbert::new(Point::new(0, 0), Point::new(8, 8));
+        points.sort_by(order.comparator());
+        assert_eq!(points[0], Point::new(0, 0));
+        for w in points.windows(2) {
+            let d = w[1] - w[0];
+            assert_eq!(d.x().abs() + d.y().abs(), 1, "{:?} -> {:?}", w[0], w[1]);
+        }
+    }
+
+    #[test]
+    fn sort_indices_by_order() {
+        let points = pts(&[(2.0, 0.0), (0.0, 0.0), (1.0, 5.0)]);
+        let mut indices = vec![0, 1, 2];
+        LexXY.sort_indices(&points, &mut indices);
+        assert_eq!(indices, vec![1, 2, 0]);
+    }
+}
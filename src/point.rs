@@ -1,113 +1,253 @@
-use rand::random;
-use std::ops::{Add, Div, Mul, Sub};
+use rand::Rng;
+use std::ops::{Add, Div, Mul, Neg, Sub};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use ordered_float::OrderedFloat;
 use std::cmp::Ordering;
 use macroquad::math::FloatExt;
+use num_rational::Rational64;
+use num_traits::{ToPrimitive, Zero};
+use crate::predicates;
+
+/// A coordinate type [`Point`] can be built over.
+///
+/// Floats are compared and hashed like [`OrderedFloat`] (so `-0.0 == 0.0`
+/// and NaN equals itself), which gives every scalar a total order.
+pub trait Scalar:
+    Copy + fmt::Debug + fmt::Display + PartialEq + PartialOrd + Zero
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self>
+{
+    fn total_cmp(&self, other: &Self) -> Ordering;
+    fn hash_scalar<H: Hasher>(&self, state: &mut H);
+    fn to_f64(self) -> f64;
+    /// Converts from a float, rounding to the nearest value the type can hold.
+    /// Never panics: like an `as` cast, the exact types saturate past their
+    /// range and take NaN as zero.
+    fn from_f64(v: f64) -> Self;
+
+    /// The sign of the orientation of `a`, `b`, `c`: `Greater` when they turn
+    /// counter-clockwise. Exact for every scalar, floats go through
+    /// [`predicates::orient2d`].
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Ordering {
+        (b - a).cross(c - a).total_cmp(&Self::zero())
+    }
+}
+
+impl Scalar for f64 {
+    fn total_cmp(&self, other: &Self) -> Ordering { OrderedFloat(*self).cmp(&OrderedFloat(*other)) }
+    fn hash_scalar<H: Hasher>(&self, state: &mut H) { OrderedFloat(*self).hash(state) }
+    fn to_f64(self) -> f64 { self }
+    fn from_f64(v: f64) -> Self { v }
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Ordering {
+        Scalar::total_cmp(&predicates::orient2d(a, b, c), &0.0)
+    }
+}
 
-/// A point in two dimensions
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Point(euclid::default::Vector2D<ordered_float::OrderedFloat<f64>>);
+impl Scalar for f32 {
+    fn total_cmp(&self, other: &Self) -> Ordering { OrderedFloat(*self).cmp(&OrderedFloat(*other)) }
+    fn hash_scalar<H: Hasher>(&self, state: &mut H) { OrderedFloat(*self).hash(state) }
+    fn to_f64(self) -> f64 { self as f64 }
+    fn from_f64(v: f64) -> Self { v as f32 }
+    fn orient(a: Point<Self>, b: Point<Self>, c: Point<Self>) -> Ordering {
+        f64::orient(a.cast(), b.cast(), c.cast())
+    }
+}
 
-impl Point {
+impl Scalar for i64 {
+    fn total_cmp(&self, other: &Self) -> Ordering { self.cmp(other) }
+    fn hash_scalar<H: Hasher>(&self, state: &mut H) { self.hash(state) }
+    fn to_f64(self) -> f64 { self as f64 }
+    fn from_f64(v: f64) -> Self { v.round() as i64 }
+}
+
+impl Scalar for Rational64 {
+    fn total_cmp(&self, other: &Self) -> Ordering { self.cmp(other) }
+    fn hash_scalar<H: Hasher>(&self, state: &mut H) { self.hash(state) }
+    fn to_f64(self) -> f64 { ToPrimitive::to_f64(&self).unwrap_or(f64::NAN) }
+    fn from_f64(v: f64) -> Self {
+        Rational64::approximate_float(v).unwrap_or_else(|| {
+            if v > 0.0 { i64::MAX.into() } else if v < 0.0 { (-i64::MAX).into() } else { Rational64::zero() }
+        })
+    }
+}
+
+/// A point in two dimensions.
+///
+/// Points are `Eq` and `Hash` but deliberately not `Ord`; sort them with one
+/// of the comparators in [`crate::order`].
+#[derive(Clone, Copy)]
+pub struct Point<T = f64>(euclid::default::Vector2D<T>);
+
+impl<T: Scalar> Point<T> {
     /// Constructs a new `Point`.
-    pub fn new(x: f64, y: f64) -> Self {
-        Point(euclid::Vector2D::new(OrderedFloat::<f64>(x), OrderedFloat::<f64>(y)))
+    pub fn new(x: T, y: T) -> Self {
+        Point(euclid::Vector2D::new(x, y))
     }
 
     /// Getter for the x coordinate.
-    pub fn x(&self) -> f64 {
-        self.0.x.into_inner()
+    pub fn x(&self) -> T {
+        self.0.x
     }
 
     /// Getter for the y coordinate.
-    pub fn y(&self) -> f64 {
-        self.0.y.into_inner()
+    pub fn y(&self) -> T {
+        self.0.y
     }
 
-    /// Maybe implement rand::Fill?
-    pub fn rand() -> Self {
-        Point::new(random::<f64>(), random::<f64>())
+    /// Converts to another scalar type, going through `f64`.
+    pub fn cast<U: Scalar>(self) -> Point<U> {
+        Point::new(U::from_f64(self.x().to_f64()), U::from_f64(self.y().to_f64()))
     }
 
     pub fn lerp(&self, other: &Self, v: f64) -> Self {
         Self::new(
-            self.x().lerp(other.x(), v),
-            self.y().lerp(other.y(), v),
+            T::from_f64(self.x().to_f64().lerp(other.x().to_f64(), v)),
+            T::from_f64(self.y().to_f64().lerp(other.y().to_f64(), v)),
         )
     }
 
     pub fn magnitude(&self) -> f64 {
-        (self.0.x.powi(2) + self.0.y.powi(2)).sqrt()
+        self.dot(*self).to_f64().sqrt()
+    }
+
+    /// Computes the cross product of two points, viewed as vectors from the origin.
+    pub fn cross(self, rhs: Self) -> T {
+        self.x() * rhs.y() - self.y() * rhs.x()
+    }
+
+    /// Computes the dot product of two points, viewed as vectors from the origin.
+    pub fn dot(self, rhs: Self) -> T {
+        self.x() * rhs.x() + self.y() * rhs.y()
     }
 }
 
-impl Div<f64> for Point {
+impl Point {
+    /// Maybe implement rand::Fill?
+    pub fn rand(rng: &mut impl Rng) -> Self {
+        Point::new(rng.random::<f64>(), rng.random::<f64>())
+    }
+}
+
+impl<T: Scalar> Div<T> for Point<T> {
     type Output = Self;
 
-    fn div(mut self, rhs: f64) -> Self::Output {
-        self.0.x /= rhs;
-        self.0.y /= rhs;
-        self
+    fn div(self, rhs: T) -> Self::Output {
+        Point::new(self.x() / rhs, self.y() / rhs)
     }
 }
 
-impl fmt::Debug for Point {
+impl<T: Scalar> fmt::Debug for Point<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({0:.1}, {1:.1})", self.x(), self.y())
     }
 }
 
-impl Mul<f64> for Point {
-    type Output = Point;
+impl<T: Scalar> Mul<T> for Point<T> {
+    type Output = Self;
 
-    fn mul(self, _rhs: f64) -> Point {
-        Point::new(self.0.x.into_inner() * _rhs, self.0.y.into_inner() * _rhs)
+    fn mul(self, _rhs: T) -> Self {
+        Point::new(self.x() * _rhs, self.y() * _rhs)
     }
 }
 
-impl Sub<Point> for Point {
-    type Output = Point;
+impl<T: Scalar> Sub for Point<T> {
+    type Output = Self;
 
-    fn sub(self, _rhs: Point) -> Point {
+    fn sub(self, _rhs: Self) -> Self {
         Point::new(self.x() - _rhs.x(), self.y() - _rhs.y())
     }
 }
 
-impl Add<Point> for Point {
-    type Output = Point;
+impl<T: Scalar> Add for Point<T> {
+    type Output = Self;
 
-    fn add(self, _rhs: Point) -> Point {
+    fn add(self, _rhs: Self) -> Self {
         Point::new(self.x() + _rhs.x(), self.y() + _rhs.y())
     }
 }
 
-impl Point {
-    /// Computes the cross product of two points, viewed as vectors from the origin.
-    pub fn cross(self, rhs: Point) -> f64 {
-        self.x() * rhs.y() - self.y() * rhs.x()
+impl<T: Scalar> PartialEq for Point<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.x().total_cmp(&other.x()).is_eq() && self.y().total_cmp(&other.y()).is_eq()
     }
+}
 
-    /// Computes the dot product of two points, viewed as vectors from the origin.
-    pub fn dot(self, rhs: Point) -> f64 {
-        self.x() * rhs.x() + self.y() * rhs.y()
+impl<T: Scalar> Eq for Point<T> {}
+
+impl<T: Scalar> Hash for Point<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.x().hash_scalar(state);
+        self.y().hash_scalar(state);
+    }
+}
+
+impl<T: Scalar> From<euclid::default::Vector2D<f32>> for Point<T> {
+    fn from(v: euclid::default::Vector2D<f32>) -> Self {
+        Point::new(T::from_f64(v.x as f64), T::from_f64(v.y as f64))
+    }
+}
+
+impl<T: Scalar> From<euclid::default::Point2D<f32>> for Point<T> {
+    fn from(p: euclid::default::Point2D<f32>) -> Self {
+        p.to_vector().into()
+    }
+}
+
+impl<T: Scalar> From<Point<T>> for euclid::default::Vector2D<f32> {
+    fn from(p: Point<T>) -> Self {
+        euclid::default::Vector2D::new(p.x().to_f64() as f32, p.y().to_f64() as f32)
+    }
+}
+
+impl<T: Scalar> From<Point<T>> for euclid::default::Point2D<f32> {
+    fn from(p: Point<T>) -> Self {
+        euclid::default::Point2D::new(p.x().to_f64() as f32, p.y().to_f64() as f32)
     }
 }
 
-impl PartialOrd for Point {
-    fn partial_cmp(&self, other: &Point) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<T: Scalar> From<Point<T>> for macroquad::math::Vec2 {
+    fn from(p: Point<T>) -> Self {
+        macroquad::math::Vec2::new(p.x().to_f64() as f32, p.y().to_f64() as f32)
     }
 }
 
-impl Ord for Point {
-    fn cmp(&self, other: &Point) -> Ordering {
-        let x = self.0.x; let y = self.0.y;
-        if y > other.0.y { return Ordering::Greater; }
-        else if y == other.0.y {
-            if x < other.0.x { return Ordering::Greater; }
-            else if x == other.0.x { return Ordering::Equal; }
-            else { return Ordering::Less; }
-        } else { return Ordering::Less; }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn signed_zeros_are_equal() {
+        let set: HashSet<Point> = [Point::new(0.0, -0.0), Point::new(-0.0, 0.0)].into_iter().collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn integer_cross_is_exact() {
+        let big = 1i64 << 30;
+        let (a, b) = (Point::new(big + 1, big), Point::new(big, big - 1));
+        assert_eq!(a.cross(b), (big + 1) * (big - 1) - big * big);
+        assert_eq!(a.cross(b), -1);
+    }
+
+    #[test]
+    fn rational_arithmetic() {
+        let third = Rational64::new(1, 3);
+        let p = Point::new(third, third * Rational64::from_integer(2));
+        assert_eq!((p * Rational64::from_integer(3)).x(), Rational64::from_integer(1));
+        assert_eq!(p.cross(p), Rational64::zero());
+        assert_eq!(Point::<Rational64>::new(Rational64::new(1, 2), Rational64::zero()).cast::<f64>(), Point::new(0.5, 0.0));
+        let odd = Point::new(f64::NAN, f64::NEG_INFINITY).cast::<Rational64>();
+        assert_eq!((odd.x(), odd.y()), (Rational64::zero(), Rational64::from_integer(-i64::MAX)));
+        assert_eq!(Rational64::from_f64(1e300), Rational64::from_integer(i64::MAX));
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        let v = euclid::default::Vector2D::new(1.5f32, -2.25);
+        let p: Point = v.into();
+        assert_eq!(p, Point::new(1.5, -2.25));
+        assert_eq!(euclid::default::Vector2D::<f32>::from(p), v);
+        assert_eq!(Point::<i64>::from(v), Point::new(2, -2));
     }
 }
@@ -0,0 +1,312 @@
+//! Robust geometric predicates, after Shewchuk's "Adaptive Precision
+//! Floating-Point Arithmetic and Fast Robust Geometric Predicates".
+//!
+//! Each predicate first evaluates its determinant in plain floating point and
+//! checks the result against a forward error bound. Only when the sign can't
+//! be trusted is the determinant recomputed exactly with expansion arithmetic,
+//! so the common case costs about as much as the naive formula.
+
+use std::ops::{Add, Mul, Sub};
+use crate::point::Point;
+
+/// Half an ulp of 1.0, the relative rounding error of a single operation.
+const EPSILON: f64 = f64::EPSILON / 2.0;
+const CCW_ERRBOUND_A: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const ICC_ERRBOUND_A: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+
+/// Twice the signed area of the triangle `a`, `b`, `c`.
+///
+/// Positive when the points run counter-clockwise (in a y-up frame), negative
+/// when they run clockwise and zero exactly when they are collinear. The
+/// magnitude is approximate, but the sign is always exact.
+pub fn orient2d(a: Point, b: Point, c: Point) -> f64 {
+    let detleft = (a.x() - c.x()) * (b.y() - c.y());
+    let detright = (a.y() - c.y()) * (b.x() - c.x());
+    let det = detleft - detright;
+
+    let detsum = if detleft > 0.0 {
+        if detright <= 0.0 { return det; }
+        detleft + detright
+    } else if detleft < 0.0 {
+        if detright >= 0.0 { return det; }
+        -detleft - detright
+    } else {
+        return det;
+    };
+
+    let errbound = CCW_ERRBOUND_A * detsum;
+    if det >= errbound || -det >= errbound {
+        return det;
+    }
+    orient2d_exact(a, b, c)
+}
+
+/// Whether `d` lies inside the circle through `a`, `b` and `c`.
+///
+/// Positive when `d` is inside and `a`, `b`, `c` run counter-clockwise,
+/// negative when it is outside, and zero exactly when the four points are
+/// cocircular. Clockwise `a`, `b`, `c` flip the sign.
+pub fn incircle(a: Point, b: Point, c: Point, d: Point) -> f64 {
+    let adx = a.x() - d.x();
+    let bdx = b.x() - d.x();
+    let cdx = c.x() - d.x();
+    let ady = a.y() - d.y();
+    let bdy = b.y() - d.y();
+    let cdy = c.y() - d.y();
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+    let alift = adx * adx + ady * ady;
+
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+    let blift = bdx * bdx + bdy * bdy;
+
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdxcdy - cdxbdy)
+            + blift * (cdxady - adxcdy)
+            + clift * (adxbdy - bdxady);
+
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+                  + (cdxady.abs() + adxcdy.abs()) * blift
+                  + (adxbdy.abs() + bdxady.abs()) * clift;
+    let errbound = ICC_ERRBOUND_A * permanent;
+    if det > errbound || -det > errbound {
+        return det;
+    }
+    incircle_exact(a, b, c, d)
+}
+
+fn orient2d_exact(a: Point, b: Point, c: Point) -> f64 {
+    let [acx, acy, bcx, bcy] = [
+        Expansion::diff(a.x(), c.x()),
+        Expansion::diff(a.y(), c.y()),
+        Expansion::diff(b.x(), c.x()),
+        Expansion::diff(b.y(), c.y()),
+    ];
+    (&(&acx * &bcy) - &(&acy * &bcx)).estimate()
+}
+
+fn incircle_exact(a: Point, b: Point, c: Point, d: Point) -> f64 {
+    let [adx, ady, bdx, bdy, cdx, cdy] = [
+        Expansion::diff(a.x(), d.x()),
+        Expansion::diff(a.y(), d.y()),
+        Expansion::diff(b.x(), d.x()),
+        Expansion::diff(b.y(), d.y()),
+        Expansion::diff(c.x(), d.x()),
+        Expansion::diff(c.y(), d.y()),
+    ];
+    let alift = &(&adx * &adx) + &(&ady * &ady);
+    let blift = &(&bdx * &bdx) + &(&bdy * &bdy);
+    let clift = &(&cdx * &cdx) + &(&cdy * &cdy);
+
+    let bc = &(&bdx * &cdy) - &(&cdx * &bdy);
+    let ca = &(&cdx * &ady) - &(&adx * &cdy);
+    let ab = &(&adx * &bdy) - &(&bdx * &ady);
+
+    (&(&(&alift * &bc) + &(&blift * &ca)) + &(&clift * &ab)).estimate()
+}
+
+/// Whether `x` and `y` have strictly opposite signs, as the results of two
+/// of these predicates on either side of a line do.
+pub fn opposite_signs(x: f64, y: f64) -> bool {
+    (x > 0.0 && y < 0.0) || (x < 0.0 && y > 0.0)
+}
+
+/// Whether `p` is on the segment `a`–`b`, ends included, decided exactly.
+pub fn on_segment(a: Point, b: Point, p: Point) -> bool {
+    orient2d(a, b, p) == 0.0 && (p - a).dot(b - a) >= 0.0 && (p - b).dot(a - b) >= 0.0
+}
+
+/// Whether `p` is on the segment `a`–`b`, off its ends, decided exactly.
+pub fn strictly_on_segment(a: Point, b: Point, p: Point) -> bool {
+    orient2d(a, b, p) == 0.0 && (p - a).dot(b - a) > 0.0 && (p - b).dot(a - b) > 0.0
+}
+
+/// `a + b` as the rounded sum and its rounding error.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    (x, (a - a_virtual) + (b - b_virtual))
+}
+
+/// `a * b` as the rounded product and its rounding error.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    (x, a.mul_add(b, -x))
+}
+
+/// An exact value held as a sum of non-overlapping doubles, smallest first.
+/// Zero components are dropped, so the empty expansion is zero.
+#[derive(Debug, Clone, Default)]
+struct Expansion(Vec<f64>);
+
+impl Expansion {
+    fn diff(a: f64, b: f64) -> Self {
+        let (x, err) = two_sum(a, -b);
+        Self::default().grow(err).grow(x)
+    }
+
+    /// Adds a single double, exactly.
+    fn grow(self, b: f64) -> Self {
+        let mut q = b;
+        let mut out = Vec::with_capacity(self.0.len() + 1);
+        for e in self.0 {
+            let (sum, err) = two_sum(q, e);
+            if err != 0.0 { out.push(err); }
+            q = sum;
+        }
+        if q != 0.0 { out.push(q); }
+        Self(out)
+    }
+
+    /// An approximation of the value with the correct sign.
+    fn estimate(&self) -> f64 {
+        self.0.iter().sum()
+    }
+}
+
+impl Add for &Expansion {
+    type Output = Expansion;
+
+    fn add(self, rhs: &Expansion) -> Expansion {
+        rhs.0.iter().fold(self.clone(), |acc, &f| acc.grow(f))
+    }
+}
+
+impl Sub for &Expansion {
+    type Output = Expansion;
+
+    fn sub(self, rhs: &Expansion) -> Expansion {
+        rhs.0.iter().fold(self.clone(), |acc, &f| acc.grow(-f))
+    }
+}
+
+impl Mul for &Expansion {
+    type Output = Expansion;
+
+    fn mul(self, rhs: &Expansion) -> Expansion {
+        let mut acc = Expansion::default();
+        for &e in &self.0 {
+            for &f in &rhs.0 {
+                let (x, err) = two_product(e, f);
+                acc = acc.grow(err).grow(x);
+            }
+        }
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_orient(a: Point, b: Point, c: Point) -> f64 {
+        (a.x() - c.x()) * (b.y() - c.y()) - (a.y() - c.y()) * (b.x() - c.x())
+    }
+
+    fn sign(x: f64) -> i32 {
+        (x > 0.0) as i32 - (x < 0.0) as i32
+    }
+
+    /// `steps` ulps away from `x`.
+    fn ulps(x: f64, steps: usize) -> f64 {
+        (0..steps).fold(x, |x, _| x.next_up())
+    }
+
+    #[test]
+    fn orient_signs() {
+        let (a, b) = (Point::new(0.0, 0.0), Point::new(1.0, 0.0));
+        assert!(orient2d(a, b, Point::new(0.0, 1.0)) > 0.0);
+        assert!(orient2d(a, b, Point::new(0.0, -1.0)) < 0.0);
+        assert_eq!(orient2d(a, b, Point::new(7.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn orient_near_collinear_grid() {
+        // The classic failure case: points a few ulps off the line y = x tested
+        // against (12, 12) and (24, 24). The exact determinant is 12 * (y - x).
+        let (b, c) = (Point::new(12.0, 12.0), Point::new(24.0, 24.0));
+        let mut naive_wrong = 0;
+        for i in 0..64 {
+            for j in 0..64 {
+                let a = Point::new(ulps(0.5, i), ulps(0.5, j));
+                let expected = sign(a.y() - a.x());
+                assert_eq!(sign(orient2d(a, b, c)), expected, "at {a:?}");
+                assert_eq!(sign(orient2d(b, c, a)), expected, "at {a:?}");
+                assert_eq!(sign(orient2d(b, a, c)), -expected, "at {a:?}");
+                if sign(naive_orient(a, b, c)) != expected { naive_wrong += 1; }
+            }
+        }
+        assert!(naive_wrong > 0, "the grid should trip up the naive formula");
+    }
+
+    #[test]
+    fn orient_exactly_collinear_far_from_origin() {
+        let o = 2f64.powi(40);
+        let (a, b, c) = (Point::new(o + 1.0, o + 3.0), Point::new(o + 2.0, o + 6.0), Point::new(o + 5.0, o + 15.0));
+        assert_eq!(orient2d(a, b, c), 0.0);
+        assert!(orient2d(a, b, Point::new(o + 5.0, ulps(o + 15.0, 1))) > 0.0);
+    }
+
+    #[test]
+    fn incircle_signs() {
+        let (a, b, c) = (Point::new(1.0, 0.0), Point::new(0.0, 1.0), Point::new(-1.0, 0.0));
+        assert!(incircle(a, b, c, Point::new(0.0, 0.0)) > 0.0);
+        assert!(incircle(a, b, c, Point::new(0.0, -2.0)) < 0.0);
+        assert_eq!(incircle(a, b, c, Point::new(0.0, -1.0)), 0.0);
+        assert!(incircle(c, b, a, Point::new(0.0, 0.0)) < 0.0);
+    }
+
+    #[test]
+    fn incircle_cocircular_far_from_origin() {
+        // A 3-4-5 circle shifted far enough that the lifted coordinates round.
+        let o = 2f64.powi(30);
+        let p = |x: f64, y: f64| Point::new(o + x, o + y);
+        let (a, b, c) = (p(5.0, 0.0), p(3.0, 4.0), p(-4.0, 3.0));
+        assert!(orient2d(a, b, c) > 0.0);
+        assert_eq!(incircle(a, b, c, p(0.0, -5.0)), 0.0);
+        assert_eq!(incircle(a, b, c, p(-3.0, -4.0)), 0.0);
+
+        let inside = Point::new(o, ulps(o - 5.0, 1));
+        let outside = Point::new(o, (o - 5.0).next_down());
+        assert!(incircle(a, b, c, inside) > 0.0);
+        assert!(incircle(a, b, c, outside) < 0.0);
+        assert!(incircle(b, a, c, inside) < 0.0);
+    }
+
+    #[test]
+    fn incircle_matches_exact_on_perturbed_points() {
+        let (a, b, c) = (Point::new(1.0, 0.0), Point::new(0.0, 1.0), Point::new(-1.0, 0.0));
+        for i in 0..32 {
+            for j in 0..32 {
+                let d = Point::new(ulps(0.6, i), ulps(0.8, j));
+                assert_eq!(sign(incircle(a, b, c, d)), sign(incircle_exact(a, b, c, d)), "at {d:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn points_on_segments() {
+        let (a, b) = (Point::new(0.1, 0.1), Point::new(0.7, 0.7));
+        assert!(strictly_on_segment(a, b, Point::new(0.3, 0.3)));
+        assert!(!strictly_on_segment(a, b, a) && !strictly_on_segment(a, b, Point::new(0.9, 0.9)));
+        assert!(!strictly_on_segment(a, b, Point::new(0.3, ulps(0.3, 1))));
+        assert!(on_segment(a, b, a) && on_segment(a, b, Point::new(0.3, 0.3)));
+        assert!(!on_segment(a, b, Point::new(0.9, 0.9)));
+        assert!(opposite_signs(-0.5, 2.0) && !opposite_signs(0.0, 2.0) && !opposite_signs(-1.0, -1.0));
+    }
+
+    #[test]
+    fn expansion_arithmetic_is_exact() {
+        let big = Expansion::diff(1e30, -1.0);
+        let back = &big - &Expansion::diff(1e30, 0.0);
+        assert_eq!(back.0, vec![1.0]);
+        let sq = &Expansion::diff(1.0, -EPSILON) * &Expansion::diff(1.0, -EPSILON);
+        assert_eq!(sq.0, vec![EPSILON * EPSILON, 1.0 + 2.0 * EPSILON]);
+    }
+}
@@ -0,0 +1,231 @@
+//! A software rasteriser for the [`Renderer`] primitives, so frames can be
+//! written to PNG without a window or a GPU.
+//!
+//! Lines and circles are antialiased by their distance to the pixel center.
+//! Triangles are not, so the faces a polygon is split into don't leave seams,
+//! much like macroquad draws them without multisampling.
+
+use macroquad::prelude::*;
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+use crate::{camera::Viewport, render::Renderer};
+
+#[derive(Debug, Clone)]
+pub struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    /// How the world maps onto the pixels, one pixel per unit from the origin by default.
+    pub view: Viewport,
+}
+
+impl Raster {
+    pub fn new(width: usize, height: usize, background: Color) -> Self {
+        Self { width, height, pixels: vec![background; width * height], view: Viewport::default() }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn clear(&mut self, background: Color) {
+        self.pixels.fill(background);
+    }
+
+    /// The pixels row by row from the top, four bytes each.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|clr| {
+            [clr.r, clr.g, clr.b, clr.a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+        }).collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // frames come by the hundred, so speed beats size
+        encoder.set_compression(png::Compression::Fast);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&self.to_rgba8()).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    /// Paints `clr` over the pixels, weighted by `coverage`, source over destination.
+    fn blend(&mut self, x: usize, y: usize, clr: Color, coverage: f32) {
+        let dst = &mut self.pixels[y * self.width + x];
+        let a = clr.a * coverage;
+        let out_a = a + dst.a * (1.0 - a);
+        if out_a <= 0.0 { return; }
+        let mix = |src: f32, dst_c: f32| (src * a + dst_c * dst.a * (1.0 - a)) / out_a;
+        *dst = Color::new(mix(clr.r, dst.r), mix(clr.g, dst.g), mix(clr.b, dst.b), out_a);
+    }
+
+    /// Fills the pixels in `min..max` (in pixels) by the coverage `coverage`
+    /// gives their centers.
+    fn fill(&mut self, min: Vec2, max: Vec2, clr: Color, coverage: impl Fn(Vec2) -> f32) {
+        let clip = |v: f32, size: usize| (v.max(0.0) as usize).min(size);
+        let (x0, x1) = (clip(min.x.floor(), self.width), clip(max.x.ceil(), self.width));
+        let (y0, y1) = (clip(min.y.floor(), self.height), clip(max.y.ceil(), self.height));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let c = coverage(vec2(x as f32 + 0.5, y as f32 + 0.5));
+                if c > 0.0 {
+                    self.blend(x, y, clr, c.min(1.0));
+                }
+            }
+        }
+    }
+}
+
+/// How much of a pixel a shape covers, going by the signed distance from the
+/// pixel center to its edge.
+fn coverage(signed_distance: f32) -> f32 {
+    (0.5 - signed_distance).clamp(0.0, 1.0)
+}
+
+impl Renderer for Raster {
+    fn line(&mut self, a: Vec2, b: Vec2, thickness: f32, clr: Color) {
+        let (a, b) = (self.view.world_to_screen(a), self.view.world_to_screen(b));
+        let half = thickness * self.view.zoom / 2.0;
+        let len = a.distance(b);
+        if len == 0.0 { return; }
+        let along = (b - a) / len;
+        let across = along.perp();
+        let reach = Vec2::splat(half + 1.0);
+        // a box around the segment, cut square at the ends like draw_line's
+        self.fill(a.min(b) - reach, a.max(b) + reach, clr, |p| {
+            let d = p - a;
+            let (u, v) = (d.dot(along), d.dot(across).abs());
+            coverage((v - half).max(-u).max(u - len))
+        });
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, clr: Color) {
+        let center = self.view.world_to_screen(center);
+        let radius = radius * self.view.zoom;
+        let reach = Vec2::splat(radius + 1.0);
+        self.fill(center - reach, center + reach, clr, |p| coverage(p.distance(center) - radius));
+    }
+
+    fn triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, clr: Color) {
+        let [a, mut b, mut c] = [a, b, c].map(|v| self.view.world_to_screen(v));
+        let area = (b - a).perp_dot(c - a);
+        if area == 0.0 { return; }
+        if area < 0.0 { std::mem::swap(&mut b, &mut c); }
+        // inside is on the same side of every edge as the opposite corner.
+        // Neighbours run along their shared edge in opposite directions, so
+        // going by the direction gives pixels right on it to only one of them.
+        let owns_edge = |d: Vec2| d.y > 0.0 || (d.y == 0.0 && d.x < 0.0);
+        let inside = move |p: Vec2| [(a, b), (b, c), (c, a)].iter().all(|(u, v)| {
+            let side = (*v - *u).perp_dot(p - *u);
+            side > 0.0 || (side == 0.0 && owns_edge(*v - *u))
+        });
+        self.fill(a.min(b).min(c), a.max(b).max(c), clr, |p| if inside(p) { 1.0 } else { 0.0 });
+    }
+
+    /// Text isn't rasterised: there's no font to draw it with off screen.
+    fn text(&mut self, _text: &str, _pos: Vec2, _size: f32, _clr: Color) {}
+}
+
+/// Writes frames as a numbered PNG sequence, `frame-00000.png` onwards, for
+/// turning into a video.
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    frames: usize,
+}
+
+impl Recorder {
+    /// Records into `dir`, creating it when missing.
+    pub fn new(dir: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir: dir.as_ref().to_path_buf(), frames: 0 })
+    }
+
+    /// How many frames were written so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn save(&mut self, frame: &Raster) -> io::Result<PathBuf> {
+        let path = self.dir.join(format!("frame-{:05}.png", self.frames));
+        frame.save(&path)?;
+        self.frames += 1;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common_traits::Draw, geom::{Line2D, Polygon, Vertex}};
+
+    #[test]
+    fn rasterises_the_primitives() {
+        let mut raster = Raster::new(40, 20, BLACK);
+        raster.circle(vec2(10.0, 10.0), 5.0, RED);
+        assert_eq!(raster.pixel(10, 10), RED);
+        assert_eq!(raster.pixel(10, 16), BLACK);
+        // the rim is blended
+        let rim = raster.pixel(14, 12);
+        assert!(rim.r > 0.0 && rim.r < RED.r, "{rim:?}");
+
+        Line2D { a: Vertex::new(20.0, 5.5, Some(WHITE)), b: Vertex::new(60.0, 5.5, None), thickness: 3.0 }.render(&mut raster);
+        assert_eq!(raster.pixel(30, 5), WHITE);
+        assert_eq!(raster.pixel(30, 4), WHITE);
+        assert_eq!(raster.pixel(30, 8), BLACK);
+        assert_eq!(raster.pixel(19, 5), BLACK);
+
+        let face = GREEN.with_alpha(0.5);
+        Polygon::rectangle(euclid::vec2(20.0, 10.0), euclid::vec2(30.0, 20.0), BLANK, face).render(&mut raster);
+        let half_green = raster.pixel(25, 15);
+        assert_eq!(half_green.a, 1.0);
+        assert!((half_green.g - GREEN.g / 2.0).abs() < 1e-6);
+        // the diagonal the rectangle is split along doesn't show
+        assert_eq!(raster.pixel(24, 14), half_green);
+        assert_eq!(raster.pixel(31, 15), BLACK);
+    }
+
+    #[test]
+    fn the_view_scales_and_moves_the_drawing() {
+        let mut raster = Raster::new(20, 20, BLANK);
+        raster.view.zoom = 2.0;
+        raster.view.origin = vec2(-5.0, 0.0);
+        raster.circle(vec2(0.0, 5.0), 2.0, BLUE);
+        assert_eq!(raster.pixel(10, 10), BLUE);
+        assert_eq!(raster.pixel(12, 10), BLUE);
+        assert_eq!(raster.pixel(15, 10), BLANK);
+    }
+
+    #[test]
+    fn records_numbered_pngs() {
+        let dir = std::env::temp_dir().join(format!("raster-test-{}", std::process::id()));
+        let mut recorder = Recorder::new(&dir).unwrap();
+        let mut raster = Raster::new(3, 2, WHITE);
+        recorder.save(&raster).unwrap();
+        raster.circle(vec2(0.5, 0.5), 1.0, RED);
+        let path = recorder.save(&raster).unwrap();
+        assert_eq!(path, dir.join("frame-00001.png"));
+        assert_eq!(recorder.frames(), 2);
+
+        let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+        let mut bytes = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut bytes).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&bytes[..info.buffer_size()], &raster.to_rgba8()[..]);
+        assert!(dir.join("frame-00000.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
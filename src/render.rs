@@ -0,0 +1,179 @@
+//! Where [`Draw`](crate::common_traits::Draw) implementations put their
+//! shapes: the [`Screen`] through macroquad's immediate mode, or an [`Svg`]
+//! document for figures that outlive the window.
+
+use euclid::default::{Box2D, Point2D};
+use macroquad::prelude::*;
+use std::fmt::Write as _;
+use crate::scene_file::HexColor;
+
+/// Where `--svg` and the export key write when no path is given.
+pub const DEFAULT_SVG_PATH: &str = "./scene.svg";
+/// The part of the drawing's size left free around it by [`Svg::document`].
+pub const SVG_MARGIN: f32 = 0.05;
+
+/// The primitives every [`Draw`](crate::common_traits::Draw) is made of.
+pub trait Renderer {
+    fn line(&mut self, a: Vec2, b: Vec2, thickness: f32, clr: Color);
+    fn circle(&mut self, center: Vec2, radius: f32, clr: Color);
+    fn triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, clr: Color);
+    /// `pos` is the left end of the baseline, like macroquad's `draw_text`.
+    fn text(&mut self, text: &str, pos: Vec2, size: f32, clr: Color);
+
+    fn rectangle(&mut self, topleft: Vec2, size: Vec2, clr: Color) {
+        let botright = topleft + size;
+        self.triangle(topleft, vec2(botright.x, topleft.y), botright, clr);
+        self.triangle(topleft, botright, vec2(topleft.x, botright.y), clr);
+    }
+}
+
+/// Draws straight to the window, through the active camera.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Screen;
+
+impl Renderer for Screen {
+    fn line(&mut self, a: Vec2, b: Vec2, thickness: f32, clr: Color) {
+        draw_line(a.x, a.y, b.x, b.y, thickness, clr);
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, clr: Color) {
+        draw_circle(center.x, center.y, radius, clr);
+    }
+
+    fn triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, clr: Color) {
+        draw_triangle(a, b, c, clr);
+    }
+
+    fn text(&mut self, text: &str, pos: Vec2, size: f32, clr: Color) {
+        draw_text(text, pos.x, pos.y, size, clr);
+    }
+
+    fn rectangle(&mut self, topleft: Vec2, size: Vec2, clr: Color) {
+        draw_rectangle(topleft.x, topleft.y, size.x, size.y, clr);
+    }
+}
+
+/// Collects the primitives as SVG elements, in world coordinates, and sizes
+/// the document to fit them.
+#[derive(Debug, Clone, Default)]
+pub struct Svg {
+    body: String,
+    bounds: Option<Box2D<f32>>,
+    background: Option<Color>,
+}
+
+impl Svg {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fills the whole document with `clr` behind the drawing, like `clear_background`.
+    pub fn with_background(clr: Color) -> Self {
+        Self { background: Some(clr), ..Self::default() }
+    }
+
+    /// The union of everything rendered so far, `None` while empty.
+    pub fn bounds(&self) -> Option<Box2D<f32>> {
+        self.bounds
+    }
+
+    fn grow(&mut self, min: Vec2, max: Vec2) {
+        let aabb = Box2D::new(Point2D::new(min.x, min.y), Point2D::new(max.x, max.y));
+        self.bounds = Some(self.bounds.map_or(aabb, |b| b.union(&aabb)));
+    }
+
+    /// The finished document, its view box fitted around the drawing and
+    /// rounded out to whole units.
+    pub fn document(&self) -> String {
+        let aabb = self.bounds.unwrap_or_else(Box2D::zero);
+        let margin = aabb.size().to_vector().to_array().map(|extent| (extent * SVG_MARGIN).max(1.0));
+        let aabb = aabb.inflate(margin[0], margin[1]).round_out();
+        let (x, y, w, h) = (aabb.min.x, aabb.min.y, aabb.width(), aabb.height());
+        let mut doc = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{x} {y} {w} {h}\" width=\"{w}\" height=\"{h}\">\n");
+        if let Some(clr) = self.background {
+            writeln!(doc, "<rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" {}/>", paint("fill", clr)).unwrap();
+        }
+        doc.push_str(&self.body);
+        doc.push_str("</svg>\n");
+        doc
+    }
+
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.document())
+    }
+}
+
+/// `attr="#rrggbb"`, plus an opacity attribute when `clr` isn't opaque.
+fn paint(attr: &str, clr: Color) -> String {
+    // #rrggbbaa, but not every SVG reader takes the alpha digits
+    let hex = HexColor(clr).to_string();
+    let mut paint = format!("{attr}=\"{}\"", &hex[..7]);
+    if &hex[7..] != "ff" {
+        write!(paint, " {attr}-opacity=\"{}\"", clr.a).unwrap();
+    }
+    paint
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+impl Renderer for Svg {
+    fn line(&mut self, a: Vec2, b: Vec2, thickness: f32, clr: Color) {
+        let half = Vec2::splat(thickness / 2.0);
+        self.grow(a.min(b) - half, a.max(b) + half);
+        writeln!(self.body, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke-width=\"{thickness}\" {}/>",
+                 a.x, a.y, b.x, b.y, paint("stroke", clr)).unwrap();
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, clr: Color) {
+        self.grow(center - Vec2::splat(radius), center + Vec2::splat(radius));
+        writeln!(self.body, "<circle cx=\"{}\" cy=\"{}\" r=\"{radius}\" {}/>",
+                 center.x, center.y, paint("fill", clr)).unwrap();
+    }
+
+    fn triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, clr: Color) {
+        self.grow(a.min(b).min(c), a.max(b).max(c));
+        writeln!(self.body, "<polygon points=\"{},{} {},{} {},{}\" {}/>",
+                 a.x, a.y, b.x, b.y, c.x, c.y, paint("fill", clr)).unwrap();
+    }
+
+    fn text(&mut self, text: &str, pos: Vec2, size: f32, clr: Color) {
+        // a rough extent, glyphs being about half as wide as they're tall
+        self.grow(pos - vec2(0.0, size), pos + vec2(text.chars().count() as f32 * size / 2.0, 0.0));
+        writeln!(self.body, "<text x=\"{}\" y=\"{}\" font-size=\"{size}\" {}>{}</text>",
+                 pos.x, pos.y, paint("fill", clr), escape(text)).unwrap();
+    }
+
+    fn rectangle(&mut self, topleft: Vec2, size: Vec2, clr: Color) {
+        self.grow(topleft.min(topleft + size), topleft.max(topleft + size));
+        writeln!(self.body, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+                 topleft.x, topleft.y, size.x, size.y, paint("fill", clr)).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common_traits::Draw, geom::{Line2D, Polygon, Vertex}};
+
+    #[test]
+    fn svg_keeps_colours_and_thickness() {
+        let mut svg = Svg::with_background(BLACK);
+        Line2D { a: Vertex::new(0.0, 0.0, Some(RED)), b: Vertex::new(100.0, 0.0, None), thickness: 4.0 }.render(&mut svg);
+        Polygon::rectangle(euclid::vec2(10.0, 10.0), euclid::vec2(50.0, 30.0), BLUE, GREEN.with_alpha(0.5)).render(&mut svg);
+        svg.text("a < b", vec2(0.0, 80.0), 10.0, WHITE);
+
+        assert_eq!(svg.bounds(), Some(Box2D::new(Point2D::new(-2.0, -2.0), Point2D::new(102.0, 80.0))));
+        let doc = svg.document();
+        assert!(doc.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-8 -7 116 92\" width=\"116\" height=\"92\">\n"));
+        assert!(doc.contains("<rect x=\"-8\" y=\"-7\" width=\"116\" height=\"92\" fill=\"#000000\"/>"));
+        assert!(doc.contains("<line x1=\"0\" y1=\"0\" x2=\"100\" y2=\"0\" stroke-width=\"4\" stroke=\"#e62938\"/>"));
+        assert_eq!(doc.matches("stroke=\"#0078f2\"").count(), 4);
+        assert_eq!(doc.matches("<polygon points=").count(), 2);
+        assert!(doc.contains("fill=\"#00e330\" fill-opacity=\"0.5\""));
+        assert!(doc.contains(">a &lt; b</text>"));
+        assert!(doc.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,584 @@
+//! The scene model shared by the viewers: objects kept in a [`GenMap`],
+//! addressed by [`Handle`] and grouped into layers that can be hidden or made
+//! unpickable independently. A [`BucketGrid`] over the objects' bounding
+//! boxes keeps picking and nearest queries from visiting every object.
+
+use std::{cell::RefCell, collections::HashSet};
+use euclid::default::{Box2D, Vector2D};
+use genmap::{GenMap, Handle};
+use crate::{
+    common_traits::*,
+    geom::{self, Vertex},
+    hud::Metrics,
+    render::{Renderer, Screen},
+    spatial::BucketGrid,
+};
+
+/// Cell size of the scene's spatial index, in pixels.
+pub const INDEX_CELL_SIZE: f32 = 32.0;
+
+/// The plain shapes every viewer can hold.
+#[derive(Debug, Clone)]
+pub enum Object {
+    Point(geom::Vertex),
+    CircleObj(geom::Circle),
+    LineObj(geom::Line2D),
+    PolyObj(geom::Polygon),
+}
+
+impl Draw for Object {
+    fn render(&self, target: &mut dyn Renderer) {
+        match self {
+            Object::Point(p) => p.render(target),
+            Object::CircleObj(c) => c.render(target),
+            Object::LineObj(l) => l.render(target),
+            Object::PolyObj(p) => p.render(target),
+        }
+    }
+    fn vertices(&self) -> Vec<Vertex> {
+        match self {
+            Object::Point(p) => p.vertices(),
+            Object::CircleObj(c) => c.vertices(),
+            Object::LineObj(l) => l.vertices(),
+            Object::PolyObj(p) => p.vertices(),
+        }
+    }
+}
+
+impl Select for Object {
+    fn compute_aabb(&self) -> Box2D<f32> {
+        match self {
+            Object::Point(p) => p.compute_aabb(),
+            Object::CircleObj(c) => c.compute_aabb(),
+            Object::LineObj(l) => l.compute_aabb(),
+            Object::PolyObj(p) => p.compute_aabb(),
+        }
+    }
+    fn sample_signed_distance_field(&self, global_sample_point: &Vector2D<f32>) -> f32 {
+        match self {
+            Object::Point(p) => p.sample_signed_distance_field(global_sample_point),
+            Object::CircleObj(c) => c.sample_signed_distance_field(global_sample_point),
+            Object::LineObj(l) => l.sample_signed_distance_field(global_sample_point),
+            Object::PolyObj(p) => p.sample_signed_distance_field(global_sample_point),
+        }
+    }
+}
+
+/// What a [`Scene`] needs from the objects it holds. Viewers with objects of
+/// their own wrap [`Object`] in a bigger enum and implement this for it.
+pub trait SceneObject: Element {
+    /// The plain shape inside this object, if it is one.
+    fn shape(&self) -> Option<&Object>;
+    fn shape_mut(&mut self) -> Option<&mut Object>;
+}
+
+impl SceneObject for Object {
+    fn shape(&self) -> Option<&Object> { Some(self) }
+    fn shape_mut(&mut self) -> Option<&mut Object> { Some(self) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayerId(usize);
+
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    /// Hidden layers are skipped by [`Scene::visible`] and [`Scene::draw`].
+    pub visible: bool,
+    /// Only objects on pickable layers are offered by [`Scene::elements`] and [`Scene::pick`].
+    pub pickable: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Entry<O> {
+    object: O,
+    layer: LayerId,
+    // insertion order, which decides between overlapping objects when picking
+    seq: usize,
+}
+
+/// The spatial index and the objects whose boxes it doesn't know yet.
+#[derive(Debug, Clone)]
+struct Index {
+    grid: BucketGrid,
+    stale: HashSet<Handle>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scene<O = Object> {
+    objects: GenMap<Entry<O>>,
+    layers: Vec<Layer>,
+    next_seq: usize,
+    // refreshed lazily by the queries, since new and changed objects need
+    // `Select` to be filed and the basic accessors don't require it
+    index: RefCell<Index>,
+}
+
+impl<O> Default for Scene<O> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<O> Scene<O> {
+    /// Every scene starts with a visible, pickable layer called "default".
+    pub const DEFAULT_LAYER: LayerId = LayerId(0);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            objects: GenMap::with_capacity(capacity),
+            layers: vec![Layer { name: "default".to_string(), visible: true, pickable: true }],
+            next_seq: 0,
+            index: RefCell::new(Index { grid: BucketGrid::new(INDEX_CELL_SIZE), stale: HashSet::new() }),
+        }
+    }
+
+    pub fn add_layer(&mut self, name: &str) -> LayerId {
+        self.layers.push(Layer { name: name.to_string(), visible: true, pickable: true });
+        LayerId(self.layers.len() - 1)
+    }
+
+    pub fn layer(&self, id: LayerId) -> &Layer {
+        &self.layers[id.0]
+    }
+
+    pub fn layer_mut(&mut self, id: LayerId) -> &mut Layer {
+        &mut self.layers[id.0]
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = (LayerId, &Layer)> {
+        self.layers.iter().enumerate().map(|(i, l)| (LayerId(i), l))
+    }
+
+    pub fn insert(&mut self, object: O) -> Handle {
+        self.insert_on(Self::DEFAULT_LAYER, object)
+    }
+
+    pub fn insert_on(&mut self, layer: LayerId, object: O) -> Handle {
+        assert!(layer.0 < self.layers.len(), "no such layer {layer:?}");
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let handle = self.objects.insert(Entry { object, layer, seq });
+        self.index.get_mut().stale.insert(handle);
+        handle
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<O> {
+        let index = self.index.get_mut();
+        index.stale.remove(&handle);
+        index.grid.remove(handle);
+        self.objects.remove(handle).map(|e| e.object)
+    }
+
+    /// Removes every object on `layer`, keeping the layer itself.
+    pub fn clear_layer(&mut self, layer: LayerId) {
+        let on_layer: Vec<_> = self.objects.iter().filter(|h| self.layer_of(*h) == Some(layer)).collect();
+        for handle in on_layer {
+            self.remove(handle);
+        }
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&O> {
+        self.objects.get(handle).map(|e| &e.object)
+    }
+
+    /// The object behind `handle`, which gets re-indexed before the next query.
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut O> {
+        let entry = self.objects.get_mut(handle)?;
+        self.index.get_mut().stale.insert(handle);
+        Some(&mut entry.object)
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.objects.get(handle).is_some()
+    }
+
+    pub fn layer_of(&self, handle: Handle) -> Option<LayerId> {
+        self.objects.get(handle).map(|e| e.layer)
+    }
+
+    pub fn set_layer(&mut self, handle: Handle, layer: LayerId) {
+        assert!(layer.0 < self.layers.len(), "no such layer {layer:?}");
+        if let Some(entry) = self.objects.get_mut(handle) {
+            entry.layer = layer;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every object's handle, in insertion order. The slots of removed
+    /// objects get reused, so that isn't the order they're stored in.
+    pub fn handles(&self) -> impl Iterator<Item = Handle> + '_ {
+        let mut handles: Vec<Handle> = self.objects.iter().collect();
+        handles.sort_by_key(|h| self.objects.get(*h).unwrap().seq);
+        handles.into_iter()
+    }
+
+    /// Every object, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &O)> {
+        self.handles().map(|h| (h, &self.objects.get(h).unwrap().object))
+    }
+
+    /// Objects on visible layers, in insertion order.
+    pub fn visible(&self) -> impl Iterator<Item = (Handle, &O)> {
+        self.handles()
+            .map(|h| (h, self.objects.get(h).unwrap()))
+            .filter(|(_, e)| self.layers[e.layer.0].visible)
+            .map(|(h, e)| (h, &e.object))
+    }
+}
+
+impl<O: SceneObject> Scene<O> {
+    pub fn draw(&self) {
+        self.render(&mut Screen);
+    }
+
+    /// Renders the objects on visible layers, in insertion order.
+    pub fn render(&self, target: &mut dyn Renderer) {
+        for (_, object) in self.visible() {
+            object.render(target);
+        }
+    }
+
+    /// The objects on pickable layers, in insertion order.
+    pub fn elements(&self) -> impl Iterator<Item = (Handle, &dyn Element)> {
+        self.handles()
+            .map(|h| (h, self.objects.get(h).unwrap()))
+            .filter(|(_, e)| self.layers[e.layer.0].pickable)
+            .map(|(h, e)| (h, &e.object as &dyn Element))
+    }
+
+    /// The union of the visible objects' bounding boxes, leaving out unbounded ones.
+    pub fn bounds(&self) -> Option<Box2D<f32>> {
+        self.visible()
+            .map(|(_, o)| o.compute_aabb())
+            .filter(|b| [b.min.x, b.min.y, b.max.x, b.max.y].iter().all(|v| v.is_finite()))
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// The pickable object under `point` that was inserted first.
+    pub fn pick(&self, point: &Vector2D<f32>) -> Option<Handle> {
+        self.hits(point).into_iter().next()
+    }
+
+    /// Every pickable object under `point`, in insertion order.
+    pub fn hits(&self, point: &Vector2D<f32>) -> Vec<Handle> {
+        let mut hits: Vec<_> = self.query_region(&Box2D::new(point.to_point(), point.to_point()))
+            .into_iter()
+            .filter(|h| self.objects.get(*h).unwrap().object.contains_point(point))
+            .collect();
+        hits.sort_by_key(|h| self.objects.get(*h).unwrap().seq);
+        hits
+    }
+
+    /// The pickable objects whose bounding boxes touch `region`.
+    pub fn query_region(&self, region: &Box2D<f32>) -> Vec<Handle> {
+        self.refresh_index();
+        self.index.borrow().grid.query_region(region).into_iter()
+            .filter(|h| self.is_pickable(*h))
+            .collect()
+    }
+
+    /// Up to `k` pickable objects nearest to `point` by their signed distance, nearest first.
+    pub fn nearest(&self, point: &Vector2D<f32>, k: usize) -> Vec<(Handle, f32)> {
+        self.refresh_index();
+        self.index.borrow().grid.nearest_by(point, k, |h| {
+            self.is_pickable(h).then(|| self.objects.get(h).unwrap().object.sample_signed_distance_field(point))
+        })
+    }
+
+    fn is_pickable(&self, handle: Handle) -> bool {
+        self.objects.get(handle).is_some_and(|e| self.layers[e.layer.0].pickable)
+    }
+
+    /// Files the objects added or handed out mutably since the last query.
+    fn refresh_index(&self) {
+        let mut index = self.index.borrow_mut();
+        let Index { grid, stale } = &mut *index;
+        for handle in stale.drain() {
+            if let Some(entry) = self.objects.get(handle) {
+                grid.insert(handle, entry.object.compute_aabb());
+            }
+        }
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = (Handle, &Object)> {
+        self.iter().flat_map(|(h, o)| o.shape().map(|s| (h, s)))
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = (Handle, &geom::Vertex)> {
+        self.shapes().flat_map(|(h, s)| if let Object::Point(p) = s { Some((h, p)) } else { None })
+    }
+
+    pub fn circles(&self) -> impl Iterator<Item = (Handle, &geom::Circle)> {
+        self.shapes().flat_map(|(h, s)| if let Object::CircleObj(c) = s { Some((h, c)) } else { None })
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = (Handle, &geom::Line2D)> {
+        self.shapes().flat_map(|(h, s)| if let Object::LineObj(l) = s { Some((h, l)) } else { None })
+    }
+
+    pub fn polys(&self) -> impl Iterator<Item = (Handle, &geom::Polygon)> {
+        self.shapes().flat_map(|(h, s)| if let Object::PolyObj(p) = s { Some((h, p)) } else { None })
+    }
+
+    /// A summary of the scene contents, one `name: value` pair per line.
+    pub fn text_digest(&self) -> String {
+        self.metrics().into_iter().map(|(name, value)| format!("{name}: {value}\n")).collect()
+    }
+}
+
+impl<O: SceneObject> Metrics for Scene<O> {
+    fn metrics(&self) -> Vec<(String, String)> {
+        let line_cnt = self.lines().count();
+        let circle_cnt = self.circles().count();
+        let poly_cnt = self.polys().count();
+        let vertex_cnt = self.iter().map(|(_, o)| o.vertices().len()).sum::<usize>();
+        let layers = self.layers().map(|(id, layer)| {
+            let count = self.objects.iter().filter(|h| self.objects.get(*h).unwrap().layer == id).count();
+            let flags = match (layer.visible, layer.pickable) {
+                (true, true) => "",
+                (true, false) => ", unpickable",
+                (false, true) => ", hidden",
+                (false, false) => ", hidden, unpickable",
+            };
+            format!("{} ({count}{flags})", layer.name)
+        }).collect::<Vec<_>>().join(", ");
+        vec![
+            ("num. of lines".to_string(), line_cnt.to_string()),
+            ("num. of circles".to_string(), circle_cnt.to_string()),
+            ("num. of polygons".to_string(), poly_cnt.to_string()),
+            ("num. of vertices".to_string(), vertex_cnt.to_string()),
+            ("layers".to_string(), layers),
+        ]
+    }
+}
+
+impl<O: From<Object>> Scene<O> {
+    pub fn add_point(&mut self, v: geom::Vertex) -> Handle {
+        self.insert(Object::Point(v).into())
+    }
+    pub fn add_line(&mut self, l: geom::Line2D) -> Handle {
+        self.insert(Object::LineObj(l).into())
+    }
+    pub fn add_circle(&mut self, c: geom::Circle) -> Handle {
+        self.insert(Object::CircleObj(c).into())
+    }
+    pub fn add_poly(&mut self, p: geom::Polygon) -> Handle {
+        self.insert(Object::PolyObj(p).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, y: f32, radius: f32) -> geom::Circle {
+        geom::Circle { center: Vertex::new(x, y, None), radius }
+    }
+
+    fn line(a: (f32, f32), b: (f32, f32)) -> geom::Line2D {
+        geom::Line2D { a: Vertex::new(a.0, a.1, None), b: Vertex::new(b.0, b.1, None), thickness: 1.0 }
+    }
+
+    #[test]
+    fn handles_outlive_removals() {
+        let mut scene = Scene::<Object>::new();
+        let a = scene.add_circle(circle(0.0, 0.0, 1.0));
+        let b = scene.add_line(line((0.0, 0.0), (1.0, 1.0)));
+        assert_eq!(scene.len(), 2);
+        assert!(scene.remove(a).is_some());
+        assert!(scene.get(a).is_none());
+        assert!(scene.remove(a).is_none());
+        let c = scene.add_circle(circle(5.0, 5.0, 1.0));
+        assert_ne!(a, c);
+        assert!(scene.get(a).is_none());
+        assert!(matches!(scene.get(b), Some(Object::LineObj(_))));
+        assert_eq!(scene.handles().collect::<Vec<_>>().len(), 2);
+    }
+
+    #[test]
+    fn renders_in_insertion_order_after_removals() {
+        let mut scene = Scene::<Object>::new();
+        let a = scene.add_circle(circle(0.0, 0.0, 1.0));
+        scene.add_circle(circle(10.0, 0.0, 1.0));
+        scene.remove(a);
+        // goes into the slot a left
+        let c = scene.add_circle(circle(20.0, 0.0, 1.0));
+        assert_eq!(scene.handles().last(), Some(c));
+
+        let mut svg = crate::render::Svg::new();
+        scene.render(&mut svg);
+        let document = svg.document();
+        let at = |x: &str| document.find(&format!("cx=\"{x}\"")).unwrap_or_else(|| panic!("no circle at {x} in {document}"));
+        assert!(at("10") < at("20"));
+        assert!(crate::scene_file::SceneFile::from_scene(&scene).to_text().starts_with("circle 10 0 1\ncircle 20 0 1\n"));
+    }
+
+    #[test]
+    fn per_type_iteration() {
+        let mut scene = Scene::<Object>::new();
+        scene.add_circle(circle(0.0, 0.0, 1.0));
+        scene.add_circle(circle(1.0, 0.0, 1.0));
+        scene.add_line(line((0.0, 0.0), (1.0, 1.0)));
+        scene.add_poly(geom::Polygon::circle(5, 1.0, macroquad::color::WHITE));
+        scene.add_point(Vertex::new(3.0, 3.0, None));
+        assert_eq!(scene.circles().count(), 2);
+        assert_eq!(scene.lines().count(), 1);
+        assert_eq!(scene.polys().map(|(_, p)| p.verts.len()).sum::<usize>(), 5);
+        assert_eq!(scene.points().count(), 1);
+    }
+
+    #[test]
+    fn layers_control_visibility_and_picking() {
+        let mut scene = Scene::<Object>::new();
+        let overlay = scene.add_layer("overlay");
+        let under = scene.add_circle(circle(0.0, 0.0, 10.0));
+        let over = scene.insert_on(overlay, Object::CircleObj(circle(0.0, 0.0, 5.0)));
+        let origin = Vector2D::new(0.0, 0.0);
+
+        assert_eq!(scene.pick(&origin), Some(under));
+        scene.layer_mut(Scene::<Object>::DEFAULT_LAYER).pickable = false;
+        assert_eq!(scene.pick(&origin), Some(over));
+        assert_eq!(scene.visible().count(), 2);
+
+        scene.layer_mut(overlay).visible = false;
+        assert_eq!(scene.visible().map(|(h, _)| h).collect::<Vec<_>>(), vec![under]);
+
+        scene.set_layer(over, Scene::<Object>::DEFAULT_LAYER);
+        assert_eq!(scene.layer_of(over), Some(Scene::<Object>::DEFAULT_LAYER));
+        assert_eq!(scene.pick(&origin), None);
+
+        let kept = scene.insert_on(overlay, Object::CircleObj(circle(0.0, 0.0, 1.0)));
+        scene.clear_layer(Scene::<Object>::DEFAULT_LAYER);
+        assert_eq!(scene.handles().collect::<Vec<_>>(), vec![kept]);
+    }
+
+    #[test]
+    fn every_shape_is_pickable() {
+        let mut scene = Scene::<Object>::new();
+        let point = scene.add_point(Vertex::new(50.0, 50.0, None));
+        let rect = scene.add_poly(geom::Polygon::rectangle(Vector2D::new(0.0, 0.0), Vector2D::new(10.0, 10.0),
+                                                           macroquad::color::WHITE, macroquad::color::WHITE));
+        assert_eq!(scene.pick(&Vector2D::new(50.5, 50.0)), Some(point));
+        assert_eq!(scene.pick(&Vector2D::new(5.0, 5.0)), Some(rect));
+        assert_eq!(scene.pick(&Vector2D::new(30.0, 30.0)), None);
+    }
+
+    #[test]
+    fn index_follows_changes() {
+        let mut scene = Scene::<Object>::new();
+        let a = scene.add_circle(circle(0.0, 0.0, 2.0));
+        let b = scene.add_circle(circle(1.0, 0.0, 2.0));
+        let far = scene.add_circle(circle(500.0, 500.0, 2.0));
+        assert_eq!(scene.hits(&Vector2D::new(0.5, 0.0)), vec![a, b]);
+        assert_eq!(scene.pick(&Vector2D::new(0.5, 0.0)), Some(a));
+
+        if let Some(Object::CircleObj(c)) = scene.get_mut(far) {
+            c.center.pos = Vector2D::new(0.0, 0.0);
+        }
+        scene.remove(a);
+        assert_eq!(scene.hits(&Vector2D::new(0.5, 0.0)), vec![b, far]);
+        assert_eq!(scene.pick(&Vector2D::new(500.0, 500.0)), None);
+    }
+
+    #[test]
+    fn nearest_objects() {
+        let mut scene = Scene::<Object>::new();
+        let hidden = scene.add_layer("hidden");
+        scene.layer_mut(hidden).pickable = false;
+        let near = scene.add_circle(circle(10.0, 0.0, 1.0));
+        let mid = scene.add_line(line((0.0, 40.0), (100.0, 40.0)));
+        scene.add_circle(circle(300.0, 0.0, 1.0));
+        scene.insert_on(hidden, Object::CircleObj(circle(0.0, 0.0, 1.0)));
+        let found = scene.nearest(&Vector2D::new(0.0, 0.0), 2);
+        assert_eq!(found, vec![(near, 9.0), (mid, 39.0)]);
+        assert_eq!(scene.query_region(&Box2D::new((-5.0, -5.0).into(), (12.0, 5.0).into())), vec![near]);
+    }
+
+    #[test]
+    fn bounds_cover_visible_objects() {
+        let mut scene = Scene::<Object>::new();
+        assert_eq!(scene.bounds(), None);
+        let hidden = scene.add_layer("hidden");
+        scene.layer_mut(hidden).visible = false;
+        scene.add_circle(circle(0.0, 0.0, 1.0));
+        scene.add_circle(circle(10.0, 5.0, 1.0));
+        scene.insert_on(hidden, Object::CircleObj(circle(100.0, 100.0, 1.0)));
+        assert_eq!(scene.bounds(), Some(Box2D::new((-1.0, -1.0).into(), (11.0, 6.0).into())));
+    }
+
+    #[test]
+    fn digest_counts() {
+        let mut scene = Scene::<Object>::new();
+        let hidden = scene.add_layer("hull");
+        scene.layer_mut(hidden).visible = false;
+        scene.add_line(line((0.0, 0.0), (1.0, 1.0)));
+        scene.add_circle(circle(0.0, 0.0, 1.0));
+        scene.insert_on(hidden, Object::PolyObj(geom::Polygon::circle(4, 1.0, macroquad::color::WHITE)));
+        assert_eq!(scene.text_digest(), "num. of lines: 1
+num. of circles: 1
+num. of polygons: 1
+num. of vertices: 7
+layers: default (2), hull (1, hidden)
+");
+    }
+
+    /// A viewer-specific object wrapping the shared shapes.
+    #[derive(Debug)]
+    enum Custom {
+        Shape(Object),
+        Marker,
+    }
+
+    impl From<Object> for Custom {
+        fn from(o: Object) -> Self { Custom::Shape(o) }
+    }
+
+    impl Draw for Custom {
+        fn render(&self, _: &mut dyn Renderer) {}
+        fn vertices(&self) -> Vec<Vertex> {
+            match self { Custom::Shape(s) => s.vertices(), Custom::Marker => vec![] }
+        }
+    }
+
+    impl Select for Custom {
+        fn compute_aabb(&self) -> Box2D<f32> {
+            match self { Custom::Shape(s) => s.compute_aabb(), Custom::Marker => Box2D::zero() }
+        }
+        fn sample_signed_distance_field(&self, p: &Vector2D<f32>) -> f32 {
+            match self { Custom::Shape(s) => s.sample_signed_distance_field(p), Custom::Marker => f32::INFINITY }
+        }
+    }
+
+    impl SceneObject for Custom {
+        fn shape(&self) -> Option<&Object> {
+            match self { Custom::Shape(s) => Some(s), Custom::Marker => None }
+        }
+        fn shape_mut(&mut self) -> Option<&mut Object> {
+            match self { Custom::Shape(s) => Some(s), Custom::Marker => None }
+        }
+    }
+
+    #[test]
+    fn wrapped_objects() {
+        let mut scene = Scene::<Custom>::new();
+        scene.add_circle(circle(0.0, 0.0, 1.0));
+        let marker = scene.insert(Custom::Marker);
+        assert_eq!(scene.circles().count(), 1);
+        assert_eq!(scene.shapes().count(), 1);
+        assert_eq!(scene.elements().count(), 2);
+        assert_eq!(scene.pick(&Vector2D::new(0.0, 0.0)), scene.circles().next().map(|(h, _)| h));
+        assert!(!scene.get(marker).unwrap().contains_point(&Vector2D::new(0.0, 0.0)));
+    }
+}
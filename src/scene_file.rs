@@ -0,0 +1,522 @@
+//! Saving and loading scenes. A [`SceneFile`] holds the plain shapes of a
+//! scene and, for the grid viewer, the grid with its obstacles, bots and goal.
+//!
+//! Files ending in `.json` are JSON, anything else uses a compact text form
+//! with one item per line, `#` starting a comment line:
+//!
+//! ```text
+//! point x y [#clr]
+//! line x y [#clr] x y [#clr] thickness
+//! circle x y [#clr] radius
+//! poly edge_thickness
+//! v x y [#clr]
+//! e a b #clr
+//! f a b c #clr
+//! grid square|hex cols rows left top right bottom
+//! obstacle col row
+//! bot col row col row
+//! goal col row
+//! ```
+//!
+//! A `poly` line is followed by the `v`, `e` and `f` lines of its vertices,
+//! edges and faces, and a `bot` gives its origin, then its destination.
+//! Colours are written as `#rrggbbaa`, `#rrggbb` reads as opaque.
+
+use std::{fmt, fs, io, path::Path, str::FromStr};
+use genmap::Handle;
+use macroquad::color::Color;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::{
+    geom,
+    scene::{Object, Scene, SceneObject},
+};
+
+/// Where the viewers save to when no `--scene` file was given.
+pub const DEFAULT_PATH: &str = "./scene.txt";
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// A line of the text form that couldn't be read, numbered from 1.
+    Syntax { line: usize, msg: String },
+    /// Well formed but inconsistent contents, like a face using a missing vertex.
+    Invalid(String),
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneFileError::Io(e) => write!(f, "{e}"),
+            SceneFileError::Json(e) => write!(f, "{e}"),
+            SceneFileError::Syntax { line, msg } => write!(f, "line {line}: {msg}"),
+            SceneFileError::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+impl From<io::Error> for SceneFileError {
+    fn from(e: io::Error) -> Self {
+        SceneFileError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SceneFileError {
+    fn from(e: serde_json::Error) -> Self {
+        SceneFileError::Json(e)
+    }
+}
+
+/// A colour stored as `#rrggbbaa`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HexColor(pub Color);
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = [self.0.r, self.0.g, self.0.b, self.0.a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        write!(f, "#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+}
+
+impl FromStr for HexColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix('#').ok_or_else(|| format!("colour {s:?} doesn't start with '#'"))?;
+        if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+            return Err(format!("colour {s:?} isn't #rrggbb or #rrggbbaa"));
+        }
+        let channel = |i: usize| u8::from_str_radix(digits.get(2 * i..2 * i + 2).unwrap_or("ff"), 16)
+            .map(|c| c as f32 / 255.0)
+            .map_err(|_| format!("colour {s:?} has a bad digit"));
+        Ok(HexColor(Color::new(channel(0)?, channel(1)?, channel(2)?, channel(3)?)))
+    }
+}
+
+impl Serialize for HexColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for HexColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VertexSpec {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clr: Option<HexColor>,
+}
+
+/// One of the shapes of [`Object`], as stored on disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Shape {
+    Point(VertexSpec),
+    Line { a: VertexSpec, b: VertexSpec, thickness: f32 },
+    Circle { center: VertexSpec, radius: f32 },
+    Polygon {
+        verts: Vec<VertexSpec>,
+        #[serde(default)]
+        edges: Vec<(usize, usize, HexColor)>,
+        edge_thickness: f32,
+        #[serde(default)]
+        faces: Vec<(usize, usize, usize, HexColor)>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GridKind {
+    Square,
+    Hex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotSpec {
+    pub origin: (usize, usize),
+    pub dest: (usize, usize),
+}
+
+/// A discrete grid and what's on it, cells given as `(col, row)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridSpec {
+    pub kind: GridKind,
+    pub size: (usize, usize),
+    pub topleft: (f64, f64),
+    pub botright: (f64, f64),
+    #[serde(default)]
+    pub obstacles: Vec<(usize, usize)>,
+    #[serde(default)]
+    pub bots: Vec<BotSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneFile {
+    #[serde(default)]
+    pub shapes: Vec<Shape>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grid: Option<GridSpec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Text,
+}
+
+impl Format {
+    /// JSON for `.json` files, the text form for everything else.
+    pub fn of(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Format::Json,
+            _ => Format::Text,
+        }
+    }
+}
+
+impl From<&geom::Vertex> for VertexSpec {
+    fn from(v: &geom::Vertex) -> Self {
+        Self { x: v.pos.x, y: v.pos.y, clr: v.clr.map(HexColor) }
+    }
+}
+
+impl From<&VertexSpec> for geom::Vertex {
+    fn from(v: &VertexSpec) -> Self {
+        geom::Vertex::new(v.x, v.y, v.clr.map(|c| c.0))
+    }
+}
+
+impl From<&Object> for Shape {
+    fn from(object: &Object) -> Self {
+        match object {
+            Object::Point(p) => Shape::Point(p.into()),
+            Object::LineObj(l) => Shape::Line { a: (&l.a).into(), b: (&l.b).into(), thickness: l.thickness },
+            Object::CircleObj(c) => Shape::Circle { center: (&c.center).into(), radius: c.radius },
+            Object::PolyObj(p) => Shape::Polygon {
+                verts: p.verts.iter().map(Into::into).collect(),
+                edges: p.edges.iter().map(|&(a, b, clr)| (a, b, HexColor(clr))).collect(),
+                edge_thickness: p.edge_thickness,
+                faces: p.faces.iter().map(|&(a, b, c, clr)| (a, b, c, HexColor(clr))).collect(),
+            },
+        }
+    }
+}
+
+impl From<&Shape> for Object {
+    fn from(shape: &Shape) -> Self {
+        match shape {
+            Shape::Point(p) => Object::Point(p.into()),
+            Shape::Line { a, b, thickness } => Object::LineObj(geom::Line2D { a: a.into(), b: b.into(), thickness: *thickness }),
+            Shape::Circle { center, radius } => Object::CircleObj(geom::Circle { center: center.into(), radius: *radius }),
+            Shape::Polygon { verts, edges, edge_thickness, faces } => Object::PolyObj(geom::Polygon {
+                verts: verts.iter().map(Into::into).collect(),
+                edges: edges.iter().map(|&(a, b, clr)| (a, b, clr.0)).collect(),
+                edge_thickness: *edge_thickness,
+                faces: faces.iter().map(|&(a, b, c, clr)| (a, b, c, clr.0)).collect(),
+            }),
+        }
+    }
+}
+
+impl SceneFile {
+    pub fn from_shapes<'a>(shapes: impl IntoIterator<Item = &'a Object>) -> Self {
+        Self { shapes: shapes.into_iter().map(Into::into).collect(), grid: None }
+    }
+
+    /// Every plain shape of `scene`, whatever its layer.
+    pub fn from_scene<O: SceneObject>(scene: &Scene<O>) -> Self {
+        Self::from_shapes(scene.shapes().map(|(_, s)| s))
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = Object> + '_ {
+        self.shapes.iter().map(Into::into)
+    }
+
+    /// Inserts the shapes into `scene` on its default layer, in file order.
+    pub fn add_to<O: From<Object>>(&self, scene: &mut Scene<O>) -> Vec<Handle> {
+        self.objects().map(|o| scene.insert(o.into())).collect()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneFileError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        match Format::of(path) {
+            Format::Json => Self::from_json(&contents),
+            Format::Text => Self::from_text(&contents),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneFileError> {
+        let path = path.as_ref();
+        let contents = match Format::of(path) {
+            Format::Json => self.to_json(),
+            Format::Text => self.to_text(),
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("scene files are always representable as JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SceneFileError> {
+        let file: Self = serde_json::from_str(json)?;
+        file.check()?;
+        Ok(file)
+    }
+
+    pub fn to_text(&self) -> String {
+        use std::fmt::Write;
+        fn vertex(v: &VertexSpec) -> String {
+            match v.clr {
+                Some(clr) => format!("{} {} {clr}", v.x, v.y),
+                None => format!("{} {}", v.x, v.y),
+            }
+        }
+
+        let mut out = String::new();
+        for shape in &self.shapes {
+            // writing to a String can't fail
+            let _ = match shape {
+                Shape::Point(p) => writeln!(out, "point {}", vertex(p)),
+                Shape::Line { a, b, thickness } => writeln!(out, "line {} {} {thickness}", vertex(a), vertex(b)),
+                Shape::Circle { center, radius } => writeln!(out, "circle {} {radius}", vertex(center)),
+                Shape::Polygon { verts, edges, edge_thickness, faces } => {
+                    let _ = writeln!(out, "poly {edge_thickness}");
+                    for v in verts {
+                        let _ = writeln!(out, "v {}", vertex(v));
+                    }
+                    for (a, b, clr) in edges {
+                        let _ = writeln!(out, "e {a} {b} {clr}");
+                    }
+                    for (a, b, c, clr) in faces {
+                        let _ = writeln!(out, "f {a} {b} {c} {clr}");
+                    }
+                    Ok(())
+                }
+            };
+        }
+        if let Some(grid) = &self.grid {
+            let kind = match grid.kind { GridKind::Square => "square", GridKind::Hex => "hex" };
+            let _ = writeln!(out, "grid {kind} {} {} {} {} {} {}", grid.size.0, grid.size.1,
+                             grid.topleft.0, grid.topleft.1, grid.botright.0, grid.botright.1);
+            for (col, row) in &grid.obstacles {
+                let _ = writeln!(out, "obstacle {col} {row}");
+            }
+            for bot in &grid.bots {
+                let _ = writeln!(out, "bot {} {} {} {}", bot.origin.0, bot.origin.1, bot.dest.0, bot.dest.1);
+            }
+            if let Some((col, row)) = grid.goal {
+                let _ = writeln!(out, "goal {col} {row}");
+            }
+        }
+        out
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, SceneFileError> {
+        let mut file = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            file.parse_line(line).map_err(|msg| SceneFileError::Syntax { line: i + 1, msg })?;
+        }
+        file.check()?;
+        Ok(file)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut words = Words(line.split_whitespace().peekable());
+        let keyword = words.0.next().unwrap_or_default();
+        match keyword {
+            "point" => self.shapes.push(Shape::Point(words.vertex()?)),
+            "line" => {
+                let (a, b) = (words.vertex()?, words.vertex()?);
+                self.shapes.push(Shape::Line { a, b, thickness: words.next("thickness")? });
+            }
+            "circle" => {
+                let center = words.vertex()?;
+                self.shapes.push(Shape::Circle { center, radius: words.next("radius")? });
+            }
+            "poly" => self.shapes.push(Shape::Polygon {
+                verts: vec![], edges: vec![], edge_thickness: words.next("edge thickness")?, faces: vec![],
+            }),
+            "v" | "e" | "f" => {
+                let Some(Shape::Polygon { verts, edges, faces, .. }) = self.shapes.last_mut() else {
+                    return Err(format!("'{keyword}' outside of a polygon"));
+                };
+                match keyword {
+                    "v" => verts.push(words.vertex()?),
+                    "e" => edges.push((words.next("index")?, words.next("index")?, words.next("colour")?)),
+                    _ => faces.push((words.next("index")?, words.next("index")?, words.next("index")?, words.next("colour")?)),
+                }
+            }
+            "grid" => {
+                let kind = match words.0.next() {
+                    Some("square") => GridKind::Square,
+                    Some("hex") => GridKind::Hex,
+                    other => return Err(format!("expected square or hex, found {other:?}")),
+                };
+                self.grid = Some(GridSpec {
+                    kind,
+                    size: (words.next("columns")?, words.next("rows")?),
+                    topleft: (words.next("left")?, words.next("top")?),
+                    botright: (words.next("right")?, words.next("bottom")?),
+                    obstacles: vec![], bots: vec![], goal: None,
+                });
+            }
+            "obstacle" | "bot" | "goal" => {
+                let Some(grid) = self.grid.as_mut() else {
+                    return Err(format!("'{keyword}' before the grid"));
+                };
+                match keyword {
+                    "obstacle" => grid.obstacles.push(words.cell()?),
+                    "bot" => grid.bots.push(BotSpec { origin: words.cell()?, dest: words.cell()? }),
+                    _ => grid.goal = Some(words.cell()?),
+                }
+            }
+            _ => return Err(format!("unknown item '{keyword}'")),
+        }
+        match words.0.next() {
+            Some(extra) => Err(format!("unexpected '{extra}'")),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes sure polygons only use their own vertices and cells lie on the grid.
+    fn check(&self) -> Result<(), SceneFileError> {
+        for (i, shape) in self.shapes.iter().enumerate() {
+            if let Shape::Polygon { verts, edges, faces, .. } = shape {
+                let indices = edges.iter().flat_map(|e| [e.0, e.1]).chain(faces.iter().flat_map(|f| [f.0, f.1, f.2]));
+                if let Some(bad) = indices.into_iter().find(|&v| v >= verts.len()) {
+                    return Err(SceneFileError::Invalid(format!("polygon {i} uses vertex {bad} of {}", verts.len())));
+                }
+            }
+        }
+        if let Some(grid) = &self.grid {
+            let cells = grid.obstacles.iter()
+                .chain(grid.bots.iter().flat_map(|b| [&b.origin, &b.dest]))
+                .chain(grid.goal.iter());
+            for &(col, row) in cells {
+                if col >= grid.size.0 || row >= grid.size.1 {
+                    return Err(SceneFileError::Invalid(format!("cell ({col}, {row}) is off the {}x{} grid", grid.size.0, grid.size.1)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Words<'a>(std::iter::Peekable<std::str::SplitWhitespace<'a>>);
+
+impl Words<'_> {
+    fn next<T: FromStr>(&mut self, what: &str) -> Result<T, String> {
+        let word = self.0.next().ok_or_else(|| format!("missing {what}"))?;
+        word.parse().map_err(|_| format!("bad {what} '{word}'"))
+    }
+
+    fn vertex(&mut self) -> Result<VertexSpec, String> {
+        let (x, y) = (self.next("x")?, self.next("y")?);
+        let clr = match self.0.peek() {
+            Some(word) if word.starts_with('#') => Some(self.next("colour")?),
+            _ => None,
+        };
+        Ok(VertexSpec { x, y, clr })
+    }
+
+    fn cell(&mut self) -> Result<(usize, usize), String> {
+        Ok((self.next("column")?, self.next("row")?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SceneFile {
+        let rgb = |r: u8, g: u8, b: u8| HexColor(Color::from_rgba(r, g, b, 255));
+        let v = |x: f32, y: f32| VertexSpec { x, y, clr: None };
+        SceneFile {
+            shapes: vec![
+                Shape::Point(VertexSpec { x: 1.5, y: -2.0, clr: Some(rgb(255, 0, 0)) }),
+                Shape::Line { a: v(0.0, 0.0), b: VertexSpec { clr: Some(rgb(0, 0, 255)), ..v(10.0, 0.25) }, thickness: 2.0 },
+                Shape::Circle { center: v(3.0, 4.0), radius: 0.1 },
+                Shape::Polygon {
+                    verts: vec![v(0.0, 0.0), v(1.0, 0.0), v(0.0, 1.0)],
+                    edges: vec![(0, 1, rgb(1, 2, 3)), (1, 2, rgb(4, 5, 6))],
+                    edge_thickness: 1.0,
+                    faces: vec![(0, 1, 2, rgb(7, 8, 9))],
+                },
+            ],
+            grid: Some(GridSpec {
+                kind: GridKind::Hex,
+                size: (30, 20),
+                topleft: (225.0, 125.0),
+                botright: (1575.0, 875.0),
+                obstacles: vec![(1, 2), (29, 19)],
+                bots: vec![BotSpec { origin: (0, 0), dest: (5, 6) }],
+                goal: Some((7, 8)),
+            }),
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let file = sample();
+        assert_eq!(SceneFile::from_json(&file.to_json()).unwrap(), file);
+        // everything but the shape type is optional where it can be
+        let sparse = SceneFile::from_json(r##"{"shapes": [{"type": "point", "x": 1, "y": 2}]}"##).unwrap();
+        assert_eq!(sparse.shapes, vec![Shape::Point(VertexSpec { x: 1.0, y: 2.0, clr: None })]);
+        assert_eq!(sparse.grid, None);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let file = sample();
+        let text = file.to_text();
+        assert!(text.starts_with("point 1.5 -2 #ff0000ff\nline 0 0 10 0.25 #0000ffff 2\n"), "{text}");
+        assert_eq!(SceneFile::from_text(&text).unwrap(), file);
+        let commented = format!("# a scene\n{text}  # the end\n");
+        assert_eq!(SceneFile::from_text(&commented).unwrap(), file);
+    }
+
+    #[test]
+    fn text_errors_name_the_line() {
+        let err = |text: &str| match SceneFile::from_text(text) {
+            Err(SceneFileError::Syntax { line, msg }) => (line, msg),
+            other => panic!("expected a syntax error, got {other:?}"),
+        };
+        assert_eq!(err("# header\n\npoint 1 x"), (3, "bad y 'x'".to_string()));
+        assert_eq!(err("v 0 0"), (1, "'v' outside of a polygon".to_string()));
+        assert_eq!(err("goal 1 1"), (1, "'goal' before the grid".to_string()));
+        assert_eq!(err("circle 0 0 #12345 3"), (1, "bad colour '#12345'".to_string()));
+        assert_eq!(err("point 0 0 1"), (1, "unexpected '1'".to_string()));
+        assert!(matches!(SceneFile::from_text("poly 1\nv 0 0\ne 0 1 #ffffff"), Err(SceneFileError::Invalid(_))));
+        assert!(matches!(SceneFile::from_text("grid square 2 2 0 0 1 1\nobstacle 2 0"), Err(SceneFileError::Invalid(_))));
+    }
+
+    #[test]
+    fn scenes_survive_a_round_trip() {
+        let mut scene = Scene::<Object>::new();
+        scene.add_circle(geom::Circle { center: geom::Vertex::new(1.0, 2.0, Some(macroquad::color::WHITE)), radius: 3.0 });
+        scene.add_poly(geom::Polygon::rectangle((0.0, 0.0).into(), (4.0, 2.0).into(), macroquad::color::BLACK, macroquad::color::WHITE));
+        let file = SceneFile::from_scene(&scene);
+        let mut copy = Scene::<Object>::new();
+        SceneFile::from_text(&file.to_text()).unwrap().add_to(&mut copy);
+        assert_eq!(copy.len(), 2);
+        assert_eq!(copy.bounds(), scene.bounds());
+        assert_eq!(SceneFile::from_scene(&copy), file);
+        assert_eq!(Format::of(Path::new("a/b.JSON")), Format::Json);
+        assert_eq!(Format::of(Path::new("scene.txt")), Format::Text);
+    }
+}
@@ -0,0 +1,299 @@
+//! A uniform bucket grid over bounding boxes, for hit-testing and nearest
+//! queries without visiting every object.
+//!
+//! Each key is filed under every cell its box overlaps. Cells are hashed, so
+//! the grid needs no bounds up front. Boxes that are unbounded, or that span
+//! more than [`MAX_BUCKETS`] cells, go on a separate list that every query
+//! checks.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use euclid::default::{Box2D, Vector2D};
+use genmap::Handle;
+
+/// The most cells a single box is filed under before it counts as large.
+pub const MAX_BUCKETS: usize = 1024;
+
+type Cell = (i32, i32);
+
+#[derive(Debug, Clone)]
+pub struct BucketGrid<K = Handle> {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<K>>,
+    large: Vec<K>,
+    boxes: HashMap<K, Box2D<f32>>,
+    // the smallest and largest occupied cells, which bound ring searches
+    extent: Option<(Cell, Cell)>,
+}
+
+impl<K: Copy + Eq + Hash> BucketGrid<K> {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive, got {cell_size}");
+        Self { cell_size, cells: HashMap::new(), large: vec![], boxes: HashMap::new(), extent: None }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.boxes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    pub fn contains(&self, key: K) -> bool {
+        self.boxes.contains_key(&key)
+    }
+
+    /// The box `key` is filed under.
+    pub fn aabb(&self, key: K) -> Option<Box2D<f32>> {
+        self.boxes.get(&key).copied()
+    }
+
+    /// Files `key` under `aabb`, replacing its previous box if it had one.
+    pub fn insert(&mut self, key: K, aabb: Box2D<f32>) {
+        self.remove(key);
+        self.boxes.insert(key, aabb);
+        match self.cell_range(&aabb) {
+            Some((lo, hi)) => {
+                for x in lo.0..=hi.0 {
+                    for y in lo.1..=hi.1 {
+                        self.cells.entry((x, y)).or_default().push(key);
+                    }
+                }
+                self.extent = Some(match self.extent {
+                    Some((min, max)) => ((min.0.min(lo.0), min.1.min(lo.1)), (max.0.max(hi.0), max.1.max(hi.1))),
+                    None => (lo, hi),
+                });
+            }
+            None => self.large.push(key),
+        }
+    }
+
+    /// Moves `key` to a new box. The same as [`BucketGrid::insert`].
+    pub fn update(&mut self, key: K, aabb: Box2D<f32>) {
+        self.insert(key, aabb);
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<Box2D<f32>> {
+        let aabb = self.boxes.remove(&key)?;
+        match self.cell_range(&aabb) {
+            Some((lo, hi)) => {
+                for x in lo.0..=hi.0 {
+                    for y in lo.1..=hi.1 {
+                        if let Some(bucket) = self.cells.get_mut(&(x, y)) {
+                            bucket.retain(|k| *k != key);
+                            if bucket.is_empty() { self.cells.remove(&(x, y)); }
+                        }
+                    }
+                }
+            }
+            None => self.large.retain(|k| *k != key),
+        }
+        if self.boxes.is_empty() { self.extent = None; }
+        Some(aabb)
+    }
+
+    /// The keys whose boxes touch `region`, each once.
+    pub fn query_region(&self, region: &Box2D<f32>) -> Vec<K> {
+        let mut seen = HashSet::new();
+        let candidates = match self.cell_range(region) {
+            Some((lo, hi)) => {
+                let (lo, hi) = self.clamp_to_extent(lo, hi);
+                (lo.0..=hi.0).flat_map(|x| (lo.1..=hi.1).map(move |y| (x, y)))
+                             .flat_map(|cell| self.cells.get(&cell).into_iter().flatten())
+                             .chain(&self.large)
+                             .copied().collect::<Vec<_>>()
+            }
+            None => self.boxes.keys().copied().collect(),
+        };
+        candidates.into_iter()
+                  .filter(|k| seen.insert(*k) && overlaps(&self.boxes[k], region))
+                  .collect()
+    }
+
+    /// The keys whose boxes contain `point`.
+    pub fn query_point(&self, point: &Vector2D<f32>) -> Vec<K> {
+        self.query_region(&Box2D::new(point.to_point(), point.to_point()))
+    }
+
+    /// Up to `k` keys closest to `point` by the distance to their boxes, nearest first.
+    pub fn nearest(&self, point: &Vector2D<f32>, k: usize) -> Vec<(K, f32)> {
+        self.nearest_by(point, k, |key| Some(box_distance(&self.boxes[&key], point)))
+    }
+
+    /// Up to `k` keys closest to `point` by `distance`, nearest first. Keys
+    /// for which `distance` returns `None` are skipped.
+    ///
+    /// Whenever `point` lies outside a key's box, `distance` must be at least
+    /// the distance to that box. Signed distance fields qualify.
+    pub fn nearest_by(&self, point: &Vector2D<f32>, k: usize, mut distance: impl FnMut(K) -> Option<f32>) -> Vec<(K, f32)> {
+        let mut best: Vec<(K, f32)> = vec![];
+        if k == 0 { return best; }
+        let mut seen = HashSet::new();
+        let mut consider = |key: K, best: &mut Vec<(K, f32)>| {
+            if !seen.insert(key) { return; }
+            let Some(d) = distance(key) else { return };
+            let at = best.partition_point(|(_, e)| *e <= d);
+            if at < k {
+                best.insert(at, (key, d));
+                best.truncate(k);
+            }
+        };
+        for key in &self.large {
+            consider(*key, &mut best);
+        }
+        let Some((min, max)) = self.extent else { return best; };
+
+        let center = self.cell_of(point.x, point.y);
+        // rings that miss the occupied cells entirely have nothing to offer
+        let first_ring = [min.0.saturating_sub(center.0), center.0.saturating_sub(max.0),
+                          min.1.saturating_sub(center.1), center.1.saturating_sub(max.1), 0]
+            .into_iter().max().unwrap();
+        for ring in first_ring.. {
+            let lo = (center.0.saturating_sub(ring), center.1.saturating_sub(ring));
+            let hi = (center.0.saturating_add(ring), center.1.saturating_add(ring));
+            let rows = (lo.0..=hi.0).flat_map(|x| [(x, lo.1), (x, hi.1)]);
+            let columns = (lo.1 + 1..hi.1).flat_map(|y| [(lo.0, y), (hi.0, y)]);
+            for cell in rows.chain(columns) {
+                for key in self.cells.get(&cell).into_iter().flatten() {
+                    consider(*key, &mut best);
+                }
+            }
+            let covers_extent = lo.0 <= min.0 && lo.1 <= min.1 && hi.0 >= max.0 && hi.1 >= max.1;
+            // keys not seen yet lie wholly outside the searched block
+            let cs = self.cell_size;
+            let bound = (point.x - lo.0 as f32 * cs)
+                .min((hi.0 as f32 + 1.0) * cs - point.x)
+                .min(point.y - lo.1 as f32 * cs)
+                .min((hi.1 as f32 + 1.0) * cs - point.y);
+            let done = best.len() == k && best[k - 1].1 <= bound;
+            if covers_extent || done { break; }
+        }
+        best
+    }
+
+    fn cell_of(&self, x: f32, y: f32) -> Cell {
+        // `as` saturates, so far away points land on the outermost cells
+        ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+    }
+
+    /// The cells `aabb` overlaps, or `None` if it is too large to bucket.
+    fn cell_range(&self, aabb: &Box2D<f32>) -> Option<(Cell, Cell)> {
+        let finite = [aabb.min.x, aabb.min.y, aabb.max.x, aabb.max.y].iter().all(|v| v.is_finite());
+        if !finite { return None; }
+        let lo = self.cell_of(aabb.min.x, aabb.min.y);
+        let hi = self.cell_of(aabb.max.x, aabb.max.y);
+        let count = (hi.0 as i64 - lo.0 as i64 + 1) * (hi.1 as i64 - lo.1 as i64 + 1);
+        (count <= MAX_BUCKETS as i64).then_some((lo, hi))
+    }
+
+    fn clamp_to_extent(&self, lo: Cell, hi: Cell) -> (Cell, Cell) {
+        match self.extent {
+            Some((min, max)) => ((lo.0.max(min.0), lo.1.max(min.1)), (hi.0.min(max.0), hi.1.min(max.1))),
+            None => (lo, (lo.0 - 1, lo.1 - 1)),
+        }
+    }
+}
+
+/// Like [`Box2D::intersects`], but boxes that merely touch, and empty boxes
+/// such as a single point, count too.
+fn overlaps(a: &Box2D<f32>, b: &Box2D<f32>) -> bool {
+    a.min.x <= b.max.x && b.min.x <= a.max.x && a.min.y <= b.max.y && b.min.y <= a.max.y
+}
+
+/// Distance from `point` to the box, zero inside it.
+pub fn box_distance(aabb: &Box2D<f32>, point: &Vector2D<f32>) -> f32 {
+    let dx = (aabb.min.x - point.x).max(point.x - aabb.max.x).max(0.0);
+    let dy = (aabb.min.y - point.y).max(point.y - aabb.max.y).max(0.0);
+    dx.hypot(dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bx(x0: f32, y0: f32, x1: f32, y1: f32) -> Box2D<f32> {
+        Box2D::new((x0, y0).into(), (x1, y1).into())
+    }
+
+    fn sorted(mut keys: Vec<u32>) -> Vec<u32> {
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn region_and_point_queries() {
+        let mut grid = BucketGrid::new(10.0);
+        grid.insert(1, bx(0.0, 0.0, 5.0, 5.0));
+        grid.insert(2, bx(8.0, 8.0, 35.0, 12.0));
+        grid.insert(3, bx(-20.0, -20.0, -15.0, -15.0));
+        assert_eq!(sorted(grid.query_point(&Vector2D::new(30.0, 10.0))), vec![2]);
+        assert_eq!(sorted(grid.query_point(&Vector2D::new(5.0, 5.0))), vec![1]);
+        assert_eq!(sorted(grid.query_region(&bx(-16.0, -16.0, 9.0, 9.0))), vec![1, 2, 3]);
+        assert!(grid.query_point(&Vector2D::new(6.0, 6.0)).is_empty());
+    }
+
+    #[test]
+    fn update_and_remove() {
+        let mut grid = BucketGrid::new(4.0);
+        grid.insert(1, bx(0.0, 0.0, 1.0, 1.0));
+        grid.update(1, bx(100.0, 100.0, 101.0, 101.0));
+        assert!(grid.query_point(&Vector2D::new(0.5, 0.5)).is_empty());
+        assert_eq!(grid.query_point(&Vector2D::new(100.5, 100.5)), vec![1]);
+        assert_eq!(grid.len(), 1);
+        assert_eq!(grid.remove(1), Some(bx(100.0, 100.0, 101.0, 101.0)));
+        assert!(grid.is_empty() && grid.query_point(&Vector2D::new(100.5, 100.5)).is_empty());
+        assert_eq!(grid.remove(1), None);
+    }
+
+    #[test]
+    fn large_and_unbounded_boxes() {
+        let mut grid = BucketGrid::new(1.0);
+        grid.insert(1, bx(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY));
+        grid.insert(2, bx(-1e6, -1e6, 1e6, 1e6));
+        grid.insert(3, bx(0.0, 0.0, 0.5, 0.5));
+        assert_eq!(sorted(grid.query_point(&Vector2D::new(5e5, -5e5))), vec![1, 2]);
+        assert_eq!(sorted(grid.query_point(&Vector2D::new(0.25, 0.25))), vec![1, 2, 3]);
+        grid.remove(1);
+        assert_eq!(sorted(grid.query_point(&Vector2D::new(0.25, 0.25))), vec![2, 3]);
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut grid = BucketGrid::new(16.0);
+        let points: Vec<(f32, f32)> = (0..200u32).map(|i| {
+            let (a, b) = (i.wrapping_mul(2654435761) % 1000, i.wrapping_mul(40503) % 700);
+            (a as f32 * 0.7, b as f32 * 1.3)
+        }).collect();
+        for (i, (x, y)) in points.iter().enumerate() {
+            grid.insert(i as u32, bx(*x, *y, *x, *y));
+        }
+        for query in [Vector2D::new(0.0, 0.0), Vector2D::new(350.0, 450.0), Vector2D::new(-500.0, 2000.0)] {
+            let mut expected: Vec<f32> = points.iter().map(|(x, y)| (x - query.x).hypot(y - query.y)).collect();
+            expected.sort_by(f32::total_cmp);
+            let found: Vec<f32> = grid.nearest(&query, 5).into_iter().map(|(_, d)| d).collect();
+            assert_eq!(found, expected[..5].to_vec(), "around {query:?}");
+        }
+        assert_eq!(grid.nearest(&Vector2D::new(0.0, 0.0), 1000).len(), 200);
+    }
+
+    #[test]
+    fn nearest_by_skips_and_reranks() {
+        let mut grid = BucketGrid::new(8.0);
+        grid.insert(1, bx(0.0, 0.0, 10.0, 10.0));
+        grid.insert(2, bx(20.0, 0.0, 22.0, 2.0));
+        grid.insert(3, bx(40.0, 0.0, 42.0, 2.0));
+        let point = Vector2D::new(5.0, 5.0);
+        // the first key is hollow, so from its middle it's 5 away
+        let found = grid.nearest_by(&point, 2, |k| match k {
+            1 => Some(5.0),
+            2 => None,
+            _ => Some(box_distance(&grid.aabb(k).unwrap(), &point)),
+        });
+        assert_eq!(found.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![1, 3]);
+    }
+}
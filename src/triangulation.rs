@@ -0,0 +1,837 @@
+//! An incremental Delaunay triangulation (Bowyer–Watson) with constrained
+//! segments, so obstacles can be forced into the mesh.
+//!
+//! Every insertion cuts out the triangles whose circumcircle holds the new
+//! point, the cavity, and fills the hole with a fan around it. Beyond the
+//! hull every edge has a ghost triangle closing it off at a vertex at
+//! infinity, so points outside the hull are no special case and there's no
+//! super triangle to strip off at the end. Cavities don't grow across
+//! constrained edges, and edge flips settle what the constraints hid from
+//! the cavity search, which keeps the triangles Delaunay among the points
+//! they can see: a constrained Delaunay triangulation.
+//!
+//! Vertex ids number the points in the order they were first inserted and
+//! stay valid when others are removed.
+
+use std::collections::{HashMap, HashSet};
+use macroquad::color::Color;
+use crate::{
+    dcel::{Dcel, VertexId},
+    geom::{segment_intersection, Line2D, Polygon, Vertex},
+    hud::Metrics,
+    point::Point,
+    predicates::{incircle, orient2d, strictly_on_segment},
+};
+
+/// The vertex at infinity every ghost triangle has.
+const GHOST: usize = usize::MAX;
+/// No triangle, for vertices that aren't in the mesh.
+const NONE: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Triangle {
+    /// Counter-clockwise. A ghost's real edge has the outside on its left.
+    v: [usize; 3],
+    /// The triangle across the edge opposite `v[i]`.
+    n: [usize; 3],
+    alive: bool,
+}
+
+impl Triangle {
+    fn is_ghost(&self) -> bool {
+        self.v.contains(&GHOST)
+    }
+
+    /// The edge opposite `v[i]`, in the triangle's direction.
+    fn edge(&self, i: usize) -> (usize, usize) {
+        (self.v[(i + 1) % 3], self.v[(i + 2) % 3])
+    }
+
+    fn index_of(&self, vertex: usize) -> Option<usize> {
+        self.v.iter().position(|&v| v == vertex)
+    }
+}
+
+fn edges_of(triangles: &[[usize; 3]]) -> Vec<(usize, usize)> {
+    triangles.iter().flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)]).collect()
+}
+
+fn key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Triangulation {
+    points: Vec<Point>,
+    alive: Vec<bool>,
+    tris: Vec<Triangle>,
+    free: Vec<usize>,
+    /// A triangle around every vertex in the mesh.
+    vertex_tri: Vec<usize>,
+    constraints: HashSet<(usize, usize)>,
+    /// Vertices waiting for three that aren't collinear, which the mesh starts from.
+    pending: Vec<usize>,
+    pending_constraints: Vec<(usize, usize)>,
+    /// Where the walk to the next point starts.
+    last: usize,
+    cavity: Vec<[usize; 3]>,
+    rebuilds: usize,
+}
+
+impl Triangulation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `points` in order, so their ids are their indices as long as
+    /// none repeats: a repeated point gets no id of its own.
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> Self {
+        let mut triangulation = Self::new();
+        for p in points {
+            triangulation.insert(p);
+        }
+        triangulation
+    }
+
+    /// The vertices in the triangulation.
+    pub fn len(&self) -> usize {
+        self.alive.iter().filter(|&&alive| alive).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn point(&self, vertex: usize) -> Option<Point> {
+        self.alive.get(vertex).copied().unwrap_or(false).then(|| self.points[vertex])
+    }
+
+    /// How many times a removal forced a full rebuild.
+    pub fn rebuilds(&self) -> usize {
+        self.rebuilds
+    }
+
+    /// The triangles, counter-clockwise. Empty until three of the points
+    /// aren't collinear.
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        self.tris.iter().filter(|t| t.alive && !t.is_ghost()).map(|t| t.v).collect()
+    }
+
+    /// The triangles the last insertion replaced, ghosts left out.
+    pub fn last_cavity(&self) -> &[[usize; 3]] {
+        &self.cavity
+    }
+
+    /// The constrained edges, as vertex pairs, smaller id first.
+    pub fn constraints(&self) -> Vec<(usize, usize)> {
+        let mut constraints: Vec<_> = self.constraints.iter().copied().collect();
+        constraints.sort();
+        constraints
+    }
+
+    pub fn is_constrained(&self, a: usize, b: usize) -> bool {
+        self.constraints.contains(&key(a, b))
+    }
+
+    /// Adds a point and returns its vertex, or the vertex already at `p`.
+    pub fn insert(&mut self, p: Point) -> usize {
+        if self.tris.is_empty() {
+            if let Some(&v) = self.pending.iter().find(|&&v| self.points[v] == p) {
+                return v;
+            }
+            let v = self.push_point(p);
+            self.place_pending(v);
+            return v;
+        }
+        match self.locate(p) {
+            Err(existing) => existing,
+            Ok(t) => {
+                let v = self.push_point(p);
+                self.dig(v, t);
+                v
+            }
+        }
+    }
+
+    /// Drops a vertex, along with the constrained edges ending at it.
+    /// Returns whether it was there. Where the vertex split a constrained
+    /// edge, the edge is put back whole.
+    ///
+    /// The hole around an inner vertex is filled back in place, then edges
+    /// are flipped out from it where constraints nearby left it short of
+    /// Delaunay, or all over when the vertex's own constraints were hiding
+    /// points from triangles. Removing a hull vertex changes the hull, so
+    /// the triangulation is rebuilt, as it is in the rare case the hole
+    /// can't be filled with empty circumcircles.
+    pub fn remove(&mut self, vertex: usize) -> bool {
+        if !self.alive.get(vertex).copied().unwrap_or(false) { return false; }
+        self.alive[vertex] = false;
+        let p = self.points[vertex];
+        let ends: Vec<usize> = self.constraints.iter()
+            .filter_map(|&(a, b)| if a == vertex { Some(b) } else if b == vertex { Some(a) } else { None })
+            .collect();
+        let mut rejoined = vec![];
+        for (i, &u) in ends.iter().enumerate() {
+            for &w in &ends[i + 1..] {
+                if strictly_on_segment(self.points[u], self.points[w], p) {
+                    rejoined.push((u, w));
+                }
+            }
+        }
+        let constraint_count = self.constraints.len();
+        self.constraints.retain(|&(a, b)| a != vertex && b != vertex);
+        let dropped_constraints = self.constraints.len() < constraint_count;
+        self.pending_constraints.retain(|&(a, b)| a != vertex && b != vertex);
+        if let Some(i) = self.pending.iter().position(|&v| v == vertex) {
+            self.pending.remove(i);
+            return true;
+        }
+
+        let star = self.star(vertex);
+        let refilled = if star.iter().any(|&t| self.tris[t].is_ghost()) { None } else { self.refill(vertex, &star) };
+        match refilled {
+            Some(new) => {
+                self.replace(&star, &new);
+                self.vertex_tri[vertex] = NONE;
+                self.cavity.clear();
+                let suspects = if dropped_constraints { self.triangles() } else { new };
+                self.legalize(edges_of(&suspects));
+            }
+            None => {
+                self.rebuild();
+                self.rebuilds += 1;
+            }
+        }
+        for (u, w) in rejoined {
+            self.insert_constraint(u, w);
+        }
+        true
+    }
+
+    /// Forces the edge `a`–`b` into the mesh. Vertices lying on it split it,
+    /// and so do constrained edges it crosses, at a new vertex where they meet.
+    pub fn insert_constraint(&mut self, a: usize, b: usize) {
+        assert!(self.point(a).is_some() && self.point(b).is_some(), "constraint {a}-{b} between missing vertices");
+        if self.tris.is_empty() {
+            if a != b && !self.pending_constraints.contains(&key(a, b)) {
+                self.pending_constraints.push(key(a, b));
+            }
+            return;
+        }
+        self.constrain(a, b);
+    }
+
+    /// Inserts both ends and constrains the segment between them.
+    pub fn insert_segment(&mut self, a: Point, b: Point) -> (usize, usize) {
+        let (a, b) = (self.insert(a), self.insert(b));
+        self.insert_constraint(a, b);
+        (a, b)
+    }
+
+    /// Forces an obstacle drawn as a line into the mesh.
+    pub fn insert_line(&mut self, line: &Line2D) {
+        self.insert_segment(Point::from(line.a.pos), Point::from(line.b.pos));
+    }
+
+    /// Forces a polygon's outline into the mesh: its vertices, and its edges
+    /// as constraints.
+    pub fn insert_polygon(&mut self, poly: &Polygon) {
+        let ids: Vec<usize> = poly.verts.iter().map(|v| self.insert(Point::from(v.pos))).collect();
+        for &(a, b, _) in &poly.edges {
+            self.insert_constraint(ids[a], ids[b]);
+        }
+    }
+
+    /// The mesh as a polygon over all the points inserted so far, with a face
+    /// per triangle and the constrained edges in their own colour.
+    pub fn to_polygon(&self, edge_color: Color, constraint_color: Color, face_color: Color) -> Polygon {
+        let mut edges = vec![];
+        let mut faces = vec![];
+        for [a, b, c] in self.triangles() {
+            faces.push((a, b, c, face_color));
+            for (u, v) in [(a, b), (b, c), (c, a)] {
+                // every inner edge is in two triangles, take it from one
+                if u < v || self.is_hull_edge(u, v) {
+                    edges.push((u, v, if self.is_constrained(u, v) { constraint_color } else { edge_color }));
+                }
+            }
+        }
+        Polygon { verts: self.points.iter().map(|&p| Vertex::from(p)).collect(), edges, edge_thickness: 1.0, faces }
+    }
+
+    /// The mesh as a subdivision with a face per triangle, its vertex ids
+    /// those of the triangulation.
+    pub fn to_dcel(&self) -> Dcel {
+        let mut dcel = Dcel::from_triangles(&self.points, &self.triangles());
+        for v in (0..self.points.len()).filter(|&v| !self.alive[v]) {
+            dcel.remove_vertex(VertexId::new(v));
+        }
+        dcel
+    }
+
+    /// [`Triangulation::last_cavity`] as filled triangles.
+    pub fn cavity_polygon(&self, face_color: Color) -> Polygon {
+        Polygon {
+            verts: self.points.iter().map(|&p| Vertex::from(p)).collect(),
+            faces: self.cavity.iter().map(|&[a, b, c]| (a, b, c, face_color)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn push_point(&mut self, p: Point) -> usize {
+        self.points.push(p);
+        self.alive.push(true);
+        self.vertex_tri.push(NONE);
+        self.points.len() - 1
+    }
+
+    fn is_hull_edge(&self, a: usize, b: usize) -> bool {
+        self.star(a).iter().any(|&t| self.tris[t].is_ghost() && self.tris[t].index_of(b).is_some())
+    }
+
+    /// Puts a vertex into the mesh, starting the mesh if it can.
+    fn place(&mut self, v: usize) {
+        if self.tris.is_empty() {
+            self.place_pending(v);
+        } else if let Ok(t) = self.locate(self.points[v]) {
+            self.dig(v, t);
+        }
+    }
+
+    fn place_pending(&mut self, v: usize) {
+        self.pending.push(v);
+        if self.pending.len() < 3 { return; }
+        let (p0, p1) = (self.points[self.pending[0]], self.points[self.pending[1]]);
+        let Some(k) = self.pending.iter().position(|&w| orient2d(p0, p1, self.points[w]) != 0.0) else { return };
+
+        let (a, mut b, mut c) = (self.pending[0], self.pending[1], self.pending[k]);
+        if orient2d(self.points[a], self.points[b], self.points[c]) < 0.0 {
+            std::mem::swap(&mut b, &mut c);
+        }
+        self.replace(&[], &[[a, b, c], [b, a, GHOST], [c, b, GHOST], [a, c, GHOST]]);
+        let rest: Vec<usize> = self.pending.drain(..).filter(|&w| w != a && w != b && w != c).collect();
+        for w in rest {
+            self.place(w);
+        }
+        for (a, b) in std::mem::take(&mut self.pending_constraints) {
+            self.constrain(a, b);
+        }
+    }
+
+    /// Rebuilds the mesh from the vertices and constraints left.
+    fn rebuild(&mut self) {
+        let mut constraints = self.constraints();
+        constraints.append(&mut self.pending_constraints);
+        self.tris.clear();
+        self.free.clear();
+        self.pending.clear();
+        self.cavity.clear();
+        self.constraints.clear();
+        self.vertex_tri.fill(NONE);
+        for v in 0..self.points.len() {
+            if self.alive[v] {
+                self.place(v);
+            }
+        }
+        for (a, b) in constraints {
+            self.insert_constraint(a, b);
+        }
+    }
+
+    /// The triangle holding `p`, or a ghost whose outside it is in, or the
+    /// vertex already at `p`.
+    fn locate(&self, p: Point) -> Result<usize, usize> {
+        let mut t = self.last;
+        if t >= self.tris.len() || !self.tris[t].alive || self.tris[t].is_ghost() {
+            t = self.tris.iter().position(|t| t.alive && !t.is_ghost()).unwrap();
+        }
+        // walks can go round in circles where constraints broke the Delaunay
+        // property, varying the edge tried first makes that unlikely, and
+        // the step limit makes sure
+        for step in 0..4 * self.tris.len() + 16 {
+            let tri = self.tris[t];
+            if tri.is_ghost() { return Ok(t); }
+            if let Some(&v) = tri.v.iter().find(|&&v| self.points[v] == p) { return Err(v); }
+            let crossed = (0..3).map(|k| (step + k) % 3).find(|&i| {
+                let (a, b) = tri.edge(i);
+                orient2d(self.points[a], self.points[b], p) < 0.0
+            });
+            match crossed {
+                Some(i) => t = tri.n[i],
+                None => return Ok(t),
+            }
+        }
+        if let Some(v) = (0..self.points.len()).find(|&v| self.vertex_tri[v] != NONE && self.points[v] == p) {
+            return Err(v);
+        }
+        let holds = |t: &Triangle| {
+            if t.is_ghost() {
+                let (a, b) = self.real_edge(t);
+                orient2d(self.points[a], self.points[b], p) > 0.0
+            } else {
+                (0..3).all(|i| { let (a, b) = t.edge(i); orient2d(self.points[a], self.points[b], p) >= 0.0 })
+            }
+        };
+        Ok(self.tris.iter().position(|t| t.alive && holds(t)).expect("every point is in a triangle or beyond the hull"))
+    }
+
+    /// A ghost's edge along the hull, the outside on its left.
+    fn real_edge(&self, t: &Triangle) -> (usize, usize) {
+        t.edge(t.index_of(GHOST).unwrap())
+    }
+
+    /// Whether `p` is inside the triangle's circumcircle. For a ghost that's
+    /// the open half plane beyond its hull edge, plus the edge itself.
+    fn in_circle(&self, t: usize, p: Point) -> bool {
+        let tri = &self.tris[t];
+        if tri.is_ghost() {
+            let (a, b) = self.real_edge(tri);
+            let (a, b) = (self.points[a], self.points[b]);
+            orient2d(a, b, p) > 0.0 || strictly_on_segment(a, b, p)
+        } else {
+            let [a, b, c] = tri.v.map(|v| self.points[v]);
+            incircle(a, b, c, p) > 0.0
+        }
+    }
+
+    /// Replaces the cavity of vertex `v`, grown from triangle `t`, with a fan around it.
+    fn dig(&mut self, v: usize, t: usize) {
+        let p = self.points[v];
+        let mut cavity = vec![t];
+        let mut in_cavity = HashSet::from([t]);
+        let mut split = vec![];
+        let mut stack = vec![t];
+        while let Some(t) = stack.pop() {
+            for i in 0..3 {
+                let next = self.tris[t].n[i];
+                if in_cavity.contains(&next) { continue; }
+                let (a, b) = self.tris[t].edge(i);
+                if a != GHOST && b != GHOST && self.is_constrained(a, b) {
+                    // only a point right on the constrained edge goes across, splitting it
+                    if !strictly_on_segment(self.points[a], self.points[b], p) { continue; }
+                    split.push((a, b));
+                }
+                if self.in_circle(next, p) {
+                    in_cavity.insert(next);
+                    cavity.push(next);
+                    stack.push(next);
+                }
+            }
+        }
+
+        let mut fan = vec![];
+        for &t in &cavity {
+            for i in 0..3 {
+                if !in_cavity.contains(&self.tris[t].n[i]) {
+                    let (a, b) = self.tris[t].edge(i);
+                    fan.push([a, b, v]);
+                }
+            }
+        }
+        self.cavity = cavity.iter().map(|&t| self.tris[t]).filter(|t| !t.is_ghost()).map(|t| t.v).collect();
+        self.replace(&cavity, &fan);
+        for (a, b) in split {
+            self.constraints.remove(&key(a, b));
+            self.constraints.insert(key(a, v));
+            self.constraints.insert(key(v, b));
+        }
+        // the cavity only grows through triangles in conflict, and behind a
+        // constraint one that isn't can hide one that is from the search
+        if !self.constraints.is_empty() {
+            self.legalize(edges_of(&fan));
+        }
+    }
+
+    /// The triangles around `v`, counter-clockwise.
+    fn star(&self, v: usize) -> Vec<usize> {
+        let first = self.vertex_tri[v];
+        if first == NONE { return vec![]; }
+        let mut star = vec![];
+        let mut t = first;
+        loop {
+            star.push(t);
+            let i = self.tris[t].index_of(v).unwrap();
+            t = self.tris[t].n[(i + 2) % 3];
+            if t == first { break; }
+        }
+        star
+    }
+
+    /// Triangulates the hole removing `v` from its star leaves, an ear at a
+    /// time, taking the ears whose circumcircle is empty of the other corners
+    /// of the hole. `None` if none of them is.
+    fn refill(&self, v: usize, star: &[usize]) -> Option<Vec<[usize; 3]>> {
+        let next: HashMap<usize, usize> = star.iter().map(|&t| {
+            let tri = &self.tris[t];
+            tri.edge(tri.index_of(v).unwrap())
+        }).collect();
+        let start = *next.keys().next()?;
+        let mut hole = vec![start];
+        while next[hole.last().unwrap()] != start {
+            hole.push(next[hole.last().unwrap()]);
+        }
+
+        let mut tris = vec![];
+        while hole.len() > 3 {
+            let ear = (0..hole.len()).find(|&i| {
+                let [a, b, c] = [hole[(i + hole.len() - 1) % hole.len()], hole[i], hole[(i + 1) % hole.len()]]
+                    .map(|u| self.points[u]);
+                orient2d(a, b, c) > 0.0
+                    && hole.iter().all(|&u| incircle(a, b, c, self.points[u]) <= 0.0)
+            })?;
+            let n = hole.len();
+            tris.push([hole[(ear + n - 1) % n], hole[ear], hole[(ear + 1) % n]]);
+            hole.remove(ear);
+        }
+        tris.push([hole[0], hole[1], hole[2]]);
+        Some(tris)
+    }
+
+    /// The triangle with the directed edge `a`-`b`, and the slot opposite it.
+    fn find_edge(&self, a: usize, b: usize) -> Option<(usize, usize)> {
+        self.star(a).into_iter().find_map(|t| {
+            (0..3).find(|&i| self.tris[t].edge(i) == (a, b)).map(|i| (t, i))
+        })
+    }
+
+    /// Flips the edges in `edges`, and the ones around those flipped, until
+    /// none that isn't constrained has the far corner across it inside its
+    /// circumcircle (Lawson's algorithm).
+    fn legalize(&mut self, mut edges: Vec<(usize, usize)>) {
+        while let Some((a, b)) = edges.pop() {
+            if a == GHOST || b == GHOST || self.is_constrained(a, b) { continue; }
+            let Some((t, i)) = self.find_edge(a, b) else { continue };
+            let (c, across) = (self.tris[t].v[i], self.tris[t].n[i]);
+            if self.tris[t].is_ghost() || self.tris[across].is_ghost() { continue; }
+            let j = (0..3).find(|&j| self.tris[across].edge(j) == (b, a)).unwrap();
+            let d = self.tris[across].v[j];
+            if !self.in_circle(t, self.points[d]) { continue; }
+            self.replace(&[t, across], &[[c, a, d], [d, b, c]]);
+            edges.extend([(a, d), (d, b), (b, c), (c, a)]);
+        }
+    }
+
+    /// Swaps the triangles `old` for `new`, which must cover the same region,
+    /// linking the new ones with each other and with the triangles around.
+    fn replace(&mut self, old: &[usize], new: &[[usize; 3]]) {
+        let old_set: HashSet<usize> = old.iter().copied().collect();
+        // the triangles outside, by the edge as the old triangles had it
+        let mut boundary = HashMap::new();
+        for &t in old {
+            for i in 0..3 {
+                let next = self.tris[t].n[i];
+                if !old_set.contains(&next) {
+                    boundary.insert(self.tris[t].edge(i), next);
+                }
+            }
+            self.tris[t].alive = false;
+            self.free.push(t);
+        }
+
+        let ids: Vec<usize> = new.iter().map(|&v| {
+            let tri = Triangle { v, n: [NONE; 3], alive: true };
+            match self.free.pop() {
+                Some(t) => { self.tris[t] = tri; t }
+                None => { self.tris.push(tri); self.tris.len() - 1 }
+            }
+        }).collect();
+        let by_edge: HashMap<(usize, usize), usize> = ids.iter()
+            .flat_map(|&t| (0..3).map(move |i| (t, i)))
+            .map(|(t, i)| (self.tris[t].edge(i), t))
+            .collect();
+        for &t in &ids {
+            for i in 0..3 {
+                let (a, b) = self.tris[t].edge(i);
+                if let Some(&twin) = by_edge.get(&(b, a)) {
+                    self.tris[t].n[i] = twin;
+                } else {
+                    let outside = *boundary.get(&(a, b)).expect("the new triangles cover the old ones");
+                    self.tris[t].n[i] = outside;
+                    let j = (0..3).find(|&j| self.tris[outside].edge(j) == (b, a)).unwrap();
+                    self.tris[outside].n[j] = t;
+                }
+            }
+            for v in self.tris[t].v {
+                if v != GHOST {
+                    self.vertex_tri[v] = t;
+                }
+            }
+            if !self.tris[t].is_ghost() {
+                self.last = t;
+            }
+        }
+    }
+
+    fn constrain(&mut self, a: usize, b: usize) {
+        if a == b { return; }
+        let (pa, pb) = (self.points[a], self.points[b]);
+        let mut first = None;
+        for t in self.star(a) {
+            let tri = self.tris[t];
+            if tri.is_ghost() { continue; }
+            let i = tri.index_of(a).unwrap();
+            let (c, d) = tri.edge(i);
+            if c == b || d == b {
+                self.constraints.insert(key(a, b));
+                return;
+            }
+            if strictly_on_segment(pa, pb, self.points[c]) {
+                self.constrain(a, c);
+                self.constrain(c, b);
+                return;
+            }
+            if orient2d(pa, self.points[c], pb) > 0.0 && orient2d(pa, self.points[d], pb) < 0.0 {
+                first = Some((t, c, d));
+            }
+        }
+        let Some((mut t, mut right, mut left)) = first else { return };
+
+        // walk along a-b, collecting the triangles it crosses and the
+        // vertices on either side of it
+        let mut crossed = vec![t];
+        let (mut left_chain, mut right_chain) = (vec![left], vec![right]);
+        let end = loop {
+            if self.is_constrained(right, left) {
+                let (pr, pl) = (self.points[right], self.points[left]);
+                let meet = segment_intersection([pa, pb], [pr, pl]).unwrap_or_else(|| pr.lerp(&pl, 0.5));
+                self.constraints.remove(&key(right, left));
+                let steiner = self.insert(meet);
+                self.constrain(right, steiner);
+                self.constrain(steiner, left);
+                self.constrain(a, steiner);
+                self.constrain(steiner, b);
+                return;
+            }
+            let tri = self.tris[t];
+            let i = (0..3).find(|&i| tri.edge(i) == (right, left) || tri.edge(i) == (left, right)).unwrap();
+            t = tri.n[i];
+            crossed.push(t);
+            let e = self.tris[t].v.into_iter().find(|&v| v != right && v != left).unwrap();
+            let side = orient2d(pa, pb, self.points[e]);
+            if e == b || side == 0.0 { break e; }
+            if side < 0.0 {
+                right_chain.push(e);
+                right = e;
+            } else {
+                left_chain.push(e);
+                left = e;
+            }
+        };
+
+        let mut new = vec![];
+        self.fill_pseudo_polygon(a, end, &left_chain, &mut new);
+        right_chain.reverse();
+        self.fill_pseudo_polygon(end, a, &right_chain, &mut new);
+        self.cavity.clear();
+        self.replace(&crossed, &new);
+        self.constraints.insert(key(a, end));
+        // a Steiner point a hair off the segment can leave a corner across
+        self.legalize(edges_of(&new));
+        if end != b {
+            self.constrain(end, b);
+        }
+    }
+
+    /// Triangulates the region between the edge `a`–`b` and `chain`, which
+    /// runs from `a` to `b` on the left of it, Delaunay within the region.
+    fn fill_pseudo_polygon(&self, a: usize, b: usize, chain: &[usize], out: &mut Vec<[usize; 3]>) {
+        if chain.is_empty() { return; }
+        let (pa, pb) = (self.points[a], self.points[b]);
+        let mut c = 0;
+        for i in 1..chain.len() {
+            if incircle(pa, pb, self.points[chain[c]], self.points[chain[i]]) > 0.0 {
+                c = i;
+            }
+        }
+        out.push([a, b, chain[c]]);
+        self.fill_pseudo_polygon(a, chain[c], &chain[..c], out);
+        self.fill_pseudo_polygon(chain[c], b, &chain[c + 1..], out);
+    }
+}
+
+/// A step of building a triangulation, so viewers can play it back one at a time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    Insert(Point),
+    /// Inserts both ends and constrains the segment between them.
+    Segment(Point, Point),
+}
+
+impl Triangulation {
+    pub fn apply(&mut self, step: Step) {
+        match step {
+            Step::Insert(p) => { self.insert(p); }
+            Step::Segment(a, b) => { self.insert_segment(a, b); }
+        }
+    }
+}
+
+impl Metrics for Triangulation {
+    fn metrics(&self) -> Vec<(String, String)> {
+        vec![
+            ("triangulation".to_string(), format!("{} vertices, {} triangles", self.len(), self.triangles().len())),
+            ("constrained edges".to_string(), self.constraints.len().to_string()),
+            ("rebuilds".to_string(), self.rebuilds.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hull::convex_hull, utils};
+
+    fn random_points(seed: u64, count: usize) -> Vec<Point> {
+        utils::random_sites(&mut utils::seeded_rng(seed), count, (0.0..1000.0, 0.0..1000.0))
+    }
+
+    fn sorted(mut triangles: Vec<[usize; 3]>) -> Vec<[usize; 3]> {
+        for t in triangles.iter_mut() {
+            t.sort();
+        }
+        triangles.sort();
+        triangles
+    }
+
+    /// Every triangle is counter-clockwise and no vertex it can see, past
+    /// no constraint, is inside its circumcircle.
+    fn assert_constrained_delaunay(triangulation: &Triangulation) {
+        let p = |v: usize| triangulation.point(v).unwrap();
+        let constraints = triangulation.constraints();
+        for [a, b, c] in triangulation.triangles() {
+            assert!(orient2d(p(a), p(b), p(c)) > 0.0, "{a} {b} {c} isn't counter-clockwise");
+            let centroid = (p(a) + p(b) + p(c)) / 3.0;
+            for d in (0..triangulation.points.len()).filter(|&d| triangulation.point(d).is_some()) {
+                if incircle(p(a), p(b), p(c), p(d)) <= 0.0 { continue; }
+                let behind_own_edge = [(a, b), (b, c), (c, a)].iter()
+                    .any(|&(u, w)| triangulation.is_constrained(u, w) && orient2d(p(u), p(w), p(d)) < 0.0);
+                let hidden = behind_own_edge || constraints.iter().any(|&(u, w)| {
+                    ![u, w].contains(&d) && segment_intersection([centroid, p(d)], [p(u), p(w)]).is_some()
+                });
+                assert!(hidden, "{d} is inside the circumcircle of {a} {b} {c}");
+            }
+        }
+    }
+
+    #[test]
+    fn inserts_random_points() {
+        let points = random_points(5, 300);
+        let triangulation = Triangulation::from_points(points.iter().copied());
+        assert_eq!(triangulation.len(), points.len());
+        assert_eq!(triangulation.triangles().len(), 2 * points.len() - 2 - convex_hull(&points).len());
+        assert_constrained_delaunay(&triangulation);
+        let dcel = triangulation.to_dcel();
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.bounded_faces().count(), triangulation.triangles().len());
+
+        // the order of insertion doesn't matter in general position
+        let reversed = Triangulation::from_points(points.iter().rev().copied());
+        let flip = |t: [usize; 3]| t.map(|v| points.len() - 1 - v);
+        assert_eq!(sorted(reversed.triangles().into_iter().map(flip).collect()), sorted(triangulation.triangles()));
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        let mut triangulation = Triangulation::new();
+        for x in 0..4 {
+            assert_eq!(triangulation.insert(Point::new(x as f64, 0.0)), x);
+        }
+        assert_eq!(triangulation.insert(Point::new(2.0, 0.0)), 2);
+        assert!(triangulation.triangles().is_empty());
+        triangulation.insert_constraint(0, 3);
+
+        // a square grid, where every cell's corners are cocircular
+        for y in 1..4 {
+            for x in 0..4 {
+                triangulation.insert(Point::new(x as f64, y as f64));
+            }
+        }
+        assert_eq!(triangulation.len(), 16);
+        assert_eq!(triangulation.triangles().len(), 18);
+        assert_eq!(triangulation.constraints(), vec![(0, 1), (1, 2), (2, 3)]);
+        assert_constrained_delaunay(&triangulation);
+    }
+
+    #[test]
+    fn constraints_are_forced_in() {
+        let points = random_points(11, 200);
+        let mut triangulation = Triangulation::from_points(points.iter().copied());
+        triangulation.insert_constraint(0, 1);
+        triangulation.insert_constraint(2, 3);
+        let (a, b) = triangulation.insert_segment(Point::new(-50.0, 500.0), Point::new(1050.0, 510.0));
+        assert_constrained_delaunay(&triangulation);
+        assert_eq!(triangulation.triangles().len(), 2 * triangulation.len() - 2 - {
+            let live: Vec<Point> = (0..triangulation.points.len()).filter_map(|v| triangulation.point(v)).collect();
+            convex_hull(&live).len()
+        });
+
+        // every constraint is a chain of edges, split where they crossed
+        let edges: HashSet<(usize, usize)> = triangulation.triangles().iter()
+            .flat_map(|&[u, v, w]| [key(u, v), key(v, w), key(w, u)])
+            .collect();
+        for c in triangulation.constraints() {
+            assert!(edges.contains(&c), "constraint {c:?} isn't an edge");
+        }
+        let pa = triangulation.point(a).unwrap();
+        let pb = triangulation.point(b).unwrap();
+        let along: f64 = triangulation.constraints().iter()
+            .map(|&(u, w)| (triangulation.point(u).unwrap(), triangulation.point(w).unwrap()))
+            .filter(|&(u, w)| orient2d(pa, pb, u).abs() < 1e-6 * (pb - pa).magnitude().powi(2)
+                           && orient2d(pa, pb, w).abs() < 1e-6 * (pb - pa).magnitude().powi(2))
+            .map(|(u, w)| (w - u).magnitude())
+            .sum();
+        assert!((along - (pb - pa).magnitude()).abs() < 1e-6, "{along}");
+
+        // played back a step at a time, the same mesh comes out
+        let mut replayed = Triangulation::new();
+        for &p in &points {
+            replayed.apply(Step::Insert(p));
+        }
+        for (u, w) in [(0, 1), (2, 3)] {
+            replayed.apply(Step::Segment(points[u], points[w]));
+        }
+        replayed.apply(Step::Segment(pa, pb));
+        assert_eq!(sorted(replayed.triangles()), sorted(triangulation.triangles()));
+        assert_eq!(replayed.constraints(), triangulation.constraints());
+    }
+
+    #[test]
+    fn removing_a_splitting_vertex_rejoins_the_constraint() {
+        let mut triangulation = Triangulation::from_points(random_points(13, 50));
+        let (a, b) = triangulation.insert_segment(Point::new(100.0, 100.0), Point::new(900.0, 900.0));
+        let v = triangulation.insert(Point::new(500.0, 500.0));
+        assert!(triangulation.is_constrained(a, v) && triangulation.is_constrained(v, b));
+        assert!(triangulation.remove(v));
+        assert!(triangulation.is_constrained(a, b));
+        assert!(!triangulation.is_constrained(a, v));
+        assert_constrained_delaunay(&triangulation);
+    }
+
+    #[test]
+    fn removals_match_a_fresh_triangulation() {
+        let points = random_points(3, 150);
+        let mut triangulation = Triangulation::from_points(points.iter().copied());
+        let hull = convex_hull(&points);
+        let inner: Vec<usize> = (0..points.len()).step_by(4).filter(|v| !hull.contains(v)).collect();
+        for &v in &inner {
+            assert!(triangulation.remove(v));
+        }
+        assert_eq!(triangulation.rebuilds(), 0);
+        assert!(!triangulation.remove(inner[0]));
+        assert!(triangulation.remove(hull[0]) && triangulation.remove(hull[2]));
+        assert_eq!(triangulation.rebuilds(), 2);
+        assert_constrained_delaunay(&triangulation);
+
+        let removed = [inner, vec![hull[0], hull[2]]].concat();
+
+        let kept: Vec<usize> = (0..points.len()).filter(|v| !removed.contains(v)).collect();
+        let fresh = Triangulation::from_points(kept.iter().map(|&v| points[v]));
+        let renumbered: Vec<[usize; 3]> = fresh.triangles().into_iter().map(|t| t.map(|i| kept[i])).collect();
+        assert_eq!(sorted(triangulation.triangles()), sorted(renumbered));
+
+        let dcel = triangulation.to_dcel();
+        dcel.check_invariants().unwrap();
+        assert_eq!(dcel.vertex_count(), kept.len());
+        assert_eq!(dcel.bounded_faces().count(), triangulation.triangles().len());
+    }
+}
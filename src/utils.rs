@@ -1,38 +1,77 @@
 use macroquad::prelude::*;
 use euclid::default::Vector2D;
+use ::rand::{rngs::StdRng, Rng, SeedableRng};
 use std::ops::Range;
+use crate::point::Point;
 
-pub fn random_color() -> macroquad::color::Color {
+/// The generator behind everything the viewers make up, so that a run can be
+/// repeated from its seed.
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// A seed for runs that weren't given one.
+pub fn fresh_seed() -> u64 {
+    ::rand::random()
+}
+
+pub fn random_color(rng: &mut impl Rng) -> macroquad::color::Color {
     use random_color::RandomColor;
     use macroquad::color::Color;
     
     Color::from_vec(glam::Vec4::from_array(
-        RandomColor::new().to_f32_rgba_array()))
+        RandomColor::new().seed(rng.random::<u64>()).to_f32_rgba_array()))
 }
 
-pub fn random_points(count: usize, bounds: (Range<f32>,Range<f32>)) -> Vec<Vector2D<f32>> {
+pub fn random_points(rng: &mut impl Rng, count: usize, bounds: (Range<f32>,Range<f32>)) -> Vec<Vector2D<f32>> {
     let mut acc = Vec::with_capacity(count);
     for _ in 0..count {
         acc.push(Vector2D::<f32>::new(
-            rand::gen_range(bounds.0.start, bounds.0.end),
-            rand::gen_range(bounds.1.start, bounds.1.end),
+            rng.random_range(bounds.0.clone()),
+            rng.random_range(bounds.1.clone()),
         ));
     }
     acc
 }
 
+/// [`random_points`] as sites for the geometry, in double precision.
+pub fn random_sites(rng: &mut impl Rng, count: usize, bounds: (Range<f32>,Range<f32>)) -> Vec<Point> {
+    random_points(rng, count, bounds).into_iter().map(Point::from).collect()
+}
+
+/// Grid resolution per axis used by [`quantize_points`].
+pub const QUANTIZATION_STEPS: i64 = 1 << 24;
+
+/// Snaps points inside `bounds` onto an integer grid of [`QUANTIZATION_STEPS`]
+/// per axis, rounding each coordinate to the nearest step. Cross products of
+/// the resulting coordinates stay exact in `i64`.
 pub fn quantize_points(
-    float_points: &Vec<Vector2D<f32>>,
+    float_points: &[Vector2D<f32>],
     bounds: (Range<f32>,Range<f32>))
-    -> Vec<Vector2D<u64>> {
-    float_points.into_iter()
-        .map(|p| {
-            let x_norm = (p.x - bounds.0.start)/(bounds.0.end - bounds.0.start);
-            let x = (x_norm*(std::u64::MAX as f32)) as u64;
-            let y_norm = (p.y - bounds.1.start)/(bounds.1.end - bounds.1.start);
-            let y = (y_norm*(std::u64::MAX as f32)) as u64;
-            Vector2D::<u64>::new(x,y)
-        })
+    -> Vec<Point<i64>> {
+    let quantize = |v: f32, range: &Range<f32>| {
+        let (v, start, end) = (v as f64, range.start as f64, range.end as f64);
+        let norm = (v - start)/(end - start);
+        (norm*(QUANTIZATION_STEPS as f64)).round() as i64
+    };
+    float_points.iter()
+        .map(|p| Point::new(quantize(p.x, &bounds.0), quantize(p.y, &bounds.1)))
         .collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_repeat_runs() {
+        let run = |seed| {
+            let mut rng = seeded_rng(seed);
+            (random_points(&mut rng, 5, (0.0..10.0, -5.0..5.0)), random_color(&mut rng), Point::rand(&mut rng))
+        };
+        let (points, clr, p) = run(7);
+        assert_eq!(run(7), (points.clone(), clr, p));
+        assert_ne!(run(8).0, points);
+        assert!(points.iter().all(|v| (0.0..10.0).contains(&v.x) && (-5.0..5.0).contains(&v.y)));
+    }
+}